@@ -2,7 +2,7 @@ use anyhow::Result;
 use clap::{Parser, Subcommand};
 use sp1_cli::commands::{
     build::BuildCmd, build_toolchain::BuildToolchainCmd, install_toolchain::InstallToolchainCmd,
-    new::NewCmd, prove::ProveCmd,
+    new::NewCmd, prove::ProveCmd, worker::WorkerCmd,
 };
 
 const VERSION_MESSAGE: &str = concat!(
@@ -37,6 +37,7 @@ pub enum ProveCliCommands {
     Prove(ProveCmd),
     BuildToolchain(BuildToolchainCmd),
     InstallToolchain(InstallToolchainCmd),
+    Worker(WorkerCmd),
 }
 
 fn main() -> Result<()> {
@@ -48,5 +49,6 @@ fn main() -> Result<()> {
         ProveCliCommands::Prove(cmd) => cmd.run(),
         ProveCliCommands::BuildToolchain(cmd) => cmd.run(),
         ProveCliCommands::InstallToolchain(cmd) => cmd.run(),
+        ProveCliCommands::Worker(cmd) => cmd.run(),
     }
 }
@@ -3,3 +3,4 @@ pub mod build_toolchain;
 pub mod install_toolchain;
 pub mod new;
 pub mod prove;
+pub mod worker;
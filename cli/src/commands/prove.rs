@@ -2,7 +2,8 @@ use anstyle::*;
 use anyhow::Result;
 use clap::Parser;
 use sp1_core::{
-    utils::{self},
+    runtime::{Program, Runtime},
+    utils::{self, ProverOptions},
     SP1Prover, SP1Stdin,
 };
 use std::time::Instant;
@@ -70,6 +71,14 @@ pub struct ProveCmd {
     #[clap(long, action)]
     verbose: bool,
 
+    /// Instead of proving, run the program under a GDB server listening on this local port.
+    #[clap(long)]
+    gdb: Option<u16>,
+
+    /// Have workers running `cargo prove worker` on this directory prove the shards.
+    #[clap(long)]
+    work_dir: Option<PathBuf>,
+
     #[clap(flatten)]
     build_args: BuildArgs,
 }
@@ -112,8 +121,23 @@ impl ProveCmd {
                 }
             }
         }
+
+        if let Some(port) = self.gdb {
+            let mut runtime = Runtime::new(Program::from(&elf));
+            runtime.write_stdin_slice(&stdin.buffer.data);
+            runtime.write_public_stdin_slice(&stdin.public_buffer.data);
+            runtime.debug(("127.0.0.1", port))?;
+            return Ok(());
+        }
+
         let start_time = Instant::now();
-        let proof = SP1Prover::prove(&elf, stdin).unwrap();
+        let proof = match self.work_dir {
+            Some(ref work_dir) => {
+                let options = ProverOptions::default().with_work_dir(work_dir);
+                SP1Prover::prove_distributed(&elf, stdin, options)?
+            }
+            None => SP1Prover::prove(&elf, stdin).unwrap(),
+        };
 
         if let Some(ref path) = self.output {
             proof
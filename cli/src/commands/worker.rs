@@ -0,0 +1,31 @@
+use anyhow::Result;
+use clap::Parser;
+use sp1_core::{
+    utils::{self, ProverOptions},
+    SP1Prover,
+};
+use std::{env, path::PathBuf};
+
+#[derive(Parser)]
+#[command(
+    name = "worker",
+    about = "Prove the shards written to a work directory by `cargo prove --work-dir`"
+)]
+pub struct WorkerCmd {
+    /// The directory shared with the coordinator.
+    #[clap(long)]
+    work_dir: PathBuf,
+}
+
+impl WorkerCmd {
+    pub fn run(&self) -> Result<()> {
+        if env::var("RUST_LOG").is_err() {
+            env::set_var("RUST_LOG", "info");
+        }
+        utils::setup_logger();
+
+        // The worker runs until it is killed, since the token is never cancelled.
+        SP1Prover::run_worker(&self.work_dir, ProverOptions::default())?;
+        Ok(())
+    }
+}
@@ -498,8 +498,36 @@ impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> MemoryAirBuilder
 impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> ProgramAirBuilder for AB {}
 impl<AB: AirBuilder + MessageBuilder<AirInteraction<AB::Expr>>> SP1AirBuilder for AB {}
 
+/// A builder that exposes the public values of the shard being proven.
+pub trait PublicValuesAirBuilder: AirBuilder {
+    /// Returns the public values, one field element per byte.
+    fn public_values(&self) -> &[Self::F];
+}
+
 impl<'a, SC: StarkGenericConfig> EmptyMessageBuilder for ProverConstraintFolder<'a, SC> {}
 impl<'a, Challenge: Field> EmptyMessageBuilder for VerifierConstraintFolder<'a, Challenge> {}
 impl<F: Field> EmptyMessageBuilder for SymbolicAirBuilder<F> {}
 
 impl<'a, F: Field> EmptyMessageBuilder for p3_uni_stark::DebugConstraintBuilder<'a, F> {}
+
+// The uni-stark builders are only used to test chips in isolation, so they carry no public values.
+impl<'a, SC: StarkGenericConfig> PublicValuesAirBuilder for ProverConstraintFolder<'a, SC> {
+    fn public_values(&self) -> &[Self::F] {
+        &[]
+    }
+}
+impl<'a, Challenge: Field> PublicValuesAirBuilder for VerifierConstraintFolder<'a, Challenge> {
+    fn public_values(&self) -> &[Self::F] {
+        &[]
+    }
+}
+impl<F: Field> PublicValuesAirBuilder for SymbolicAirBuilder<F> {
+    fn public_values(&self) -> &[Self::F] {
+        &[]
+    }
+}
+impl<'a, F: Field> PublicValuesAirBuilder for p3_uni_stark::DebugConstraintBuilder<'a, F> {
+    fn public_values(&self) -> &[Self::F] {
+        &[]
+    }
+}
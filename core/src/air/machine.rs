@@ -6,6 +6,21 @@ use crate::runtime::{ExecutionRecord, Program};
 
 pub use sp1_derive::MachineAir;
 
+/// The names of the chips proving ALU operations.
+pub const ALU_CHIPS: [&str; 8] = [
+    "Add",
+    "Sub",
+    "Bitwise",
+    "Mul",
+    "DivRem",
+    "Lt",
+    "ShiftLeft",
+    "ShiftRight",
+];
+
+/// The names of the lookup tables for byte operations and field comparisons.
+pub const LOOKUP_CHIPS: [&str; 2] = ["Byte", "FieldLTU"];
+
 /// An AIR that is part of a Risc-V AIR arithmetization.
 pub trait MachineAir<F: Field>: BaseAir<F> {
     /// A unique identifier for this AIR as part of a machine.
@@ -27,6 +42,12 @@ pub trait MachineAir<F: Field>: BaseAir<F> {
         self.generate_trace(input, output);
     }
 
+    /// The names of the chips this AIR emits events to in `generate_dependencies`, whose
+    /// dependencies must therefore be generated after this AIR's.
+    fn emits_events_to(&self) -> Vec<String> {
+        Vec::new()
+    }
+
     /// The number of preprocessed columns in the trace.
     fn preprocessed_width(&self) -> usize {
         0
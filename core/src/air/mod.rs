@@ -2,6 +2,7 @@ mod builder;
 mod interaction;
 mod machine;
 mod polynomial;
+mod public_values;
 mod sub_builder;
 mod word;
 
@@ -9,5 +10,6 @@ pub use builder::*;
 pub use interaction::*;
 pub use machine::*;
 pub use polynomial::*;
+pub use public_values::*;
 pub use sub_builder::*;
 pub use word::*;
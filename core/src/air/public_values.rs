@@ -0,0 +1,135 @@
+use p3_field::{AbstractField, PrimeField32};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::Word;
+use super::WORD_SIZE;
+
+/// The number of words in the digest of the public values.
+pub const PV_DIGEST_NUM_WORDS: usize = 8;
+
+/// The number of words committed by the program through the `COMMIT` syscall.
+///
+/// The first `PV_DIGEST_NUM_WORDS` words are the digest of the public outputs and the next
+/// `PV_DIGEST_NUM_WORDS` words are the digest of the public inputs.
+pub const PV_NUM_COMMITTED_WORDS: usize = 2 * PV_DIGEST_NUM_WORDS;
+
+/// The number of field elements used to represent the public values of a shard.
+pub const PV_NUM_ELEMENTS: usize = (PV_NUM_COMMITTED_WORDS + 2) * WORD_SIZE;
+
+/// The public values of a shard, which are exposed to the verifier and observed by the challenger.
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq, Eq)]
+pub struct PublicValues<T> {
+    /// The SHA-256 digest of the bytes the program committed to through `FD_PUBLIC_VALUES`.
+    pub committed_value_digest: [T; PV_DIGEST_NUM_WORDS],
+
+    /// The SHA-256 digest of the bytes the program read through `FD_PUBLIC_INPUT`.
+    pub committed_input_digest: [T; PV_DIGEST_NUM_WORDS],
+
+    /// The exit code the program passed to the `HALT` syscall.
+    pub exit_code: T,
+
+    /// Whether the last instruction of the shard is the `HALT` syscall, which is the case for
+    /// exactly one shard of a finished execution.
+    pub halted: T,
+}
+
+impl<T> PublicValues<T> {
+    /// The words committed by the program, in the order of their `COMMIT` index.
+    pub fn committed_words(&self) -> impl Iterator<Item = &T> {
+        self.committed_value_digest
+            .iter()
+            .chain(self.committed_input_digest.iter())
+    }
+
+    /// Mutable access to the words committed by the program, in the order of their `COMMIT` index.
+    pub fn committed_words_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.committed_value_digest
+            .iter_mut()
+            .chain(self.committed_input_digest.iter_mut())
+    }
+}
+
+/// Computes the SHA-256 digest of `bytes` as little-endian words.
+fn digest_words(bytes: &[u8]) -> [u32; PV_DIGEST_NUM_WORDS] {
+    let digest: [u8; 32] = Sha256::digest(bytes).into();
+    let mut words = [0u32; PV_DIGEST_NUM_WORDS];
+    for (word, bytes) in words.iter_mut().zip(digest.chunks_exact(WORD_SIZE)) {
+        *word = u32::from_le_bytes(bytes.try_into().unwrap());
+    }
+    words
+}
+
+impl PublicValues<u32> {
+    /// Computes the public values of a program which read `input` as its public input, committed
+    /// `output` as its public values and halted with `exit_code`.
+    pub fn from_io(input: &[u8], output: &[u8], exit_code: u32) -> Self {
+        Self {
+            committed_value_digest: digest_words(output),
+            committed_input_digest: digest_words(input),
+            exit_code,
+            halted: 1,
+        }
+    }
+
+    /// Reads the public values from their field element representation, or returns `None` if
+    /// `values` has the wrong length or holds an element which is not a byte.
+    pub fn from_vec<F: PrimeField32>(values: &[F]) -> Option<Self> {
+        if values.len() != PV_NUM_ELEMENTS {
+            return None;
+        }
+        let mut words = values.chunks_exact(WORD_SIZE).map(|chunk| {
+            let mut bytes = [0u8; WORD_SIZE];
+            for (byte, value) in bytes.iter_mut().zip(chunk) {
+                *byte = u8::try_from(value.as_canonical_u32()).ok()?;
+            }
+            Some(u32::from_le_bytes(bytes))
+        });
+        let mut public_values = Self::default();
+        for word in public_values.committed_words_mut() {
+            *word = words.next()??;
+        }
+        public_values.exit_code = words.next()??;
+        public_values.halted = words.next()??;
+        Some(public_values)
+    }
+
+    /// The public values with `halted` cleared, which are the same for every shard of a proof.
+    pub fn without_halted(&self) -> Self {
+        Self { halted: 0, ..*self }
+    }
+
+    /// Converts the public values into field elements, with one element per byte.
+    pub fn to_vec<F: AbstractField>(&self) -> Vec<F> {
+        self.committed_words()
+            .chain([&self.exit_code, &self.halted])
+            .flat_map(|word| word.to_le_bytes())
+            .map(F::from_canonical_u8)
+            .collect()
+    }
+}
+
+impl<F: AbstractField + Copy> PublicValues<Word<F>> {
+    /// Reads the public values from their field element representation.
+    ///
+    /// Builders which do not carry public values provide an empty slice, which is read as zeros.
+    pub fn from_slice(values: &[F]) -> Self {
+        let mut public_values = Self {
+            committed_value_digest: [Word([F::zero(); WORD_SIZE]); PV_DIGEST_NUM_WORDS],
+            committed_input_digest: [Word([F::zero(); WORD_SIZE]); PV_DIGEST_NUM_WORDS],
+            exit_code: Word([F::zero(); WORD_SIZE]),
+            halted: Word([F::zero(); WORD_SIZE]),
+        };
+        if values.is_empty() {
+            return public_values;
+        }
+        assert_eq!(values.len(), PV_NUM_ELEMENTS);
+        let mut chunks = values.chunks_exact(WORD_SIZE);
+        for (word, chunk) in public_values.committed_words_mut().zip(&mut chunks) {
+            *word = Word(chunk.try_into().unwrap());
+        }
+        public_values.exit_code = Word(chunks.next().unwrap().try_into().unwrap());
+        public_values.halted = Word(chunks.next().unwrap().try_into().unwrap());
+        public_values
+    }
+}
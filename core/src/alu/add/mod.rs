@@ -10,6 +10,7 @@ use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
 use crate::air::MachineAir;
+use crate::air::LOOKUP_CHIPS;
 use crate::air::{SP1AirBuilder, Word};
 use crate::operations::AddOperation;
 use crate::runtime::{ExecutionRecord, Opcode};
@@ -44,6 +45,10 @@ impl<F: PrimeField> MachineAir<F> for AddChip {
         "Add".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     #[instrument(name = "generate add trace", skip_all)]
     fn generate_trace(
         &self,
@@ -8,6 +8,7 @@ use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
 use crate::air::MachineAir;
+use crate::air::LOOKUP_CHIPS;
 use crate::air::{SP1AirBuilder, Word};
 use crate::bytes::{ByteLookupEvent, ByteOpcode};
 use crate::runtime::{ExecutionRecord, Opcode};
@@ -47,6 +48,10 @@ impl<F: PrimeField> MachineAir<F> for BitwiseChip {
         "Bitwise".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     #[instrument(name = "generate bitwise trace", skip_all)]
     fn generate_trace(
         &self,
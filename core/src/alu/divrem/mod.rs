@@ -74,6 +74,7 @@ use tracing::instrument;
 
 use self::utils::eval_abs_value;
 use crate::air::MachineAir;
+use crate::air::LOOKUP_CHIPS;
 use crate::air::{SP1AirBuilder, Word};
 use crate::alu::divrem::utils::{get_msb, get_quotient_and_remainder, is_signed_operation};
 use crate::alu::AluEvent;
@@ -186,6 +187,15 @@ impl<F: PrimeField> MachineAir<F> for DivRemChip {
         "DivRem".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        // The quotient and remainder are checked with the MUL and LT chips.
+        ["Mul", "Lt"]
+            .iter()
+            .chain(LOOKUP_CHIPS.iter())
+            .map(|name| name.to_string())
+            .collect()
+    }
+
     #[instrument(name = "generate divrem trace", skip_all)]
     fn generate_trace(
         &self,
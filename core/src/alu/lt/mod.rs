@@ -1,4 +1,5 @@
 use crate::air::MachineAir;
+use crate::air::LOOKUP_CHIPS;
 use core::borrow::{Borrow, BorrowMut};
 use core::mem::size_of;
 use p3_air::{Air, AirBuilder, BaseAir};
@@ -78,6 +79,10 @@ impl<F: PrimeField> MachineAir<F> for LtChip {
         "Lt".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     fn generate_dependencies(&self, _input: &ExecutionRecord, _output: &mut ExecutionRecord) {}
 
     #[instrument(name = "generate lt trace", skip_all)]
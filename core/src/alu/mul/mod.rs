@@ -38,6 +38,7 @@ use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
 use crate::air::MachineAir;
+use crate::air::LOOKUP_CHIPS;
 use crate::air::{SP1AirBuilder, Word};
 use crate::alu::mul::utils::get_msb;
 use crate::bytes::{ByteLookupEvent, ByteOpcode};
@@ -114,6 +115,10 @@ impl<F: PrimeField> MachineAir<F> for MulChip {
         "Mul".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     #[instrument(name = "generate mul trace", skip_all)]
     fn generate_trace(
         &self,
@@ -41,6 +41,7 @@ use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
 use crate::air::MachineAir;
+use crate::air::LOOKUP_CHIPS;
 use crate::air::{SP1AirBuilder, Word};
 use crate::disassembler::WORD_SIZE;
 use crate::runtime::{ExecutionRecord, Opcode};
@@ -95,6 +96,10 @@ impl<F: PrimeField> MachineAir<F> for ShiftLeft {
         "ShiftLeft".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     #[instrument(name = "generate sll trace", skip_all)]
     fn generate_trace(
         &self,
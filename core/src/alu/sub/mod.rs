@@ -10,6 +10,7 @@ use sp1_derive::AlignedBorrow;
 use tracing::instrument;
 
 use crate::air::MachineAir;
+use crate::air::LOOKUP_CHIPS;
 use crate::air::{SP1AirBuilder, Word};
 use crate::runtime::{ExecutionRecord, Opcode};
 use crate::utils::pad_to_power_of_two;
@@ -45,6 +46,10 @@ impl<F: PrimeField> MachineAir<F> for SubChip {
         "Sub".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     #[instrument(name = "generate sub trace", skip_all)]
     fn generate_trace(
         &self,
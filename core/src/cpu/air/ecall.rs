@@ -0,0 +1,106 @@
+use p3_air::AirBuilder;
+use p3_field::AbstractField;
+
+use crate::air::{PublicValues, SP1AirBuilder, Word, WordAirBuilder};
+use crate::cpu::columns::CpuCols;
+use crate::cpu::CpuChip;
+use crate::operations::IsZeroOperation;
+use crate::runtime::SyscallCode;
+
+impl CpuChip {
+    /// Constraints related to the ECALL opcode.
+    ///
+    /// For the `COMMIT` syscall, `a0` holds the index of the committed word and `a1` (read into
+    /// `op_c`) holds its value, which must match the corresponding word of the public values. For
+    /// the `HALT` syscall, `a0` holds the exit code, which must match the public exit code.
+    ///
+    /// The public `halted` flag is set if and only if the last real row of the shard is a `HALT`,
+    /// so that the exit code and the committed words of a proof which claims to have halted are
+    /// constrained by the rows which set them.
+    pub(crate) fn ecall_eval<AB: SP1AirBuilder>(
+        &self,
+        builder: &mut AB,
+        local: &CpuCols<AB::Var>,
+        next: &CpuCols<AB::Var>,
+        public_values: &PublicValues<Word<AB::F>>,
+    ) {
+        let ecall_cols = local.opcode_specific_columns.ecall();
+        let is_ecall: AB::Expr = local.selectors.is_ecall.into();
+
+        // Check whether the syscall id in `op_b` is `COMMIT`.
+        IsZeroOperation::<AB::F>::eval(
+            builder,
+            local.op_b_val().reduce::<AB>()
+                - AB::Expr::from_canonical_u32(SyscallCode::COMMIT as u32),
+            ecall_cols.is_commit,
+            is_ecall.clone(),
+        );
+        let is_commit = ecall_cols.is_commit.result;
+
+        // The index bitmap is one-hot when committing and zero otherwise.
+        let mut bitmap_sum = AB::Expr::zero();
+        let mut bitmap_index = AB::Expr::zero();
+        for (i, bit) in ecall_cols.index_bitmap.iter().enumerate() {
+            builder.when(is_ecall.clone()).assert_bool(*bit);
+            bitmap_sum += (*bit).into();
+            bitmap_index += AB::Expr::from_canonical_usize(i) * *bit;
+        }
+        builder
+            .when(is_ecall.clone())
+            .assert_eq(bitmap_sum, is_commit);
+
+        // The bitmap must encode the word index held in `a0`.
+        builder
+            .when(is_ecall.clone())
+            .when(is_commit)
+            .assert_eq(bitmap_index, local.op_a_access.prev_value.reduce::<AB>());
+
+        // The committed word must match the public values.
+        for (bit, committed_word) in ecall_cols
+            .index_bitmap
+            .iter()
+            .zip(public_values.committed_words())
+        {
+            let committed_word = committed_word.map(AB::Expr::from);
+            let op_c_val = local.op_c_val().map(Into::into);
+            builder
+                .when(is_ecall.clone())
+                .when(*bit)
+                .assert_word_eq(op_c_val, committed_word);
+        }
+
+        // Check whether the syscall id in `op_b` is `HALT`.
+        IsZeroOperation::<AB::F>::eval(
+            builder,
+            local.op_b_val().reduce::<AB>()
+                - AB::Expr::from_canonical_u32(SyscallCode::HALT as u32),
+            ecall_cols.is_halt,
+            is_ecall.clone(),
+        );
+
+        builder.assert_eq(local.is_halt, is_ecall * ecall_cols.is_halt.result);
+
+        // The exit code held in `a0` must match the public exit code.
+        let exit_code = public_values.exit_code.map(AB::Expr::from);
+        let prev_a0 = local.op_a_access.prev_value.map(Into::into);
+        builder
+            .when(local.is_halt)
+            .assert_word_eq(prev_a0, exit_code);
+
+        // The last real row, which is either followed by a padding row or is the last row of the
+        // trace, halts if and only if the shard has halted. A trace with no real rows has not.
+        let halted: AB::Expr = public_values.halted[0].into();
+        builder
+            .when(local.is_real)
+            .when_not(next.is_real)
+            .assert_eq(halted.clone(), local.is_halt);
+        builder
+            .when_last_row()
+            .when(local.is_real)
+            .assert_eq(halted.clone(), local.is_halt);
+        builder
+            .when_first_row()
+            .when_not(local.is_real)
+            .assert_zero(halted);
+    }
+}
@@ -1,4 +1,5 @@
 pub mod branch;
+pub mod ecall;
 pub mod memory;
 
 use core::borrow::Borrow;
@@ -8,7 +9,7 @@ use p3_air::BaseAir;
 use p3_field::AbstractField;
 use p3_matrix::MatrixRowSlices;
 
-use crate::air::{SP1AirBuilder, WordAirBuilder};
+use crate::air::{PublicValues, PublicValuesAirBuilder, SP1AirBuilder, Word, WordAirBuilder};
 use crate::cpu::columns::OpcodeSelectorCols;
 use crate::cpu::columns::{CpuCols, NUM_CPU_COLS};
 use crate::cpu::CpuChip;
@@ -17,7 +18,7 @@ use crate::runtime::{AccessPosition, Opcode};
 
 impl<AB> Air<AB> for CpuChip
 where
-    AB: SP1AirBuilder,
+    AB: SP1AirBuilder + PublicValuesAirBuilder,
 {
     #[inline(never)]
     fn eval(&self, builder: &mut AB) {
@@ -39,6 +40,9 @@ where
         // Program constraints.
         builder.send_program(local.pc, local.instruction, local.selectors, local.is_real);
 
+        // `EBREAK` only traps to a debugger, so an execution which contains it cannot be proven.
+        builder.assert_zero(local.selectors.is_ebreak);
+
         // Load immediates into b and c, if the immediate flags are on.
         builder
             .when(local.selectors.imm_b)
@@ -139,6 +143,9 @@ where
         );
 
         // ECALL instructions.
+        let public_values = PublicValues::<Word<AB::F>>::from_slice(builder.public_values());
+        self.ecall_eval(builder, local, next, &public_values);
+
         // TODO:  Need to handle HALT ecall
         // For all non branch or jump instructions, verify that next.pc == pc + 4
         // builder
@@ -148,6 +155,12 @@ where
         // Range checks.
         builder.assert_bool(local.is_real);
 
+        // The padding rows follow all the real rows.
+        builder
+            .when_transition()
+            .when_not(local.is_real)
+            .assert_zero(next.is_real);
+
         // Dummy constraint of degree 3.
         builder.assert_eq(
             local.pc * local.pc * local.pc,
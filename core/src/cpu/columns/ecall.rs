@@ -0,0 +1,20 @@
+use sp1_derive::AlignedBorrow;
+use std::mem::size_of;
+
+use crate::air::PV_NUM_COMMITTED_WORDS;
+use crate::operations::IsZeroOperation;
+
+pub const NUM_ECALL_COLS: usize = size_of::<EcallCols<u8>>();
+
+#[derive(AlignedBorrow, Default, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct EcallCols<T> {
+    /// Whether the syscall id in `op_b` is `COMMIT`.
+    pub is_commit: IsZeroOperation<T>,
+
+    /// A one-hot encoding of the index of the word being committed.
+    pub index_bitmap: [T; PV_NUM_COMMITTED_WORDS],
+
+    /// Whether the syscall id in `op_b` is `HALT`.
+    pub is_halt: IsZeroOperation<T>,
+}
@@ -1,5 +1,6 @@
 mod auipc;
 mod branch;
+mod ecall;
 mod instruction;
 mod jump;
 mod memory;
@@ -8,6 +9,7 @@ mod opcode_specific;
 
 pub use auipc::*;
 pub use branch::*;
+pub use ecall::*;
 pub use instruction::*;
 pub use jump::*;
 pub use memory::*;
@@ -56,6 +58,9 @@ pub struct CpuCols<T: Copy> {
     /// Selector to label whether this row is a non padded row.
     pub is_real: T,
 
+    /// Whether the instruction is an ECALL of the `HALT` syscall.
+    pub is_halt: T,
+
     /// The branching column is equal to:
     ///
     /// > is_beq & a_eq_b ||
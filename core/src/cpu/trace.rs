@@ -1,6 +1,7 @@
 use super::columns::{CPU_COL_MAP, NUM_CPU_COLS};
 use super::{CpuChip, CpuEvent};
 use crate::air::MachineAir;
+use crate::air::{ALU_CHIPS, LOOKUP_CHIPS};
 use crate::alu::{self, AluEvent};
 use crate::bytes::{ByteLookupEvent, ByteOpcode};
 use crate::cpu::columns::CpuCols;
@@ -8,7 +9,7 @@ use crate::cpu::memory::MemoryRecordEnum;
 use crate::disassembler::WORD_SIZE;
 use crate::field::event::FieldEvent;
 use crate::memory::MemoryCols;
-use crate::runtime::{ExecutionRecord, Opcode};
+use crate::runtime::{ExecutionRecord, Opcode, SyscallCode};
 use hashbrown::HashMap;
 use p3_field::PrimeField;
 use p3_matrix::dense::RowMajorMatrix;
@@ -23,6 +24,14 @@ impl<F: PrimeField> MachineAir<F> for CpuChip {
         "CPU".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        ALU_CHIPS
+            .iter()
+            .chain(LOOKUP_CHIPS.iter())
+            .map(|name| name.to_string())
+            .collect()
+    }
+
     #[instrument(name = "generate CPU trace", skip_all)]
     fn generate_trace(
         &self,
@@ -152,11 +161,12 @@ impl CpuChip {
                 .populate(record, &mut new_field_events)
         }
 
-        // Populate memory, branch, jump, and auipc specific fields.
+        // Populate memory, branch, jump, auipc, and ecall specific fields.
         self.populate_memory(cols, event, &mut new_alu_events, &mut new_blu_events);
         self.populate_branch(cols, event, &mut new_alu_events);
         self.populate_jump(cols, event, &mut new_alu_events);
         self.populate_auipc(cols, event, &mut new_alu_events);
+        self.populate_ecall(cols, event);
 
         // Assert that the instruction is not a no-op.
         cols.is_real = F::one();
@@ -459,14 +469,51 @@ impl CpuChip {
         }
     }
 
+    /// Populates columns related to ECALL.
+    fn populate_ecall<F: PrimeField>(&self, cols: &mut CpuCols<F>, event: CpuEvent) {
+        if event.instruction.opcode != Opcode::ECALL {
+            return;
+        }
+        let ecall_columns = cols.opcode_specific_columns.ecall_mut();
+
+        // Check whether the syscall is `COMMIT`.
+        let commit = SyscallCode::COMMIT as u32;
+        ecall_columns.is_commit.populate_from_field_element(
+            F::from_canonical_u32(event.b) - F::from_canonical_u32(commit),
+        );
+
+        // Set the bitmap of the committed word, whose index is the value of `a0` before the call.
+        if event.b == commit {
+            if let Some(MemoryRecordEnum::Write(record)) = event.a_record {
+                ecall_columns.index_bitmap[record.prev_value as usize] = F::one();
+            }
+        }
+
+        // Check whether the syscall is `HALT`.
+        ecall_columns.is_halt.populate_from_field_element(
+            F::from_canonical_u32(event.b) - F::from_canonical_u32(SyscallCode::HALT as u32),
+        );
+        cols.is_halt = F::from_bool(event.b == SyscallCode::HALT as u32);
+    }
+
     fn pad_to_power_of_two<F: PrimeField>(values: &mut Vec<F>) {
         let len: usize = values.len();
         let n_real_rows = values.len() / NUM_CPU_COLS;
-        let last_row = &values[len - NUM_CPU_COLS..];
-        let pc = last_row[CPU_COL_MAP.pc];
-        let clk = last_row[CPU_COL_MAP.clk];
 
-        values.resize(n_real_rows.next_power_of_two() * NUM_CPU_COLS, F::zero());
+        // A shard can have no CPU events if it only holds the events of other chips which did not
+        // fit in the previous shards, in which case it is all padding.
+        let (pc, clk, n_rows) = if n_real_rows == 0 {
+            (F::zero(), F::zero(), 8)
+        } else {
+            let last_row = &values[len - NUM_CPU_COLS..];
+            (
+                last_row[CPU_COL_MAP.pc],
+                last_row[CPU_COL_MAP.clk],
+                n_real_rows.next_power_of_two(),
+            )
+        };
+
+        values.resize(n_rows * NUM_CPU_COLS, F::zero());
 
         // Interpret values as a slice of arrays of length `NUM_CPU_COLS`
         let rows = unsafe {
@@ -536,7 +583,7 @@ mod tests {
     fn generate_trace_simple_program() {
         let program = simple_program();
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         let chip = CpuChip::default();
         let trace: RowMajorMatrix<BabyBear> =
             chip.generate_trace(&runtime.record, &mut ExecutionRecord::default());
@@ -553,7 +600,7 @@ mod tests {
 
         let program = simple_program();
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         let chip = CpuChip::default();
         let trace: RowMajorMatrix<BabyBear> =
             chip.generate_trace(&runtime.record, &mut ExecutionRecord::default());
@@ -564,4 +611,20 @@ mod tests {
         let mut challenger = config.challenger();
         verify(&config, &chip, &mut challenger, &proof).unwrap();
     }
+
+    #[test]
+    fn prove_trace_without_events() {
+        let config = BabyBearPoseidon2::new();
+        let mut challenger = config.challenger();
+
+        let chip = CpuChip::default();
+        let trace: RowMajorMatrix<BabyBear> =
+            chip.generate_trace(&ExecutionRecord::default(), &mut ExecutionRecord::default());
+        assert_eq!(trace.height(), 8);
+
+        let proof = prove::<BabyBearPoseidon2, _>(&config, &chip, &mut challenger, trace);
+
+        let mut challenger = config.challenger();
+        verify(&config, &chip, &mut challenger, &proof).unwrap();
+    }
 }
@@ -1,4 +1,4 @@
-use elf::abi::{EM_RISCV, ET_EXEC, PF_X, PT_LOAD};
+use elf::abi::{EM_RISCV, ET_EXEC, PF_X, PT_LOAD, STT_FUNC};
 use elf::endian::LittleEndian;
 use elf::file::Class;
 use elf::ElfBytes;
@@ -25,6 +25,9 @@ pub struct Elf {
 
     /// The initial memory image, useful for global constants.
     pub memory_image: BTreeMap<u32, u32>,
+
+    /// The demangled names of the functions of the program, keyed by their start address.
+    pub symbols: BTreeMap<u32, String>,
 }
 
 impl Elf {
@@ -34,12 +37,14 @@ impl Elf {
         pc_start: u32,
         pc_base: u32,
         memory_image: BTreeMap<u32, u32>,
+        symbols: BTreeMap<u32, String>,
     ) -> Self {
         Self {
             instructions,
             pc_start,
             pc_base,
             memory_image,
+            symbols,
         }
     }
 
@@ -150,6 +155,23 @@ impl Elf {
             }
         }
 
-        Elf::new(instructions, entry, base_address, image)
+        // Read the function symbols, if the ELF file was not stripped.
+        let mut symbols = BTreeMap::new();
+        if let Ok(Some((symtab, strtab))) = elf.symbol_table() {
+            for symbol in symtab.iter() {
+                if symbol.st_symtype() != STT_FUNC || symbol.st_value == 0 {
+                    continue;
+                }
+                let (Ok(name), Ok(addr)) = (
+                    strtab.get(symbol.st_name as usize),
+                    u32::try_from(symbol.st_value),
+                ) else {
+                    continue;
+                };
+                symbols.insert(addr, format!("{:#}", rustc_demangle::demangle(name)));
+            }
+        }
+
+        Elf::new(instructions, entry, base_address, image, symbols)
     }
 }
@@ -326,9 +326,9 @@ impl InstructionProcessor for InstructionTranspiler {
             Opcode::ECALL,
             Register::X10 as u32,
             Register::X5 as u32,
-            0,
+            Register::X11 as u32,
+            false,
             false,
-            true,
         )
     }
 
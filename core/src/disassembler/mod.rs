@@ -15,6 +15,7 @@ impl Program {
             pc_start,
             pc_base,
             memory_image: BTreeMap::new(),
+            symbols: BTreeMap::new(),
         }
     }
 
@@ -32,6 +33,7 @@ impl Program {
             pc_start: elf.pc_start,
             pc_base: elf.pc_base,
             memory_image: elf.memory_image,
+            symbols: elf.symbols,
         }
     }
 
@@ -1,17 +1,54 @@
+use std::collections::BTreeMap;
+
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+use crate::runtime::ExecutionError;
 use crate::utils::Buffer;
 
 /// Standard input for the prover.
 #[derive(Serialize, Deserialize)]
 pub struct SP1Stdin {
+    /// The private inputs, which are not revealed to the verifier.
     pub buffer: Buffer,
+
+    /// The public inputs, whose digest is committed in the proof.
+    pub public_buffer: Buffer,
+
+    /// The environment variables of the program, which are not revealed to the verifier.
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+
+    /// The seed of the random number generator used by `getrandom` in the program.
+    #[serde(default)]
+    pub rng_seed: u64,
 }
 
 /// Standard output for the prover.
 #[derive(Serialize, Deserialize)]
 pub struct SP1Stdout {
     pub buffer: Buffer,
+
+    /// The exit code the program halted with, which is committed in the proof.
+    pub exit_code: u32,
+
+    /// The text the program wrote to stdout, which is not part of the proof.
+    #[serde(skip)]
+    pub program_stdout: String,
+
+    /// The text the program wrote to stderr, which is not part of the proof.
+    #[serde(skip)]
+    pub program_stderr: String,
+
+    /// The seed of the random number generator the program was executed with, which is only
+    /// information for the prover. It is neither committed in the proof nor saved with it, since
+    /// the prover may choose any seed.
+    #[serde(skip)]
+    pub rng_seed: u64,
+
+    /// The error describing why the program halted with a non-zero exit code, if it did so with
+    /// `ProverOptions::with_allow_nonzero_exit`. It is not part of the proof.
+    #[serde(skip)]
+    pub panic: Option<ExecutionError>,
 }
 
 impl SP1Stdin {
@@ -19,6 +56,9 @@ impl SP1Stdin {
     pub fn new() -> Self {
         Self {
             buffer: Buffer::new(),
+            public_buffer: Buffer::new(),
+            env: BTreeMap::new(),
+            rng_seed: 0,
         }
     }
 
@@ -26,6 +66,9 @@ impl SP1Stdin {
     pub fn from(data: &[u8]) -> Self {
         Self {
             buffer: Buffer::from(data),
+            public_buffer: Buffer::new(),
+            env: BTreeMap::new(),
+            rng_seed: 0,
         }
     }
 
@@ -48,6 +91,32 @@ impl SP1Stdin {
     pub fn write_slice(&mut self, slice: &[u8]) {
         self.buffer.write_slice(slice);
     }
+
+    /// Write a value to the public buffer.
+    ///
+    /// The program must read the entire public buffer with `io::read_public`, since the verifier
+    /// checks the proof against the digest of all of it.
+    pub fn write_public<T: Serialize>(&mut self, data: &T) {
+        self.public_buffer.write(data);
+    }
+
+    /// Write a slice of bytes to the public buffer.
+    pub fn write_public_slice(&mut self, slice: &[u8]) {
+        self.public_buffer.write_slice(slice);
+    }
+
+    /// Set an environment variable which the program can read with `std::env::var`.
+    pub fn set_env(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.env.insert(key.into(), value.into());
+    }
+
+    /// Set the seed of the random number generator used by `getrandom` in the program.
+    ///
+    /// The seed is chosen by the prover and not constrained, so randomness derived from it must
+    /// not be relied upon for soundness.
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng_seed = seed;
+    }
 }
 
 impl SP1Stdout {
@@ -55,6 +124,11 @@ impl SP1Stdout {
     pub fn new() -> Self {
         Self {
             buffer: Buffer::new(),
+            exit_code: 0,
+            program_stdout: String::new(),
+            program_stderr: String::new(),
+            rng_seed: 0,
+            panic: None,
         }
     }
 
@@ -62,6 +136,11 @@ impl SP1Stdout {
     pub fn from(data: &[u8]) -> Self {
         Self {
             buffer: Buffer::from(data),
+            exit_code: 0,
+            program_stdout: String::new(),
+            program_stderr: String::new(),
+            rng_seed: 0,
+            panic: None,
         }
     }
 
@@ -152,5 +231,26 @@ pub mod proof_serde {
                 bincode::deserialize::<SP1ProofWithIO<BabyBearBlake3>>(&serialized).unwrap();
             SP1Verifier::verify(FIBONACCI_IO_ELF, &output).unwrap();
         }
+
+        /// Tests that the verifier rejects outputs which do not match the committed digest
+        #[test]
+        fn test_tampered_stdout() {
+            let mut stdin = SP1Stdin::new();
+            stdin.write(&3u32);
+            let mut proof = SP1Prover::prove(FIBONACCI_IO_ELF, stdin).unwrap();
+            proof.stdout.write(&4u32);
+            assert!(SP1Verifier::verify(FIBONACCI_IO_ELF, &proof).is_err());
+        }
+
+        /// Tests that the verifier rejects an exit code which does not match the committed one
+        #[test]
+        fn test_tampered_exit_code() {
+            let mut stdin = SP1Stdin::new();
+            stdin.write(&3u32);
+            let mut proof = SP1Prover::prove(FIBONACCI_IO_ELF, stdin).unwrap();
+            assert_eq!(proof.stdout.exit_code, 0);
+            proof.stdout.exit_code = 1;
+            assert!(SP1Verifier::verify(FIBONACCI_IO_ELF, &proof).is_err());
+        }
     }
 }
@@ -31,16 +31,23 @@ pub mod utils;
 
 pub use io::*;
 
+use air::PublicValues;
 use anyhow::Result;
 use p3_commit::Pcs;
 use p3_matrix::dense::RowMajorMatrix;
-use runtime::{Program, Runtime};
+use runtime::{ExecutionError, ExecutionLimits, Program, Runtime};
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use stark::{OpeningProof, ProgramVerificationError, Proof, ShardMainData};
-use stark::{RiscvStark, StarkGenericConfig};
+use stark::{DistributedProver, RiscvStark, StarkGenericConfig};
+use stark::{OpeningProof, ProgramVerificationError, Proof, ProverError, ShardMainData};
 use std::fs;
-use utils::{prove_core, BabyBearBlake3, StarkUtils};
+use std::io::Write;
+use std::path::Path;
+use syscall::precompiles::custom::Precompiles;
+use utils::{
+    prove_core, prove_core_streaming, prove_core_with_prover, BabyBearBlake3, ProverOptions,
+    StarkUtils,
+};
 
 /// A prover that can prove RISCV ELFs.
 pub struct SP1Prover;
@@ -58,26 +65,77 @@ pub struct SP1ProofWithIO<SC: StarkGenericConfig + Serialize + DeserializeOwned>
 }
 
 impl SP1Prover {
-    /// Executes the elf with the given inputs and returns the output.
-    pub fn execute(elf: &[u8], stdin: SP1Stdin) -> Result<SP1Stdout> {
-        let program = Program::from(elf);
-        let mut runtime = Runtime::new(program);
-        runtime.write_stdin_slice(&stdin.buffer.data);
-        runtime.run();
-        Ok(SP1Stdout::from(&runtime.state.output_stream))
+    /// Executes the elf with the given inputs and returns the output, including the exit code.
+    ///
+    /// If the program halts with a non-zero exit code, e.g. because it panicked, execution fails with
+    /// `ExecutionError::NonZeroExitCode`, which describes the function that halted and what it last
+    /// wrote to stderr, i.e. the message of a panic. Proving fails with the same error, unless
+    /// `ProverOptions::with_allow_nonzero_exit` is set, in which case the proof is returned with the
+    /// exit code and the error in `SP1Stdout::panic`.
+    pub fn execute(elf: &[u8], stdin: SP1Stdin) -> Result<SP1Stdout, ExecutionError> {
+        Self::execute_with_options(elf, stdin, ProverOptions::default())
+    }
+
+    /// Executes the elf with the given inputs, failing if it exceeds the given cycle or memory
+    /// limits, and returns the output, including the exit code.
+    pub fn execute_with_limits(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        limits: ExecutionLimits,
+    ) -> Result<SP1Stdout, ExecutionError> {
+        Self::execute_with_options(elf, stdin, ProverOptions::default().with_limits(limits))
+    }
+
+    /// Executes the elf with the given inputs and the limits, precompiles and profile file of
+    /// `options`, and returns the output, including the exit code.
+    pub fn execute_with_options(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        options: ProverOptions,
+    ) -> Result<SP1Stdout, ExecutionError> {
+        let mut runtime = runtime_for(Program::from(elf), &stdin, &options);
+        runtime.run()?;
+        Ok(runtime.stdout())
+    }
+
+    /// Executes the elf with the given inputs and options, sending the text it writes to stdout and
+    /// stderr to the given writers instead of printing it, and returns the output, including the
+    /// exit code and the text.
+    pub fn execute_with_output(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        options: ProverOptions,
+        stdout: impl Write + 'static,
+        stderr: impl Write + 'static,
+    ) -> Result<SP1Stdout, ExecutionError> {
+        let mut runtime = runtime_for(Program::from(elf), &stdin, &options);
+        runtime.redirect_stdout(stdout);
+        runtime.redirect_stderr(stderr);
+        runtime.run()?;
+        Ok(runtime.stdout())
     }
 
     /// Generate a proof for the execution of the ELF with the given public inputs.
-    pub fn prove(elf: &[u8], stdin: SP1Stdin) -> Result<SP1ProofWithIO<BabyBearBlake3>> {
-        let program = Program::from(elf);
-        let mut runtime = Runtime::new(program);
-        runtime.write_stdin_slice(&stdin.buffer.data);
-        tracing::info_span!("runtime.run(...)").in_scope(|| {
-            runtime.run();
-        });
+    pub fn prove(
+        elf: &[u8],
+        stdin: SP1Stdin,
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>, ProverError> {
+        Self::prove_with_options(elf, stdin, ProverOptions::default())
+    }
+
+    /// Generate a proof for the execution of the ELF with the given public inputs, sharded and
+    /// proven as set in `options` instead of the environment variables. The progress is reported
+    /// to the observer of `options`, and the proof can be aborted with its cancellation token.
+    pub fn prove_with_options(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        options: ProverOptions,
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>, ProverError> {
+        let mut runtime = runtime_for(Program::from(elf), &stdin, &options);
+        tracing::info_span!("runtime.run(...)").in_scope(|| runtime.run())?;
         let config = BabyBearBlake3::new();
-        let stdout = SP1Stdout::from(&runtime.state.output_stream);
-        let proof = prove_core(config, runtime);
+        let stdout = runtime.stdout();
+        let proof = prove_core(config, runtime, &options)?;
         Ok(SP1ProofWithIO {
             proof,
             stdin,
@@ -85,12 +143,85 @@ impl SP1Prover {
         })
     }
 
+    /// Generate a proof for the execution of the ELF with the given public inputs, proving each
+    /// shard as soon as it has been executed so that memory usage does not grow with the number of
+    /// cycles.
+    pub fn prove_streaming(
+        elf: &[u8],
+        stdin: SP1Stdin,
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>, ProverError> {
+        Self::prove_streaming_with_options(elf, stdin, ProverOptions::default())
+    }
+
+    /// Generate a proof for the execution of the ELF with the given public inputs one shard at a
+    /// time, sharded and proven as set in `options` instead of the environment variables.
+    pub fn prove_streaming_with_options(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        options: ProverOptions,
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>, ProverError> {
+        let mut runtime = runtime_for(Program::from(elf), &stdin, &options);
+        let config = BabyBearBlake3::new();
+        let proof = prove_core_streaming(config, &mut runtime, &options)?;
+        Ok(SP1ProofWithIO {
+            proof,
+            stdin,
+            stdout: runtime.stdout(),
+        })
+    }
+
+    /// Generate a proof for the execution of the ELF with the given public inputs, whose shards are
+    /// proven by workers running `SP1Prover::run_worker` on the `work_dir` of `options`.
+    pub fn prove_distributed(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        options: ProverOptions,
+    ) -> Result<SP1ProofWithIO<BabyBearBlake3>, ProverError> {
+        let mut runtime = runtime_for(Program::from(elf), &stdin, &options);
+        tracing::info_span!("runtime.run(...)").in_scope(|| runtime.run())?;
+        let config = BabyBearBlake3::new();
+        let stdout = runtime.stdout();
+        let proof = prove_core_with_prover::<_, DistributedProver<_>>(config, runtime, &options)?;
+        Ok(SP1ProofWithIO {
+            proof,
+            stdin,
+            stdout,
+        })
+    }
+
+    /// Proves the shards written to `work_dir` by `SP1Prover::prove_distributed` until the
+    /// cancellation token of `options` is cancelled, and returns the number of shards proven.
+    pub fn run_worker(work_dir: &Path, options: ProverOptions) -> Result<usize, ProverError> {
+        let machine = RiscvStark::with_precompiles(BabyBearBlake3::new(), &options.precompiles);
+        DistributedProver::run_worker(&machine, work_dir, &options)
+    }
+
     /// Generate a proof for the execution of the ELF with the given public inputs and a custom config.
     pub fn prove_with_config<SC: StarkGenericConfig>(
         elf: &[u8],
         stdin: SP1Stdin,
         config: SC,
-    ) -> Result<SP1ProofWithIO<SC>>
+    ) -> Result<SP1ProofWithIO<SC>, ProverError>
+    where
+        SC: StarkUtils + Send + Sync + Serialize + DeserializeOwned + Clone,
+        SC::Challenger: Clone,
+        OpeningProof<SC>: Send + Sync,
+        <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment: Send + Sync,
+        <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::ProverData: Send + Sync,
+        ShardMainData<SC>: Serialize + DeserializeOwned,
+        <SC as StarkGenericConfig>::Val: p3_field::PrimeField32,
+    {
+        Self::prove_with_config_and_options(elf, stdin, config, ProverOptions::default())
+    }
+
+    /// Generate a proof for the execution of the ELF with the given public inputs, a custom config
+    /// and the given options.
+    pub fn prove_with_config_and_options<SC: StarkGenericConfig>(
+        elf: &[u8],
+        stdin: SP1Stdin,
+        config: SC,
+        options: ProverOptions,
+    ) -> Result<SP1ProofWithIO<SC>, ProverError>
     where
         SC: StarkUtils + Send + Sync + Serialize + DeserializeOwned + Clone,
         SC::Challenger: Clone,
@@ -100,12 +231,10 @@ impl SP1Prover {
         ShardMainData<SC>: Serialize + DeserializeOwned,
         <SC as StarkGenericConfig>::Val: p3_field::PrimeField32,
     {
-        let program = Program::from(elf);
-        let mut runtime = Runtime::new(program);
-        runtime.write_stdin_slice(&stdin.buffer.data);
-        runtime.run();
-        let stdout = SP1Stdout::from(&runtime.state.output_stream);
-        let proof = prove_core(config, runtime);
+        let mut runtime = runtime_for(Program::from(elf), &stdin, &options);
+        runtime.run()?;
+        let stdout = runtime.stdout();
+        let proof = prove_core(config, runtime, &options)?;
         Ok(SP1ProofWithIO {
             proof,
             stdin,
@@ -114,18 +243,37 @@ impl SP1Prover {
     }
 }
 
+/// Creates a runtime which executes `program` on the inputs of `stdin` with `options`.
+fn runtime_for(program: Program, stdin: &SP1Stdin, options: &ProverOptions) -> Runtime {
+    let mut runtime = Runtime::with_options(program, options);
+    runtime.write_stdin_slice(&stdin.buffer.data);
+    runtime.write_public_stdin_slice(&stdin.public_buffer.data);
+    runtime.state.env.extend(stdin.env.clone());
+    runtime.set_rng_seed(stdin.rng_seed);
+    runtime
+}
+
 impl SP1Verifier {
     /// Verify a proof generated by `SP1Prover`.
-    #[allow(unused_variables)]
     pub fn verify(
         elf: &[u8],
         proof: &SP1ProofWithIO<BabyBearBlake3>,
+    ) -> Result<(), ProgramVerificationError> {
+        Self::verify_with_precompiles(elf, proof, &Precompiles::new())
+    }
+
+    /// Verify a proof generated by `SP1Prover` with options using the given precompiles.
+    pub fn verify_with_precompiles(
+        elf: &[u8],
+        proof: &SP1ProofWithIO<BabyBearBlake3>,
+        precompiles: &Precompiles,
     ) -> Result<(), ProgramVerificationError> {
         let config = BabyBearBlake3::new();
         let mut challenger = config.challenger();
-        let machine = RiscvStark::new(config);
+        let machine = RiscvStark::with_precompiles(config, precompiles);
         let (_, vk) = machine.setup(&Program::from(elf));
-        machine.verify(&vk, &proof.proof, &mut challenger)
+        machine.verify(&vk, &proof.proof, &mut challenger)?;
+        proof.verify_public_values()
     }
 
     /// Verify a proof generated by `SP1Prover` with a custom config.
@@ -148,7 +296,8 @@ impl SP1Verifier {
         let machine = RiscvStark::new(config);
 
         let (_, vk) = machine.setup(&Program::from(elf));
-        machine.verify(&vk, &proof.proof, &mut challenger)
+        machine.verify(&vk, &proof.proof, &mut challenger)?;
+        proof.verify_public_values()
     }
 }
 
@@ -159,4 +308,34 @@ impl<SC: StarkGenericConfig + Serialize + DeserializeOwned> SP1ProofWithIO<SC> {
         fs::write(path, data).unwrap();
         Ok(())
     }
+
+    /// Checks that every shard commits to the digests of the public inputs in `stdin` and the
+    /// public values in `stdout`, and to the exit code in `stdout`, and that a shard has halted.
+    ///
+    /// The exit code and the digests are only constrained by the `HALT` and `COMMIT` syscalls, so
+    /// they are meaningless for an execution which did not halt.
+    fn verify_public_values(&self) -> Result<(), ProgramVerificationError> {
+        let expected = PublicValues::from_io(
+            &self.stdin.public_buffer.data,
+            &self.stdout.buffer.data,
+            self.stdout.exit_code,
+        );
+        let public_values = self
+            .proof
+            .shard_proofs
+            .iter()
+            .map(|shard_proof| PublicValues::<u32>::from_vec(&shard_proof.public_values))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(ProgramVerificationError::InvalidPublicValuesDigest)?;
+        if public_values
+            .iter()
+            .any(|pv| pv.without_halted() != expected.without_halted())
+        {
+            return Err(ProgramVerificationError::InvalidPublicValuesDigest);
+        }
+        if !public_values.iter().any(|pv| pv.halted == 1) {
+            return Err(ProgramVerificationError::NotHalted);
+        }
+        Ok(())
+    }
 }
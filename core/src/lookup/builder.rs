@@ -1,34 +1,55 @@
-use crate::air::{AirInteraction, MessageBuilder};
-use p3_air::{AirBuilder, PairCol, VirtualPairCol};
+use crate::air::{AirInteraction, MessageBuilder, PublicValuesAirBuilder};
+use p3_air::{AirBuilder, PairBuilder, PairCol, VirtualPairCol};
 use p3_field::Field;
 use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Matrix;
 use p3_uni_stark::{SymbolicExpression, SymbolicVariable};
 
 use super::Interaction;
 
-/// A builder for the lookup table interactions.
+/// A builder for the lookup table interactions, which also records the constraints of the AIR.
+///
+/// Preprocessed columns are represented as symbolic variables whose column index is offset by the
+/// width of the main trace, so that they can be told apart when converting to a `VirtualPairCol`.
 pub struct InteractionBuilder<F: Field> {
+    preprocessed: RowMajorMatrix<SymbolicVariable<F>>,
     main: RowMajorMatrix<SymbolicVariable<F>>,
     sends: Vec<Interaction<F>>,
     receives: Vec<Interaction<F>>,
+    constraints: Vec<SymbolicExpression<F>>,
 }
 
 impl<F: Field> InteractionBuilder<F> {
-    /// Creates a new `InteractionBuilder` with the given width.
-    pub fn new(width: usize) -> Self {
-        let values = [false, true]
-            .into_iter()
-            .flat_map(|is_next| {
-                (0..width).map(move |column| SymbolicVariable::new(is_next, column))
-            })
-            .collect();
+    /// Creates a new `InteractionBuilder` with the given preprocessed and main widths.
+    pub fn new(preprocessed_width: usize, main_width: usize) -> Self {
+        let symbolic_row = |offset: usize, width: usize| {
+            [false, true]
+                .into_iter()
+                .flat_map(|is_next| {
+                    (0..width).map(move |column| SymbolicVariable::new(is_next, offset + column))
+                })
+                .collect::<Vec<_>>()
+        };
         Self {
-            main: RowMajorMatrix::new(values, width),
+            // A matrix cannot have zero width, so chips without preprocessed columns get an empty
+            // matrix of width one.
+            preprocessed: RowMajorMatrix::new(
+                symbolic_row(main_width, preprocessed_width),
+                preprocessed_width.max(1),
+            ),
+            main: RowMajorMatrix::new(symbolic_row(0, main_width), main_width),
             sends: vec![],
             receives: vec![],
+            constraints: vec![],
         }
     }
 
+    /// Returns the constraints asserted by the AIR, in terms of the symbolic main and preprocessed
+    /// columns.
+    pub fn constraints(&self) -> &[SymbolicExpression<F>] {
+        &self.constraints
+    }
+
     /// Returns the sends and receives.
     pub fn interactions(self) -> (Vec<Interaction<F>>, Vec<Interaction<F>>) {
         (self.sends, self.receives)
@@ -61,43 +82,62 @@ impl<F: Field> AirBuilder for InteractionBuilder<F> {
         }
     }
 
-    fn assert_zero<I: Into<Self::Expr>>(&mut self, _x: I) {}
+    fn assert_zero<I: Into<Self::Expr>>(&mut self, x: I) {
+        self.constraints.push(x.into());
+    }
+}
+
+impl<F: Field> PairBuilder for InteractionBuilder<F> {
+    fn preprocessed(&self) -> Self::M {
+        self.preprocessed.clone()
+    }
+}
+
+impl<F: Field> PublicValuesAirBuilder for InteractionBuilder<F> {
+    fn public_values(&self) -> &[Self::F] {
+        &[]
+    }
 }
 
 impl<F: Field> MessageBuilder<AirInteraction<SymbolicExpression<F>>> for InteractionBuilder<F> {
     fn send(&mut self, message: AirInteraction<SymbolicExpression<F>>) {
+        let main_width = self.main.width();
         let values = message
             .values
             .into_iter()
-            .map(|v| symbolic_to_virtual_pair(&v))
+            .map(|v| symbolic_to_virtual_pair(&v, main_width))
             .collect::<Vec<_>>();
 
-        let multiplicity = symbolic_to_virtual_pair(&message.multiplicity);
+        let multiplicity = symbolic_to_virtual_pair(&message.multiplicity, main_width);
 
         self.sends
             .push(Interaction::new(values, multiplicity, message.kind));
     }
 
     fn receive(&mut self, message: AirInteraction<SymbolicExpression<F>>) {
+        let main_width = self.main.width();
         let values = message
             .values
             .into_iter()
-            .map(|v| symbolic_to_virtual_pair(&v))
+            .map(|v| symbolic_to_virtual_pair(&v, main_width))
             .collect::<Vec<_>>();
 
-        let multiplicity = symbolic_to_virtual_pair(&message.multiplicity);
+        let multiplicity = symbolic_to_virtual_pair(&message.multiplicity, main_width);
 
         self.receives
             .push(Interaction::new(values, multiplicity, message.kind));
     }
 }
 
-fn symbolic_to_virtual_pair<F: Field>(expression: &SymbolicExpression<F>) -> VirtualPairCol<F> {
+fn symbolic_to_virtual_pair<F: Field>(
+    expression: &SymbolicExpression<F>,
+    main_width: usize,
+) -> VirtualPairCol<F> {
     if expression.degree_multiple() > 1 {
         panic!("degree multiple is too high");
     }
 
-    let (column_weights, constant) = eval_symbolic_to_virtual_pair(expression);
+    let (column_weights, constant) = eval_symbolic_to_virtual_pair(expression, main_width);
 
     let column_weights = column_weights.into_iter().collect();
 
@@ -106,30 +146,35 @@ fn symbolic_to_virtual_pair<F: Field>(expression: &SymbolicExpression<F>) -> Vir
 
 fn eval_symbolic_to_virtual_pair<F: Field>(
     expression: &SymbolicExpression<F>,
+    main_width: usize,
 ) -> (Vec<(PairCol, F)>, F) {
     match expression {
         SymbolicExpression::Constant(c) => (vec![], *c),
-        SymbolicExpression::Variable(v) if !v.is_next => {
+        SymbolicExpression::Variable(v) if !v.is_next && v.column < main_width => {
             (vec![(PairCol::Main(v.column), F::one())], F::zero())
         }
+        SymbolicExpression::Variable(v) if !v.is_next => (
+            vec![(PairCol::Preprocessed(v.column - main_width), F::one())],
+            F::zero(),
+        ),
         SymbolicExpression::Add { x, y, .. } => {
-            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x);
-            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y);
+            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x, main_width);
+            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y, main_width);
             ([v_l, v_r].concat(), c_l + c_r)
         }
         SymbolicExpression::Sub { x, y, .. } => {
-            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x);
-            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y);
+            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x, main_width);
+            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y, main_width);
             let neg_v_r = v_r.iter().map(|(c, w)| (*c, -*w)).collect();
             ([v_l, neg_v_r].concat(), c_l - c_r)
         }
         SymbolicExpression::Neg { x, .. } => {
-            let (v, c) = eval_symbolic_to_virtual_pair(x);
+            let (v, c) = eval_symbolic_to_virtual_pair(x, main_width);
             (v.iter().map(|(c, w)| (*c, -*w)).collect(), -c)
         }
         SymbolicExpression::Mul { x, y, .. } => {
-            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x);
-            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y);
+            let (v_l, c_l) = eval_symbolic_to_virtual_pair(x, main_width);
+            let (v_r, c_r) = eval_symbolic_to_virtual_pair(y, main_width);
 
             let mut v = vec![];
             v.extend(v_l.iter().map(|(c, w)| (*c, *w * c_r)));
@@ -180,7 +225,7 @@ mod tests {
 
         let z = x + y;
 
-        let (column_weights, constant) = super::eval_symbolic_to_virtual_pair(&z);
+        let (column_weights, constant) = super::eval_symbolic_to_virtual_pair(&z, 2);
         println!("column_weights: {:?}", column_weights);
         println!("constant: {:?}", constant);
 
@@ -235,7 +280,7 @@ mod tests {
     fn test_lookup_interactions() {
         let air = LookupTestAir {};
 
-        let mut builder = InteractionBuilder::<BabyBear>::new(NUM_COLS);
+        let mut builder = InteractionBuilder::<BabyBear>::new(0, NUM_COLS);
 
         air.eval(&mut builder);
 
@@ -158,7 +158,7 @@ mod tests {
     fn test_memory_generate_trace() {
         let program = simple_program();
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         let shard = runtime.record.clone();
 
         let chip: MemoryGlobalChip = MemoryGlobalChip::new(MemoryChipKind::Init);
@@ -184,7 +184,7 @@ mod tests {
 
         let program = simple_program();
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
 
         let chip = MemoryGlobalChip::new(MemoryChipKind::Init);
 
@@ -201,7 +201,7 @@ mod tests {
         setup_logger();
         let program = sha_extend_program();
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
 
         let machine = RiscvStark::new(BabyBearPoseidon2::new());
         debug_interactions_with_all_chips::<BabyBearPoseidon2>(
@@ -216,7 +216,7 @@ mod tests {
         setup_logger();
         let program = sha_extend_program();
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
 
         let machine = RiscvStark::new(BabyBearPoseidon2::new());
         debug_interactions_with_all_chips::<BabyBearPoseidon2>(
@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use super::{Instruction, Opcode, Program, Register};
+
+/// A function which has been called and has not yet returned.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct Frame {
+    function: u32,
+    return_addr: u32,
+}
+
+/// The functions the program is executing, recovered from its `JAL` and `JALR` instructions like in
+/// the `Profiler`.
+///
+/// The runtime only tracks it for programs with symbols, to name the function which made the
+/// program halt.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CallStack {
+    frames: Vec<Frame>,
+}
+
+impl CallStack {
+    /// Record the execution of `instruction` at `pc`, after which the program continued at
+    /// `next_pc`.
+    pub fn record(&mut self, program: &Program, pc: u32, next_pc: u32, instruction: &Instruction) {
+        let rd = match instruction.opcode {
+            Opcode::JAL => instruction.j_type().0,
+            Opcode::JALR => instruction.i_type().0,
+            _ => return,
+        };
+        if rd == Register::X1 {
+            self.frames.push(Frame {
+                function: program.function_at(next_pc).unwrap_or(next_pc),
+                return_addr: pc.wrapping_add(4),
+            });
+        } else if rd == Register::X0 {
+            if let Some(depth) = self
+                .frames
+                .iter()
+                .rposition(|frame| frame.return_addr == next_pc)
+            {
+                self.frames.truncate(depth);
+            } else if program.symbols.contains_key(&next_pc) {
+                // A tail call replaces the function of the current frame.
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.function = next_pc;
+                }
+            }
+        }
+    }
+
+    /// The start addresses of the called functions, from the innermost to the outermost.
+    pub fn functions(&self) -> impl Iterator<Item = u32> + '_ {
+        self.frames.iter().rev().map(|frame| frame.function)
+    }
+}
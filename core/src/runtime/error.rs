@@ -0,0 +1,164 @@
+use std::fmt::{Display, Formatter};
+
+use serde::{Deserialize, Serialize};
+
+/// An error that occurred while executing a program.
+///
+/// Every error records the program counter of the instruction that caused it and the global clock,
+/// i.e. the number of instructions executed before it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExecutionError {
+    /// The program called a syscall which is not supported by the runtime.
+    UnsupportedSyscall { pc: u32, clk: u32, syscall_id: u32 },
+
+    /// A syscall was called with invalid arguments.
+    InvalidSyscallArgument {
+        pc: u32,
+        clk: u32,
+        syscall_id: u32,
+        description: String,
+    },
+
+    /// The program read more bytes from an input stream than were provided.
+    InputExhausted { pc: u32, clk: u32, fd: u32 },
+
+    /// The program accessed memory at an address which is not aligned to the size of the access.
+    UnalignedMemoryAccess { pc: u32, clk: u32, addr: u32 },
+
+    /// The program accessed memory at an address which is reserved for registers or which does not
+    /// fit in the field.
+    InvalidMemoryAccess { pc: u32, clk: u32, addr: u32 },
+
+    /// The program executed an `EBREAK` instruction.
+    Breakpoint { pc: u32, clk: u32 },
+
+    /// The program executed an `UNIMP` instruction.
+    Unimplemented { pc: u32, clk: u32 },
+
+    /// The program halted with a non-zero exit code.
+    ///
+    /// The error records the function which halted, if the program has symbols, and the last
+    /// output written to stderr before halting, which contains the message of a panic.
+    NonZeroExitCode {
+        pc: u32,
+        clk: u32,
+        exit_code: u32,
+        function: Option<String>,
+        message: Option<String>,
+    },
+
+    /// The program did not halt within the configured maximum number of cycles.
+    CycleLimitExceeded { pc: u32, clk: u32, max_cycles: u64 },
+
+    /// The program touched more memory words than the configured maximum.
+    MemoryLimitExceeded {
+        pc: u32,
+        clk: u32,
+        max_memory_words: usize,
+    },
+
+    /// The execution was aborted through the cancellation token of its `ProverOptions`.
+    Cancelled { pc: u32, clk: u32 },
+}
+
+impl ExecutionError {
+    /// The program counter of the instruction that caused the error.
+    pub fn pc(&self) -> u32 {
+        match self {
+            ExecutionError::UnsupportedSyscall { pc, .. }
+            | ExecutionError::InvalidSyscallArgument { pc, .. }
+            | ExecutionError::InputExhausted { pc, .. }
+            | ExecutionError::UnalignedMemoryAccess { pc, .. }
+            | ExecutionError::InvalidMemoryAccess { pc, .. }
+            | ExecutionError::Breakpoint { pc, .. }
+            | ExecutionError::Unimplemented { pc, .. }
+            | ExecutionError::NonZeroExitCode { pc, .. }
+            | ExecutionError::CycleLimitExceeded { pc, .. }
+            | ExecutionError::MemoryLimitExceeded { pc, .. }
+            | ExecutionError::Cancelled { pc, .. } => *pc,
+        }
+    }
+
+    /// The global clock at which the error occurred.
+    pub fn clk(&self) -> u32 {
+        match self {
+            ExecutionError::UnsupportedSyscall { clk, .. }
+            | ExecutionError::InvalidSyscallArgument { clk, .. }
+            | ExecutionError::InputExhausted { clk, .. }
+            | ExecutionError::UnalignedMemoryAccess { clk, .. }
+            | ExecutionError::InvalidMemoryAccess { clk, .. }
+            | ExecutionError::Breakpoint { clk, .. }
+            | ExecutionError::Unimplemented { clk, .. }
+            | ExecutionError::NonZeroExitCode { clk, .. }
+            | ExecutionError::CycleLimitExceeded { clk, .. }
+            | ExecutionError::MemoryLimitExceeded { clk, .. }
+            | ExecutionError::Cancelled { clk, .. } => *clk,
+        }
+    }
+
+    /// A description of the error, without the location at which it occurred.
+    pub fn description(&self) -> String {
+        match self {
+            ExecutionError::UnsupportedSyscall { syscall_id, .. } => {
+                format!("unsupported syscall {}", syscall_id)
+            }
+            ExecutionError::InvalidSyscallArgument {
+                syscall_id,
+                description,
+                ..
+            } => format!(
+                "invalid argument to syscall {}: {}",
+                syscall_id, description
+            ),
+            ExecutionError::InputExhausted { fd, .. } => {
+                format!("not enough input was provided to read from fd {}", fd)
+            }
+            ExecutionError::UnalignedMemoryAccess { addr, .. } => {
+                format!("unaligned memory access at address 0x{:x}", addr)
+            }
+            ExecutionError::InvalidMemoryAccess { addr, .. } => {
+                format!("invalid memory access at address 0x{:x}", addr)
+            }
+            ExecutionError::Breakpoint { .. } => "breakpoint encountered".to_string(),
+            ExecutionError::Unimplemented { .. } => "UNIMP instruction encountered".to_string(),
+            ExecutionError::NonZeroExitCode {
+                exit_code,
+                function,
+                message,
+                ..
+            } => {
+                let mut description =
+                    format!("program halted with non-zero exit code {}", exit_code);
+                if let Some(function) = function {
+                    description.push_str(&format!(" in `{}`", function));
+                }
+                match message {
+                    Some(message) => description.push_str(&format!(": {}", message.trim_end())),
+                    None => description.push_str(", which likely means it panicked"),
+                }
+                description
+            }
+            ExecutionError::CycleLimitExceeded { max_cycles, .. } => {
+                format!("cycle limit of {} exceeded", max_cycles)
+            }
+            ExecutionError::MemoryLimitExceeded {
+                max_memory_words, ..
+            } => format!("memory limit of {} words exceeded", max_memory_words),
+            ExecutionError::Cancelled { .. } => "execution was cancelled".to_string(),
+        }
+    }
+}
+
+impl Display for ExecutionError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (pc=0x{:x}, clk={})",
+            self.description(),
+            self.pc(),
+            self.clk()
+        )
+    }
+}
+
+impl std::error::Error for ExecutionError {}
@@ -0,0 +1,475 @@
+//! A stub for the GDB remote serial protocol, which lets `gdb` debug a program in the runtime.
+//!
+//! The stub supports reading registers and memory, software and hardware breakpoints,
+//! single-stepping and continuing. See https://sourceware.org/gdb/current/onlinedocs/gdb.html/Remote-Protocol.html
+//! for the protocol.
+
+use std::collections::BTreeSet;
+use std::io::{self, ErrorKind, Read, Write};
+use std::net::{TcpListener, TcpStream, ToSocketAddrs};
+
+use super::{ExecutionError, Opcode, Runtime};
+
+/// The number of instructions executed between checks for an interrupt from GDB.
+const INTERRUPT_CHECK_CYCLES: u64 = 1 << 12;
+
+/// The maximum size of a packet, which is advertised to GDB.
+const PACKET_SIZE: usize = 0x4000;
+
+/// The names of the general purpose registers, in the order GDB expects them.
+const REGISTER_NAMES: [&str; 32] = [
+    "zero", "ra", "sp", "gp", "tp", "t0", "t1", "t2", "fp", "s1", "a0", "a1", "a2", "a3", "a4",
+    "a5", "a6", "a7", "s2", "s3", "s4", "s5", "s6", "s7", "s8", "s9", "s10", "s11", "t3", "t4",
+    "t5", "t6",
+];
+
+/// The signal numbers reported to GDB.
+const SIGINT: u8 = 2;
+const SIGILL: u8 = 4;
+const SIGTRAP: u8 = 5;
+const SIGABRT: u8 = 6;
+const SIGSEGV: u8 = 11;
+
+impl Runtime {
+    /// Listen for a GDB connection at `addr` and run the program under the control of the debugger.
+    ///
+    /// Attach with `target remote <addr>` from `gdb` after loading the ELF of the program.
+    pub fn debug(&mut self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        tracing::info!("waiting for gdb to connect on {}", listener.local_addr()?);
+        let (stream, _) = listener.accept()?;
+        self.debug_connection(stream)
+    }
+
+    /// Run the program under the control of a debugger connected through `stream`.
+    ///
+    /// Returns once the debugger detaches, kills the program or closes the connection.
+    pub fn debug_connection(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_nodelay(true)?;
+        self.initialize();
+        self.trap_on_ebreak = true;
+        let result = GdbSession {
+            runtime: self,
+            stream,
+            breakpoints: BTreeSet::new(),
+        }
+        .serve();
+        self.trap_on_ebreak = false;
+        result
+    }
+}
+
+/// Why the program stopped, as reported to GDB.
+enum StopReason {
+    /// The program stopped with a signal but can be resumed.
+    Signal(u8),
+    /// The program executed an `EBREAK` instruction and can be resumed after it.
+    Trap,
+    /// The program halted with an exit code.
+    Exited(u32),
+    /// The program failed with an error and cannot be resumed.
+    Failed(ExecutionError),
+}
+
+/// A connection between GDB and the runtime.
+struct GdbSession<'a> {
+    runtime: &'a mut Runtime,
+    stream: TcpStream,
+    breakpoints: BTreeSet<u32>,
+}
+
+impl GdbSession<'_> {
+    /// Answer packets from GDB until it disconnects.
+    fn serve(mut self) -> io::Result<()> {
+        while let Some(packet) = self.read_packet()? {
+            let response = match packet.as_bytes().first() {
+                Some(b'?') => self.stop_reply(StopReason::Signal(SIGTRAP)),
+                Some(b'g') => self.read_registers(),
+                Some(b'p') => self.read_register(&packet[1..]),
+                Some(b'm') => self.read_memory(&packet[1..]),
+                Some(b'Z') => self.update_breakpoint(&packet[1..], true),
+                Some(b'z') => self.update_breakpoint(&packet[1..], false),
+                Some(b's') => {
+                    let reason = self.step();
+                    self.stop_reply(reason)
+                }
+                Some(b'c') => {
+                    let reason = self.resume()?;
+                    self.stop_reply(reason)
+                }
+                Some(b'H') => "OK".to_string(),
+                Some(b'D') => {
+                    self.write_packet("OK")?;
+                    return Ok(());
+                }
+                Some(b'k') => return Ok(()),
+                Some(b'q') => self.query(&packet[1..]),
+                _ => String::new(),
+            };
+            self.write_packet(&response)?;
+        }
+        Ok(())
+    }
+
+    /// Read the next packet, acknowledging it. Returns `None` once the connection is closed.
+    ///
+    /// Packets with an invalid checksum are rejected, which makes GDB send them again.
+    fn read_packet(&mut self) -> io::Result<Option<String>> {
+        loop {
+            // Skip acknowledgements and interrupts until the start of a packet.
+            loop {
+                match self.read_byte()? {
+                    Some(b'$') => break,
+                    Some(_) => continue,
+                    None => return Ok(None),
+                }
+            }
+            let mut data = Vec::new();
+            loop {
+                match self.read_byte()? {
+                    Some(b'#') => break,
+                    Some(byte) => data.push(byte),
+                    None => return Ok(None),
+                }
+            }
+            let mut checksum = [0u8; 2];
+            self.stream.read_exact(&mut checksum)?;
+            let expected = data.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+            let valid = std::str::from_utf8(&checksum)
+                .ok()
+                .and_then(|checksum| u8::from_str_radix(checksum, 16).ok())
+                == Some(expected);
+            if !valid || data.len() > PACKET_SIZE {
+                self.stream.write_all(b"-")?;
+                continue;
+            }
+            self.stream.write_all(b"+")?;
+            return Ok(Some(String::from_utf8_lossy(&data).into_owned()));
+        }
+    }
+
+    fn read_byte(&mut self) -> io::Result<Option<u8>> {
+        let mut byte = [0u8; 1];
+        match self.stream.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    }
+
+    fn write_packet(&mut self, data: &str) -> io::Result<()> {
+        let mut escaped = Vec::with_capacity(data.len());
+        for &byte in data.as_bytes() {
+            if matches!(byte, b'$' | b'#' | b'}' | b'*') {
+                escaped.extend_from_slice(&[b'}', byte ^ 0x20]);
+            } else {
+                escaped.push(byte);
+            }
+        }
+        let checksum = escaped
+            .iter()
+            .fold(0u8, |sum, byte| sum.wrapping_add(*byte));
+        self.stream.write_all(b"$")?;
+        self.stream.write_all(&escaped)?;
+        write!(self.stream, "#{:02x}", checksum)?;
+        self.stream.flush()
+    }
+
+    /// The registers x0 to x31 and the pc, each as little-endian hex bytes.
+    fn read_registers(&self) -> String {
+        self.runtime
+            .registers()
+            .iter()
+            .chain(std::iter::once(&self.runtime.state.pc))
+            .map(|value| format!("{:08x}", value.swap_bytes()))
+            .collect()
+    }
+
+    fn read_register(&self, args: &str) -> String {
+        match u32::from_str_radix(args, 16) {
+            Ok(index @ 0..=31) => {
+                format!(
+                    "{:08x}",
+                    self.runtime.registers()[index as usize].swap_bytes()
+                )
+            }
+            Ok(32) => format!("{:08x}", self.runtime.state.pc.swap_bytes()),
+            _ => "E01".to_string(),
+        }
+    }
+
+    /// Read up to `len` bytes of memory. The reply is truncated to fit in a packet, in which case
+    /// GDB requests the rest separately.
+    fn read_memory(&self, args: &str) -> String {
+        let Some((addr, len)) = parse_hex_pair(args) else {
+            return "E01".to_string();
+        };
+        let len = len.min((PACKET_SIZE / 2) as u32);
+        (0..len)
+            .map(|i| {
+                let addr = addr.wrapping_add(i);
+                let word = self.runtime.word(addr - addr % 4);
+                format!("{:02x}", (word >> ((addr % 4) * 8)) as u8)
+            })
+            .collect()
+    }
+
+    /// Insert or remove a software (`0`) or hardware (`1`) breakpoint.
+    fn update_breakpoint(&mut self, args: &str, insert: bool) -> String {
+        let (kind, args) = args.split_once(',').unwrap_or((args, ""));
+        let Some((addr, _)) = parse_hex_pair(args) else {
+            return "E01".to_string();
+        };
+        if kind != "0" && kind != "1" {
+            return String::new();
+        }
+        if insert {
+            self.breakpoints.insert(addr);
+        } else {
+            self.breakpoints.remove(&addr);
+        }
+        "OK".to_string()
+    }
+
+    /// Execute a single instruction.
+    fn step(&mut self) -> StopReason {
+        if !self.runtime.is_running() {
+            return StopReason::Exited(self.runtime.record.public_values.exit_code);
+        }
+        let is_ebreak = self.runtime.fetch().opcode == Opcode::EBREAK;
+        match self.runtime.run_cycles(1) {
+            Ok(true) => StopReason::Exited(self.runtime.record.public_values.exit_code),
+            Ok(false) if is_ebreak => StopReason::Trap,
+            Ok(false) => StopReason::Signal(SIGTRAP),
+            Err(err) => StopReason::Failed(err),
+        }
+    }
+
+    /// Execute until a breakpoint is reached, the program stops or GDB interrupts it.
+    fn resume(&mut self) -> io::Result<StopReason> {
+        let mut executed = 0u64;
+        loop {
+            let reason = self.step();
+            if !matches!(reason, StopReason::Signal(_))
+                || self.breakpoints.contains(&self.runtime.state.pc)
+            {
+                return Ok(reason);
+            }
+            executed += 1;
+            if executed % INTERRUPT_CHECK_CYCLES == 0 && self.interrupted()? {
+                return Ok(StopReason::Signal(SIGINT));
+            }
+        }
+    }
+
+    /// Whether GDB has sent an interrupt since the program was resumed.
+    fn interrupted(&mut self) -> io::Result<bool> {
+        self.stream.set_nonblocking(true)?;
+        let mut byte = [0u8; 1];
+        let result = match self.stream.read(&mut byte) {
+            Ok(1) => Ok(byte[0] == 0x03),
+            Ok(_) => Ok(false),
+            Err(err) if err.kind() == ErrorKind::WouldBlock => Ok(false),
+            Err(err) => Err(err),
+        };
+        self.stream.set_nonblocking(false)?;
+        result
+    }
+
+    fn stop_reply(&mut self, reason: StopReason) -> String {
+        match reason {
+            StopReason::Signal(signal) => format!("S{:02x}", signal),
+            StopReason::Trap => format!("S{:02x}", SIGTRAP),
+            StopReason::Exited(exit_code) => format!("W{:02x}", exit_code as u8),
+            StopReason::Failed(err) => {
+                tracing::error!("program failed: {}", err);
+                // Show the error in the debugger before reporting that the program terminated.
+                let message = hex_encode(format!("{}\n", err).as_bytes());
+                if let Err(err) = self.write_packet(&format!("O{}", message)) {
+                    tracing::error!("failed to send the error to gdb: {}", err);
+                }
+                let signal = match err {
+                    ExecutionError::Breakpoint { .. } => SIGTRAP,
+                    ExecutionError::Unimplemented { .. } => SIGILL,
+                    ExecutionError::UnalignedMemoryAccess { .. }
+                    | ExecutionError::InvalidMemoryAccess { .. } => SIGSEGV,
+                    _ => SIGABRT,
+                };
+                format!("X{:02x}", signal)
+            }
+        }
+    }
+
+    fn query(&self, query: &str) -> String {
+        if query.starts_with("Supported") {
+            format!("PacketSize={:x};qXfer:features:read+", PACKET_SIZE)
+        } else if query == "Attached" {
+            "1".to_string()
+        } else if query == "C" {
+            "QC1".to_string()
+        } else if query == "fThreadInfo" {
+            "m1".to_string()
+        } else if query == "sThreadInfo" {
+            "l".to_string()
+        } else if let Some(args) = query.strip_prefix("Xfer:features:read:target.xml:") {
+            let Some((offset, len)) = parse_hex_pair(args) else {
+                return "E01".to_string();
+            };
+            let xml = target_xml();
+            let start = (offset as usize).min(xml.len());
+            let end = (start + len as usize).min(xml.len());
+            let prefix = if end == xml.len() { 'l' } else { 'm' };
+            format!("{}{}", prefix, &xml[start..end])
+        } else {
+            String::new()
+        }
+    }
+}
+
+/// The target description of a 32-bit RISC-V machine with only the integer registers.
+fn target_xml() -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\"?><!DOCTYPE target SYSTEM \"gdb-target.dtd\"><target version=\"1.0\">\
+         <architecture>riscv:rv32</architecture><feature name=\"org.gnu.gdb.riscv.cpu\">",
+    );
+    for (i, name) in REGISTER_NAMES.iter().enumerate() {
+        let ty = match *name {
+            "sp" | "fp" => "data_ptr",
+            "ra" => "code_ptr",
+            _ => "int",
+        };
+        xml += &format!(
+            "<reg name=\"{}\" bitsize=\"32\" type=\"{}\" regnum=\"{}\"/>",
+            name, ty, i
+        );
+    }
+    xml += "<reg name=\"pc\" bitsize=\"32\" type=\"code_ptr\" regnum=\"32\"/></feature></target>";
+    xml
+}
+
+/// Parse arguments of the form `<hex>,<hex>`.
+fn parse_hex_pair(args: &str) -> Option<(u32, u32)> {
+    let (first, second) = args.split_once(',')?;
+    Some((
+        u32::from_str_radix(first, 16).ok()?,
+        u32::from_str_radix(second, 16).ok()?,
+    ))
+}
+
+fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::thread;
+
+    use super::PACKET_SIZE;
+    use crate::runtime::{Instruction, Opcode, Program, Register, Runtime};
+
+    /// Send a packet and return the response, skipping acknowledgements.
+    fn request(stream: &mut TcpStream, data: &str) -> String {
+        let checksum = data.bytes().fold(0u8, |sum, byte| sum.wrapping_add(byte));
+        write!(stream, "${}#{:02x}", data, checksum).unwrap();
+        let mut byte = [0u8; 1];
+        loop {
+            stream.read_exact(&mut byte).unwrap();
+            if byte[0] == b'$' {
+                break;
+            }
+        }
+        let mut response = Vec::new();
+        loop {
+            stream.read_exact(&mut byte).unwrap();
+            if byte[0] == b'#' {
+                break;
+            }
+            response.push(byte[0]);
+        }
+        let mut checksum = [0u8; 2];
+        stream.read_exact(&mut checksum).unwrap();
+        String::from_utf8(response).unwrap()
+    }
+
+    #[test]
+    fn test_gdb_session() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 0xdeadbeef, false, true),
+            Instruction::new(Opcode::SW, 30, 29, 0, false, true),
+            Instruction::new(Opcode::ADD, 31, 30, 0, false, true),
+            Instruction::new(Opcode::ADD, 28, 0, 1, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            assert_eq!(request(&mut stream, "?"), "S05");
+            assert_eq!(request(&mut stream, "Z0,c,4"), "OK");
+            assert_eq!(request(&mut stream, "c"), "S05");
+            assert_eq!(request(&mut stream, "p20"), "0c000000");
+            assert_eq!(request(&mut stream, "m27654320,4"), "efbeadde");
+            assert_eq!(request(&mut stream, "m0,ffffffff").len(), PACKET_SIZE);
+            let registers = request(&mut stream, "g");
+            assert_eq!(registers.len(), 33 * 8);
+            assert_eq!(&registers[30 * 8..31 * 8], "efbeadde");
+            assert_eq!(request(&mut stream, "z0,c,4"), "OK");
+            assert_eq!(request(&mut stream, "s"), "S05");
+            assert_eq!(request(&mut stream, "c"), "W00");
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        runtime.debug_connection(stream).unwrap();
+        client.join().unwrap();
+        assert_eq!(runtime.register(Register::X31), 0xdeadbeef);
+    }
+
+    #[test]
+    fn test_gdb_ebreak() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::EBREAK, 0, 0, 0, false, false),
+            Instruction::new(Opcode::ADD, 30, 29, 1, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            assert_eq!(request(&mut stream, "c"), "S05");
+            assert_eq!(request(&mut stream, "p20"), "08000000");
+            assert_eq!(request(&mut stream, "c"), "W00");
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        runtime.debug_connection(stream).unwrap();
+        client.join().unwrap();
+        assert_eq!(runtime.register(Register::X30), 6);
+    }
+
+    #[test]
+    fn test_gdb_invalid_checksum() {
+        let instructions = vec![Instruction::new(Opcode::ADD, 29, 0, 5, false, true)];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            stream.write_all(b"$p20#00").unwrap();
+            let mut byte = [0u8; 1];
+            stream.read_exact(&mut byte).unwrap();
+            assert_eq!(byte[0], b'-');
+            assert_eq!(request(&mut stream, "p20"), "00000000");
+            assert_eq!(request(&mut stream, "c"), "W00");
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        runtime.debug_connection(stream).unwrap();
+        client.join().unwrap();
+    }
+}
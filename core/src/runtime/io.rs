@@ -2,7 +2,9 @@ use serde::de::DeserializeOwned;
 use serde::Serialize;
 use std::io::Read;
 
-use super::Runtime;
+use super::{ExecutionError, Runtime};
+use crate::syscall::{FD_HINT, FD_HOST_RESPONSE, FD_PUBLIC_INPUT};
+use crate::SP1Stdout;
 
 impl Read for Runtime {
     fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
@@ -22,6 +24,68 @@ impl Runtime {
         self.state.input_stream.extend(input);
     }
 
+    pub fn write_public_stdin<T: Serialize>(&mut self, input: &T) {
+        let mut buf = Vec::new();
+        bincode::serialize_into(&mut buf, input).expect("serialization failed");
+        self.state.public_input_stream.extend(buf);
+    }
+
+    pub fn write_public_stdin_slice(&mut self, input: &[u8]) {
+        self.state.public_input_stream.extend(input);
+    }
+
+    /// Set an environment variable which the program can read with `std::env::var`.
+    pub fn set_env(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.state.env.insert(key.into(), value.into());
+    }
+
+    /// Set the seed of the random number generator used by `getrandom` in the program.
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.state.rng_seed = seed;
+    }
+
+    /// Read the next `num_bytes` bytes which the program requested from `fd`, i.e. from the public
+    /// input stream for `FD_PUBLIC_INPUT`, from the hint stream for `FD_HINT`, from the response of
+    /// the last host call for `FD_HOST_RESPONSE` and from the private input stream otherwise.
+    pub(crate) fn read_input(
+        &mut self,
+        fd: u32,
+        num_bytes: usize,
+    ) -> Result<Vec<u8>, ExecutionError> {
+        let state = &mut self.state;
+        let (stream, stream_ptr) = match fd {
+            FD_PUBLIC_INPUT => (
+                &state.public_input_stream,
+                &mut state.public_input_stream_ptr,
+            ),
+            FD_HINT => (&state.hint_stream, &mut state.hint_stream_ptr),
+            FD_HOST_RESPONSE => (&state.host_response, &mut state.host_response_ptr),
+            _ => (&state.input_stream, &mut state.input_stream_ptr),
+        };
+        if *stream_ptr + num_bytes > stream.len() {
+            return Err(ExecutionError::InputExhausted {
+                pc: state.pc,
+                clk: state.global_clk,
+                fd,
+            });
+        }
+        let bytes = stream[*stream_ptr..*stream_ptr + num_bytes].to_vec();
+        *stream_ptr += num_bytes;
+        Ok(bytes)
+    }
+
+    /// The output of the program, together with the exit code it halted with and the text it
+    /// wrote to stdout and stderr.
+    pub fn stdout(&self) -> SP1Stdout {
+        let mut stdout = SP1Stdout::from(&self.state.output_stream);
+        stdout.exit_code = self.record.public_values.exit_code;
+        stdout.program_stdout = self.program_stdout.clone();
+        stdout.program_stderr = self.program_stderr.clone();
+        stdout.rng_seed = self.state.rng_seed;
+        stdout.panic = self.panic.clone();
+        stdout
+    }
+
     pub fn read_stdout<T: DeserializeOwned>(&mut self) -> T {
         let result = bincode::deserialize_from::<_, T>(self);
         result.unwrap()
@@ -42,7 +106,7 @@ pub mod tests {
     use super::*;
     use crate::runtime::Program;
     use crate::utils::tests::IO_ELF;
-    use crate::utils::{self, prove_core, BabyBearBlake3};
+    use crate::utils::{self, prove_core, BabyBearBlake3, ProverOptions};
     use serde::Deserialize;
 
     #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -75,7 +139,7 @@ pub mod tests {
         let points = points();
         runtime.write_stdin(&points.0);
         runtime.write_stdin(&points.1);
-        runtime.run();
+        runtime.run().unwrap();
         let added_point = runtime.read_stdout::<MyPointUnaligned>();
         assert_eq!(
             added_point,
@@ -95,8 +159,8 @@ pub mod tests {
         let points = points();
         runtime.write_stdin(&points.0);
         runtime.write_stdin(&points.1);
-        runtime.run();
+        runtime.run().unwrap();
         let config = BabyBearBlake3::new();
-        prove_core(config, runtime);
+        prove_core(config, runtime, &ProverOptions::default()).unwrap();
     }
 }
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+/// Limits on the resources a program may use during execution.
+///
+/// By default, execution is unbounded. Exceeding a limit makes `Runtime::run` return
+/// `ExecutionError::CycleLimitExceeded` or `ExecutionError::MemoryLimitExceeded`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExecutionLimits {
+    /// The maximum number of instructions the program may execute.
+    pub max_cycles: Option<u64>,
+
+    /// The maximum number of distinct memory words, including registers, the program may touch.
+    ///
+    /// Words of the program's memory image only count once the program accesses them.
+    pub max_memory_words: Option<usize>,
+}
+
+impl ExecutionLimits {
+    /// Limits without any bound on cycles or memory.
+    pub fn unbounded() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of instructions the program may execute.
+    pub fn with_max_cycles(mut self, max_cycles: u64) -> Self {
+        self.max_cycles = Some(max_cycles);
+        self
+    }
+
+    /// Sets the maximum number of distinct memory words the program may touch.
+    pub fn with_max_memory_words(mut self, max_memory_words: usize) -> Self {
+        self.max_memory_words = Some(max_memory_words);
+        self
+    }
+}
@@ -1,22 +1,33 @@
+mod call_stack;
+mod error;
+mod gdb;
 mod instruction;
 mod io;
+mod limits;
 mod opcode;
+mod profiler;
 mod program;
 mod record;
 mod register;
+mod snapshot;
 mod state;
 mod syscall;
 
 use crate::cpu::{MemoryReadRecord, MemoryRecord, MemoryWriteRecord};
-use crate::utils::env;
+use crate::utils::{CancellationToken, ProverEvent, ProverObserver, ProverOptions};
 use crate::{alu::AluEvent, cpu::CpuEvent};
+pub use call_stack::*;
+pub use error::*;
 use hashbrown::hash_map::Entry;
 pub use instruction::*;
+pub use limits::*;
 use nohash_hasher::BuildNoHashHasher;
 pub use opcode::*;
+pub use profiler::*;
 pub use program::*;
 pub use record::*;
 pub use register::*;
+pub use snapshot::*;
 pub use state::*;
 use std::collections::HashMap;
 use std::fs::File;
@@ -67,26 +78,82 @@ pub struct Runtime {
     /// A buffer for stdout and stderr IO.
     pub io_buf: HashMap<u32, String>,
 
+    /// The text the program wrote to stdout.
+    pub program_stdout: String,
+
+    /// The text the program wrote to stderr.
+    pub program_stderr: String,
+
+    /// The last output the program wrote to stderr, which is reported if it halts with a non-zero
+    /// exit code.
+    pub(crate) last_stderr: Option<String>,
+
+    /// The error describing why the program halted with a non-zero exit code, if it did so while
+    /// the runtime was not set to fail on panics.
+    pub panic: Option<ExecutionError>,
+
+    /// The writer for the program's stdout, which is printed line by line if unset.
+    pub(crate) stdout_sink: Option<Box<dyn Write>>,
+
+    /// The writer for the program's stderr, which is printed line by line if unset.
+    pub(crate) stderr_sink: Option<Box<dyn Write>>,
+
     /// A buffer for writing trace events to a file.
     pub trace_buf: Option<BufWriter<File>>,
 
+    /// A profiler which attributes cycles to the function stacks of the program.
+    pub profiler: Option<Profiler>,
+
+    /// The functions the program is executing, if it has symbols.
+    pub call_stack: CallStack,
+
     /// Whether the runtime should fail on panic or not.
     pub fail_on_panic: bool,
 
+    /// The limits on the cycles and memory the program may use.
+    pub limits: ExecutionLimits,
+
+    /// The observer which receives the number of executed cycles after each shard, if any.
+    pub(crate) observer: Option<Arc<dyn ProverObserver>>,
+
+    /// The token which aborts the execution with `ExecutionError::Cancelled` once cancelled. It
+    /// is checked after each shard.
+    pub(crate) cancellation_token: CancellationToken,
+
     /// Whether the runtime is in constrained mode or not.
     /// In unconstrained mode, any events, clock, register, or memory changes are reset after leaving
-    /// the unconstrained block. The only thing preserved is writes to the input stream.
+    /// the unconstrained block. The only thing preserved is writes to the hint stream.
     pub unconstrained: bool,
 
     pub(crate) unconstrained_state: ForkState,
 
     pub syscall_map: HashMap<SyscallCode, Rc<dyn Syscall>>,
+
+    /// The syscalls which are not part of the runtime, keyed by their syscall number.
+    ///
+    /// This contains the precompiles of the `ProverOptions` the runtime was created with.
+    pub custom_syscall_map: HashMap<u32, Rc<dyn Syscall>>,
+
+    /// The callbacks which the program can invoke with the `HOST_CALL` syscall, keyed by their tag.
+    pub(crate) host_callbacks: HashMap<u32, Box<dyn FnMut(&[u8]) -> Vec<u8>>>,
+
+    /// Whether `EBREAK` traps to an attached debugger, which resumes after it, instead of failing
+    /// with `ExecutionError::Breakpoint`.
+    pub(crate) trap_on_ebreak: bool,
 }
 
 impl Runtime {
     // Create a new runtime
     pub fn new(program: Program) -> Self {
-        let program_arc = Arc::new(program);
+        Self::with_options(program, &ProverOptions::default())
+    }
+
+    /// Create a new runtime which splits the execution into shards of `options.shard_size` cycles.
+    pub fn with_options(program: Program, options: &ProverOptions) -> Self {
+        Self::with_program(Arc::new(program), options)
+    }
+
+    fn with_program(program_arc: Arc<Program>, options: &ProverOptions) -> Self {
         let record = ExecutionRecord {
             program: program_arc.clone(),
             ..Default::default()
@@ -98,23 +165,74 @@ impl Runtime {
         } else {
             None
         };
+        let profiler = options
+            .profile_file
+            .as_ref()
+            .map(|profile_file| Profiler::new().with_output(profile_file.clone()));
 
         Self {
             record,
             state: ExecutionState::new(program_arc.pc_start),
             program: program_arc,
             cpu_record: CpuRecord::default(),
-            shard_size: env::shard_size() as u32 * 4,
+            shard_size: options.shard_size as u32 * 4,
             cycle_tracker: HashMap::new(),
             io_buf: HashMap::new(),
+            program_stdout: String::new(),
+            program_stderr: String::new(),
+            last_stderr: None,
+            panic: None,
+            stdout_sink: None,
+            stderr_sink: None,
             trace_buf,
-            fail_on_panic: true,
+            profiler,
+            call_stack: CallStack::default(),
+            fail_on_panic: !options.allow_nonzero_exit,
+            limits: options.limits,
+            observer: options.observer.clone(),
+            cancellation_token: options.cancellation_token.clone(),
             unconstrained: false,
             unconstrained_state: ForkState::default(),
             syscall_map: default_syscall_map(),
+            custom_syscall_map: options.precompiles.syscall_map(),
+            host_callbacks: options
+                .host_callbacks
+                .iter()
+                .map(|(tag, callback)| {
+                    let callback = callback.clone();
+                    let callback: Box<dyn FnMut(&[u8]) -> Vec<u8>> =
+                        Box::new(move |request| (&mut *callback.lock().unwrap())(request));
+                    (*tag, callback)
+                })
+                .collect(),
+            trap_on_ebreak: false,
         }
     }
 
+    /// Send the program's stdout to `writer` instead of printing it with a `[stdout]` prefix.
+    pub fn redirect_stdout(&mut self, writer: impl Write + 'static) {
+        self.stdout_sink = Some(Box::new(writer));
+    }
+
+    /// Send the program's stderr to `writer` instead of printing it with a `[stderr]` prefix.
+    pub fn redirect_stderr(&mut self, writer: impl Write + 'static) {
+        self.stderr_sink = Some(Box::new(writer));
+    }
+
+    /// Register a callback which the program can invoke with the `HOST_CALL` syscall and `tag`.
+    ///
+    /// The callback receives the bytes of the request and the program reads its response through
+    /// `FD_HOST_RESPONSE`. The callbacks set with `ProverOptions::with_host_callback`
+    /// are registered when the runtime is created. Callbacks are not part of snapshots, so they must
+    /// be registered again on a resumed runtime.
+    pub fn register_host_callback(
+        &mut self,
+        tag: u32,
+        callback: impl FnMut(&[u8]) -> Vec<u8> + 'static,
+    ) {
+        self.host_callbacks.insert(tag, Box::new(callback));
+    }
+
     /// Get the current values of the registers.
     pub fn registers(&self) -> [u32; 32] {
         let mut registers = [0; 32];
@@ -163,24 +281,35 @@ impl Runtime {
         addr - addr % 4
     }
 
+    /// Checks that the program accesses memory at a valid address with the given alignment.
     #[inline]
-    #[cfg(debug_assertions)]
-    fn validate_memory_access(&self, addr: u32, position: AccessPosition) {
+    pub(crate) fn validate_memory_access(
+        &self,
+        addr: u32,
+        alignment: u32,
+    ) -> Result<(), ExecutionError> {
         use p3_baby_bear::BabyBear;
-        use p3_field::AbstractField;
+        use p3_field::PrimeField64;
 
-        if position == AccessPosition::Memory {
-            assert_eq!(addr % 4, 0, "addr is not aligned");
-            let _ = BabyBear::from_canonical_u32(addr);
-            assert!(addr > 40); // Assert that the address is > the max register.
-        } else {
-            let _ = Register::from_u32(addr);
+        if addr % alignment != 0 {
+            return Err(ExecutionError::UnalignedMemoryAccess {
+                pc: self.state.pc,
+                clk: self.state.global_clk,
+                addr,
+            });
+        }
+        // The address must be above the registers and must fit in the field.
+        let aligned_addr = self.align(addr);
+        if aligned_addr <= 40 || aligned_addr as u64 >= BabyBear::ORDER_U64 {
+            return Err(ExecutionError::InvalidMemoryAccess {
+                pc: self.state.pc,
+                clk: self.state.global_clk,
+                addr,
+            });
         }
+        Ok(())
     }
 
-    #[cfg(not(debug_assertions))]
-    fn validate_memory_access(&self, _addr: u32, _position: AccessPosition) {}
-
     pub fn mr(&mut self, addr: u32, shard: u32, clk: u32) -> MemoryReadRecord {
         // Get the memory entry.
         let memory_entry = self.state.memory.entry(addr);
@@ -201,6 +330,9 @@ impl Runtime {
         // Get the last time this memory address was accessed, and then update with current clock.
         let (value, prev_shard, prev_timestamp) = *entry_value;
         (entry_value.1, entry_value.2) = (shard, clk);
+        if (prev_shard, prev_timestamp) == (0, 0) {
+            self.state.touched_memory_words += 1;
+        }
 
         MemoryReadRecord::new(value, shard, clk, prev_shard, prev_timestamp)
     }
@@ -225,13 +357,14 @@ impl Runtime {
         // Get previous values and then update with new values.
         let (prev_value, prev_shard, prev_timestamp) = *entry_value;
         *entry_value = (value, shard, clk);
+        if (prev_shard, prev_timestamp) == (0, 0) {
+            self.state.touched_memory_words += 1;
+        }
         MemoryWriteRecord::new(value, shard, clk, prev_value, prev_shard, prev_timestamp)
     }
 
     /// Read from memory, assuming that all addresses are aligned.
     pub fn mr_cpu(&mut self, addr: u32, position: AccessPosition) -> u32 {
-        self.validate_memory_access(addr, position);
-
         let record = self.mr(
             addr,
             self.current_shard(),
@@ -251,8 +384,6 @@ impl Runtime {
 
     /// Write to memory.
     pub fn mw_cpu(&mut self, addr: u32, value: u32, position: AccessPosition) {
-        self.validate_memory_access(addr, position);
-
         let record = self.mw(
             addr,
             value,
@@ -398,26 +529,36 @@ impl Runtime {
         self.emit_alu(self.state.clk, instruction.opcode, a, b, c);
     }
 
-    /// Fetch the input operand values for a load instruction.
+    /// Fetch the input operand values for a load instruction of `alignment` bytes.
     #[inline(always)]
-    fn load_rr(&mut self, instruction: Instruction) -> (Register, u32, u32, u32, u32) {
+    fn load_rr(
+        &mut self,
+        instruction: Instruction,
+        alignment: u32,
+    ) -> Result<(Register, u32, u32, u32, u32), ExecutionError> {
         let (rd, rs1, imm) = instruction.i_type();
         let (b, c) = (self.rr(rs1, AccessPosition::B), imm);
         let addr = b.wrapping_add(c);
+        self.validate_memory_access(addr, alignment)?;
         let memory_value = self.mr_cpu(self.align(addr), AccessPosition::Memory);
-        (rd, b, c, addr, memory_value)
+        Ok((rd, b, c, addr, memory_value))
     }
 
-    /// Fetch the input operand values for a store instruction.
+    /// Fetch the input operand values for a store instruction of `alignment` bytes.
     #[inline(always)]
-    fn store_rr(&mut self, instruction: Instruction) -> (u32, u32, u32, u32, u32) {
+    fn store_rr(
+        &mut self,
+        instruction: Instruction,
+        alignment: u32,
+    ) -> Result<(u32, u32, u32, u32, u32), ExecutionError> {
         let (rs1, rs2, imm) = instruction.s_type();
         let c = imm;
         let b = self.rr(rs2, AccessPosition::B);
         let a = self.rr(rs1, AccessPosition::A);
         let addr = b.wrapping_add(c);
+        self.validate_memory_access(addr, alignment)?;
         let memory_value = self.word(self.align(addr));
-        (a, b, c, addr, memory_value)
+        Ok((a, b, c, addr, memory_value))
     }
 
     /// Fetch the input operand values for a branch instruction.
@@ -437,20 +578,24 @@ impl Runtime {
         self.program.instructions[idx]
     }
 
-    fn get_syscall(&mut self, code: SyscallCode) -> Option<&Rc<dyn Syscall>> {
-        self.syscall_map.get(&code)
+    fn get_syscall(&mut self, syscall_id: u32) -> Option<&Rc<dyn Syscall>> {
+        match SyscallCode::from_u32(syscall_id) {
+            Some(code) => self.syscall_map.get(&code),
+            None => self.custom_syscall_map.get(&syscall_id),
+        }
     }
 
     fn max_syscall_cycles(&self) -> u32 {
         self.syscall_map
             .values()
+            .chain(self.custom_syscall_map.values())
             .map(|syscall| syscall.num_extra_cycles())
             .max()
             .unwrap_or(0)
     }
 
     /// Execute the given instruction over the current state of the runtime.
-    fn execute(&mut self, instruction: Instruction) {
+    fn execute(&mut self, instruction: Instruction) -> Result<(), ExecutionError> {
         let pc = self.state.pc;
         let mut next_pc = self.state.pc.wrapping_add(4);
 
@@ -515,15 +660,14 @@ impl Runtime {
 
             // Load instructions.
             Opcode::LB => {
-                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction);
+                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction, 1)?;
                 let value = (memory_read_value).to_le_bytes()[(addr % 4) as usize];
                 a = ((value as i8) as i32) as u32;
                 memory_store_value = Some(memory_read_value);
                 self.rw(rd, a);
             }
             Opcode::LH => {
-                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction);
-                assert_eq!(addr % 2, 0, "addr is not aligned");
+                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction, 2)?;
                 let value = match (addr >> 1) % 2 {
                     0 => memory_read_value & 0x0000FFFF,
                     1 => (memory_read_value & 0xFFFF0000) >> 16,
@@ -534,22 +678,20 @@ impl Runtime {
                 self.rw(rd, a);
             }
             Opcode::LW => {
-                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction);
-                assert_eq!(addr % 4, 0, "addr is not aligned");
+                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction, 4)?;
                 a = memory_read_value;
                 memory_store_value = Some(memory_read_value);
                 self.rw(rd, a);
             }
             Opcode::LBU => {
-                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction);
+                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction, 1)?;
                 let value = (memory_read_value).to_le_bytes()[(addr % 4) as usize];
                 a = value as u32;
                 memory_store_value = Some(memory_read_value);
                 self.rw(rd, a);
             }
             Opcode::LHU => {
-                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction);
-                assert_eq!(addr % 2, 0, "addr is not aligned");
+                (rd, b, c, addr, memory_read_value) = self.load_rr(instruction, 2)?;
                 let value = match (addr >> 1) % 2 {
                     0 => memory_read_value & 0x0000FFFF,
                     1 => (memory_read_value & 0xFFFF0000) >> 16,
@@ -562,7 +704,7 @@ impl Runtime {
 
             // Store instructions.
             Opcode::SB => {
-                (a, b, c, addr, memory_read_value) = self.store_rr(instruction);
+                (a, b, c, addr, memory_read_value) = self.store_rr(instruction, 1)?;
                 let value = match addr % 4 {
                     0 => (a & 0x000000FF) + (memory_read_value & 0xFFFFFF00),
                     1 => ((a & 0x000000FF) << 8) + (memory_read_value & 0xFFFF00FF),
@@ -574,8 +716,7 @@ impl Runtime {
                 self.mw_cpu(self.align(addr), value, AccessPosition::Memory);
             }
             Opcode::SH => {
-                (a, b, c, addr, memory_read_value) = self.store_rr(instruction);
-                assert_eq!(addr % 2, 0, "addr is not aligned");
+                (a, b, c, addr, memory_read_value) = self.store_rr(instruction, 2)?;
                 let value = match (addr >> 1) % 2 {
                     0 => (a & 0x0000FFFF) + (memory_read_value & 0xFFFF0000),
                     1 => ((a & 0x0000FFFF) << 16) + (memory_read_value & 0x0000FFFF),
@@ -585,8 +726,7 @@ impl Runtime {
                 self.mw_cpu(self.align(addr), value, AccessPosition::Memory);
             }
             Opcode::SW => {
-                (a, b, c, addr, _) = self.store_rr(instruction);
-                assert_eq!(addr % 4, 0, "addr is not aligned");
+                (a, b, c, addr, _) = self.store_rr(instruction, 4)?;
                 let value = a;
                 memory_store_value = Some(value);
                 self.mw_cpu(self.align(addr), value, AccessPosition::Memory);
@@ -659,30 +799,42 @@ impl Runtime {
                 let t0 = Register::X5;
                 let a0 = Register::X10;
                 let syscall_id = self.register(t0);
-                let syscall = SyscallCode::from_u32(syscall_id);
+                let syscall_impl = self.get_syscall(syscall_id).cloned().ok_or(
+                    ExecutionError::UnsupportedSyscall {
+                        pc,
+                        clk: self.state.global_clk,
+                        syscall_id,
+                    },
+                )?;
 
                 let init_clk = self.state.clk;
-                let syscall_impl = self.get_syscall(syscall).cloned();
                 let mut precompile_rt = SyscallContext::new(self);
-
-                if let Some(syscall_impl) = syscall_impl {
-                    a = syscall_impl.execute(&mut precompile_rt);
-                    next_pc = precompile_rt.next_pc;
-                    self.state.clk = precompile_rt.clk;
-                    assert_eq!(init_clk + syscall_impl.num_extra_cycles(), self.state.clk);
-                } else {
-                    panic!("Unsupported syscall: {:?}", syscall);
-                }
+                a = syscall_impl.execute(&mut precompile_rt)?;
+                next_pc = precompile_rt.next_pc;
+                self.state.clk = precompile_rt.clk;
+                assert_eq!(init_clk + syscall_impl.num_extra_cycles(), self.state.clk);
 
                 // We have to do this AFTER the precompile execution because the CPU event
                 // gets emitted at the end of this loop with the incremented clock.
                 // TODO: fix this.
                 self.rw(a0, a);
-                (b, c) = (self.rr(t0, AccessPosition::B), 0);
+                c = if instruction.imm_c {
+                    instruction.op_c
+                } else {
+                    self.rr(Register::from_u32(instruction.op_c), AccessPosition::C)
+                };
+                b = self.rr(t0, AccessPosition::B);
             }
 
             Opcode::EBREAK => {
-                todo!()
+                if !self.trap_on_ebreak {
+                    return Err(ExecutionError::Breakpoint {
+                        pc,
+                        clk: self.state.global_clk,
+                    });
+                }
+                // The debugger stops after the instruction, so it resumes with the next one.
+                (a, b, c) = (0, 0, 0);
             }
 
             // Multiply instructions.
@@ -745,7 +897,10 @@ impl Runtime {
 
             Opcode::UNIMP => {
                 // See https://github.com/riscv-non-isa/riscv-asm-manual/blob/master/riscv-asm.md#instruction-aliases
-                panic!("UNIMP encountered, we should never get here.");
+                return Err(ExecutionError::Unimplemented {
+                    pc,
+                    clk: self.state.global_clk,
+                });
             }
         }
 
@@ -764,25 +919,101 @@ impl Runtime {
             memory_store_value,
             self.cpu_record,
         );
+        Ok(())
     }
 
-    /// Execute the program.
-    pub fn run(&mut self) {
-        tracing::info_span!("load memory").in_scope(|| {
-            // First load the memory image into the memory table.
-            for (addr, value) in self.program.memory_image.iter() {
-                self.state.memory.insert(*addr, (*value, 0, 0));
-            }
-        });
+    /// Execute the program, returning an error if the program performs an invalid operation.
+    pub fn run(&mut self) -> Result<(), ExecutionError> {
+        let finished = self.run_cycles(u64::MAX)?;
+        debug_assert!(finished);
+        Ok(())
+    }
 
-        let max_syscall_cycles = self.max_syscall_cycles();
+    /// Execute at most `cycles` instructions of the program, starting from the current state.
+    ///
+    /// Returns `true` once the program has finished. Otherwise the runtime is paused and can be
+    /// snapshotted or run further. Execution never pauses inside an unconstrained block, so it may
+    /// exceed `cycles` until the block is exited.
+    pub fn run_cycles(&mut self, cycles: u64) -> Result<bool, ExecutionError> {
+        self.execute_cycles(cycles, false)
+    }
 
-        self.state.clk += 1;
-        while self.state.pc.wrapping_sub(self.program.pc_base)
+    /// Execute the program until its current shard is finished and return the events of the shard.
+    ///
+    /// Returns `None` once the program has finished and all of its shards have been returned. The
+    /// memory records of the whole execution are part of the last shard.
+    pub fn execute_shard(&mut self) -> Result<Option<ExecutionRecord>, ExecutionError> {
+        if !self.is_running() {
+            return Ok(None);
+        }
+        self.execute_cycles(u64::MAX, true)?;
+        let next_record = ExecutionRecord {
+            program: self.program.clone(),
+            public_values: self.record.public_values,
+            ..Default::default()
+        };
+        Ok(Some(std::mem::replace(&mut self.record, next_record)))
+    }
+
+    /// Report the number of cycles executed so far to the observer, if any.
+    fn observe_executed(&self) {
+        if let Some(observer) = &self.observer {
+            observer.on_event(&ProverEvent::Executed {
+                cycles: self.state.global_clk as u64,
+            });
+        }
+    }
+
+    /// Load the memory image of the program, unless execution has already started.
+    fn initialize(&mut self) {
+        // The clock is only zero before the first instruction, since it starts at one.
+        if self.state.global_clk == 0 && self.state.clk == 0 {
+            tracing::info_span!("load memory").in_scope(|| {
+                // First load the memory image into the memory table.
+                for (addr, value) in self.program.memory_image.iter() {
+                    self.state.memory.insert(*addr, (*value, 0, 0));
+                }
+            });
+            self.state.clk += 1;
+        }
+    }
+
+    /// Whether the program counter is still within the program.
+    fn is_running(&self) -> bool {
+        self.state.pc.wrapping_sub(self.program.pc_base)
             < (self.program.instructions.len() * 4) as u32
-        {
+    }
+
+    /// Execute at most `cycles` instructions, pausing early once the current shard is finished if
+    /// `stop_at_shard` is set. Returns whether the program has finished.
+    fn execute_cycles(&mut self, cycles: u64, stop_at_shard: bool) -> Result<bool, ExecutionError> {
+        self.initialize();
+
+        let max_syscall_cycles = self.max_syscall_cycles();
+
+        let start_shard = self.state.current_shard;
+        let mut executed = 0;
+        while self.is_running() {
+            if !self.unconstrained
+                && (executed >= cycles
+                    || (stop_at_shard && self.state.current_shard != start_shard))
+            {
+                return Ok(false);
+            }
+
+            if let Some(max_cycles) = self.limits.max_cycles {
+                if self.state.global_clk as u64 >= max_cycles {
+                    return Err(ExecutionError::CycleLimitExceeded {
+                        pc: self.state.pc,
+                        clk: self.state.global_clk,
+                        max_cycles,
+                    });
+                }
+            }
+
             // Fetch the instruction at the current program counter.
             let instruction = self.fetch();
+            let pc = self.state.pc;
 
             if let Some(ref mut buf) = self.trace_buf {
                 if !self.unconstrained {
@@ -818,22 +1049,56 @@ impl Runtime {
             );
 
             // Execute the instruction.
-            self.execute(instruction);
+            self.execute(instruction)?;
+
+            if !self.unconstrained {
+                if let Some(ref mut profiler) = self.profiler {
+                    profiler.record(&self.program, pc, self.state.pc, &instruction);
+                }
+                if !self.program.symbols.is_empty() {
+                    self.call_stack
+                        .record(&self.program, pc, self.state.pc, &instruction);
+                }
+            }
+
+            if let Some(max_memory_words) = self.limits.max_memory_words {
+                if self.state.touched_memory_words > max_memory_words {
+                    return Err(ExecutionError::MemoryLimitExceeded {
+                        pc,
+                        clk: self.state.global_clk,
+                        max_memory_words,
+                    });
+                }
+            }
 
             // Increment the clock.
             self.state.global_clk += 1;
             self.state.clk += 4;
+            executed += 1;
 
             // If there's not enough cycles left for another instruction, move to the next shard.
             // We multiply by 4 because clk is incremented by 4 for each normal instruction.
             if !self.unconstrained && max_syscall_cycles + self.state.clk >= self.shard_size * 4 {
                 self.state.current_shard += 1;
                 self.state.clk = 0;
+                self.observe_executed();
+                if self.cancellation_token.is_cancelled() {
+                    return Err(ExecutionError::Cancelled {
+                        pc: self.state.pc,
+                        clk: self.state.global_clk,
+                    });
+                }
             }
         }
+        self.observe_executed();
         if let Some(ref mut buf) = self.trace_buf {
             buf.flush().unwrap();
         }
+        if let Some(ref profiler) = self.profiler {
+            if let Err(err) = profiler.flush(&self.program) {
+                tracing::error!("failed to write the profile: {}", err);
+            }
+        }
         // Flush remaining stdout/stderr
         for (fd, buf) in self.io_buf.iter() {
             if !buf.is_empty() {
@@ -848,10 +1113,72 @@ impl Runtime {
                 }
             }
         }
+        for sink in [&mut self.stdout_sink, &mut self.stderr_sink]
+            .into_iter()
+            .flatten()
+        {
+            if let Err(err) = sink.flush() {
+                tracing::error!("failed to write the program output: {}", err);
+            }
+        }
 
         // Call postprocess to set up all variables needed for global accounts, like memory
         // argument or any other deferred tables.
         tracing::info_span!("postprocess").in_scope(|| self.postprocess());
+        Ok(true)
+    }
+
+    /// Take a snapshot of a paused runtime, from which execution can be resumed later.
+    ///
+    /// The runtime must not be inside an unconstrained block, which `Runtime::run_cycles` ensures.
+    pub fn snapshot(&self) -> Result<RuntimeSnapshot, SnapshotError> {
+        if self.unconstrained {
+            return Err(SnapshotError::Unconstrained {
+                pc: self.state.pc,
+                clk: self.state.global_clk,
+            });
+        }
+        Ok(RuntimeSnapshot {
+            state: self.state.clone(),
+            record: self.record.clone(),
+            symbols: self.program.symbols.clone(),
+            shard_size: self.shard_size,
+            limits: self.limits,
+            fail_on_panic: self.fail_on_panic,
+            call_stack: self.call_stack.clone(),
+            cycle_tracker: self.cycle_tracker.clone(),
+            io_buf: self.io_buf.clone(),
+            program_stdout: self.program_stdout.clone(),
+            program_stderr: self.program_stderr.clone(),
+            last_stderr: self.last_stderr.clone(),
+            panic: self.panic.clone(),
+        })
+    }
+
+    /// Create a runtime which continues the execution captured by `snapshot`.
+    ///
+    /// The runtime has the shard size and limits of the snapshotted runtime. Output sinks, host
+    /// callbacks and debugger settings are not part of the snapshot and must be set again.
+    pub fn resume(mut snapshot: RuntimeSnapshot) -> Self {
+        // The symbols are not serialized with the program, so restore them on a loaded snapshot.
+        if snapshot.record.program.symbols.is_empty() && !snapshot.symbols.is_empty() {
+            Arc::make_mut(&mut snapshot.record.program).symbols = snapshot.symbols;
+        }
+        let mut runtime =
+            Runtime::with_program(snapshot.record.program.clone(), &ProverOptions::default());
+        runtime.state = snapshot.state;
+        runtime.record = snapshot.record;
+        runtime.shard_size = snapshot.shard_size;
+        runtime.limits = snapshot.limits;
+        runtime.fail_on_panic = snapshot.fail_on_panic;
+        runtime.call_stack = snapshot.call_stack;
+        runtime.cycle_tracker = snapshot.cycle_tracker;
+        runtime.io_buf = snapshot.io_buf;
+        runtime.program_stdout = snapshot.program_stdout;
+        runtime.program_stderr = snapshot.program_stderr;
+        runtime.last_stderr = snapshot.last_stderr;
+        runtime.panic = snapshot.panic;
+        runtime
     }
 
     fn postprocess(&mut self) {
@@ -927,10 +1254,17 @@ pub mod tests {
 
     use crate::{
         runtime::Register,
+        syscall::{FD_HINT, FD_HOST_RESPONSE, FD_PUBLIC_INPUT},
         utils::tests::{FIBONACCI_ELF, SSZ_WITHDRAWALS_ELF},
     };
 
-    use super::{Instruction, Opcode, Program, Runtime};
+    use super::{
+        CancellationToken, ExecutionError, ExecutionLimits, Instruction, Opcode, Program,
+        ProverEvent, ProverOptions, Runtime, RuntimeSnapshot,
+    };
+    use std::cell::RefCell;
+    use std::io::Write;
+    use std::rc::Rc;
 
     pub fn simple_program() -> Program {
         let instructions = vec![
@@ -961,10 +1295,674 @@ pub mod tests {
     fn test_simple_program_run() {
         let program = simple_program();
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 42);
     }
 
+    #[test]
+    fn test_public_input_run() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 101, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, FD_PUBLIC_INPUT, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 11, false, false),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.write_stdin_slice(&[1, 2, 3, 4]);
+        runtime.write_public_stdin_slice(&[5, 6, 7, 8]);
+        runtime.run().unwrap();
+        assert_eq!(
+            runtime.register(Register::X10),
+            u32::from_le_bytes([5, 6, 7, 8])
+        );
+        assert_eq!(runtime.state.input_stream_ptr, 0);
+        assert_eq!(runtime.state.public_input_stream_ptr, 4);
+    }
+
+    #[test]
+    fn test_host_call() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 0x04030201, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x27654320, false, true),
+            // Write a hint which the program has not read yet.
+            Instruction::new(Opcode::ADD, 5, 0, 999, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, FD_HINT, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            // Call the host with the same bytes and read the response.
+            Instruction::new(Opcode::ADD, 5, 0, 114, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 7, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 31, 10, 0, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, 101, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, FD_HOST_RESPONSE, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 11, false, false),
+        ];
+        let options = ProverOptions::default()
+            .with_host_callback(7, |request| request.iter().rev().copied().collect());
+        let mut runtime = Runtime::with_options(Program::new(instructions, 0, 0), &options);
+        runtime.write_stdin_slice(&[9, 9, 9, 9]);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 4);
+        assert_eq!(
+            runtime.register(Register::X10),
+            u32::from_le_bytes([4, 3, 2, 1])
+        );
+        assert_eq!(runtime.state.host_response, vec![4, 3, 2, 1]);
+        assert_eq!(runtime.state.hint_stream, vec![1, 2, 3, 4]);
+        assert_eq!(runtime.state.hint_stream_ptr, 0);
+        assert_eq!(runtime.state.input_stream_ptr, 0);
+
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 114, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 8, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionError::InvalidSyscallArgument {
+                syscall_id: 114,
+                ..
+            }
+        ));
+
+        // A request which wraps around the address space is rejected.
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 114, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 7, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0xfffffff0, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 0x100, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.register_host_callback(7, |request| request.to_vec());
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionError::InvalidSyscallArgument {
+                syscall_id: 114,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_unconstrained_hint() {
+        let instructions = vec![
+            // Write a hint inside an unconstrained block.
+            Instruction::new(Opcode::ADD, 5, 0, 110, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::BEQ, 10, 0, 40, false, true),
+            Instruction::new(Opcode::ADD, 29, 0, 0x04030201, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, 999, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, FD_HINT, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, 111, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            // Read the regular input and then the hint after the block.
+            Instruction::new(Opcode::ADD, 5, 0, 101, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 0, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 11, false, false),
+            Instruction::new(Opcode::ADD, 30, 10, 0, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, FD_HINT, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 11, false, false),
+            Instruction::new(Opcode::ADD, 31, 10, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.write_stdin_slice(&[9, 9, 9, 9]);
+        runtime.run().unwrap();
+
+        // The changes of the block are reverted, except for the hint.
+        assert_eq!(runtime.register(Register::X29), 0);
+        assert_eq!(runtime.word(0x27654320), 0);
+        assert_eq!(runtime.state.hint_stream, vec![1, 2, 3, 4]);
+        assert_eq!(runtime.register(Register::X30), 0x09090909);
+        assert_eq!(runtime.register(Register::X31), 0x04030201);
+        assert_eq!(runtime.state.input_stream_ptr, 4);
+        assert_eq!(runtime.state.hint_stream_ptr, 4);
+    }
+
+    #[test]
+    fn test_getenv() {
+        let instructions = |name_len| {
+            vec![
+                Instruction::new(Opcode::ADD, 29, 0, 0x004f4f46, false, true),
+                Instruction::new(Opcode::SW, 29, 0, 0x27654320, false, true),
+                // Write a hint which the program has not read yet.
+                Instruction::new(Opcode::ADD, 5, 0, 999, false, true),
+                Instruction::new(Opcode::ADD, 10, 0, FD_HINT, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, 0x27654320, false, true),
+                Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+                Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+                // Look up the variable.
+                Instruction::new(Opcode::ADD, 5, 0, 116, false, true),
+                Instruction::new(Opcode::ADD, 10, 0, 0x27654320, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, name_len, false, true),
+                Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+                Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+                Instruction::new(Opcode::ADD, 31, 10, 0, false, true),
+            ]
+        };
+
+        let mut program = instructions(3);
+        program.extend([
+            Instruction::new(Opcode::ADD, 5, 0, 101, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, FD_HOST_RESPONSE, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 11, false, false),
+        ]);
+        let mut runtime = Runtime::new(Program::new(program, 0, 0));
+        runtime.set_env("FOO", "bar=baz");
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 7);
+        assert_eq!(
+            runtime.register(Register::X10),
+            u32::from_le_bytes(*b"bar=")
+        );
+        assert_eq!(runtime.state.host_response, b"bar=");
+        assert_eq!(runtime.state.hint_stream, b"FOO\0");
+        assert_eq!(runtime.state.hint_stream_ptr, 0);
+
+        let mut runtime = Runtime::new(Program::new(instructions(2), 0, 0));
+        runtime.set_env("FOO", "bar=baz");
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), u32::MAX);
+        assert!(runtime.state.host_response.is_empty());
+    }
+
+    #[test]
+    fn test_rng_seed() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 117, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 0, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 31, 10, 0, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 1, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.set_rng_seed(0x1122334455667788);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X31), 0x55667788);
+        assert_eq!(runtime.register(Register::X10), 0x11223344);
+        assert_eq!(runtime.stdout().rng_seed, 0x1122334455667788);
+    }
+
+    #[test]
+    fn test_redirect_output() {
+        #[derive(Clone, Default)]
+        struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
+
+        impl Write for SharedBuffer {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.borrow_mut().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let instructions = vec![
+            Instruction::new(
+                Opcode::ADD,
+                29,
+                0,
+                u32::from_le_bytes(*b"hi!\n"),
+                false,
+                true,
+            ),
+            Instruction::new(Opcode::SW, 29, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, 999, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 1, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 2, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 2, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        let stdout = SharedBuffer::default();
+        runtime.redirect_stdout(stdout.clone());
+        runtime.run().unwrap();
+        assert_eq!(stdout.0.borrow().as_slice(), b"hi!\n");
+        assert_eq!(runtime.program_stdout, "hi!\n");
+        assert_eq!(runtime.program_stderr, "hi");
+
+        let output = runtime.stdout();
+        assert_eq!(output.program_stdout, "hi!\n");
+        assert_eq!(output.program_stderr, "hi");
+    }
+
+    #[test]
+    fn test_write_invalid_output() {
+        // Bytes which are not valid UTF-8 are written with replacement characters.
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 0xffff, false, true),
+            Instruction::new(Opcode::SW, 29, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, 999, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 2, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 2, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.redirect_stderr(std::io::sink());
+        runtime.run().unwrap();
+        assert_eq!(runtime.program_stderr, "\u{FFFD}\u{FFFD}");
+
+        // A buffer which wraps around the address space is rejected.
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 999, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 1, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0xfffffff0, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 0x100, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionError::InvalidSyscallArgument {
+                pc: 16,
+                syscall_id: 999,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_write_failing_output() {
+        struct FailingWriter;
+
+        impl Write for FailingWriter {
+            fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Err(std::io::ErrorKind::BrokenPipe.into())
+            }
+        }
+
+        // The output is still recorded when the writer fails, and the execution continues.
+        let instructions = vec![
+            Instruction::new(
+                Opcode::ADD,
+                29,
+                0,
+                u32::from_le_bytes(*b"hi!\n"),
+                false,
+                true,
+            ),
+            Instruction::new(Opcode::SW, 29, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, 999, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 1, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 1, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.redirect_stdout(FailingWriter);
+        runtime.run().unwrap();
+        assert_eq!(runtime.program_stdout, "hi!\nhi!\n");
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let instructions = vec![
+            Instruction::new(
+                Opcode::ADD,
+                29,
+                0,
+                u32::from_le_bytes(*b"boom"),
+                false,
+                true,
+            ),
+            Instruction::new(Opcode::SW, 29, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 5, 0, 999, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 2, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            // Call `syscall_halt`, which halts with exit code 1.
+            Instruction::new(Opcode::JAL, 1, 8, 0, true, true),
+            Instruction::new(Opcode::UNIMP, 0, 0, 0, true, true),
+            Instruction::new(Opcode::ADD, 5, 0, 100, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 1, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        // Halting jumps to pc 0, so the program must start elsewhere.
+        let mut program = Program::new(instructions, 0x1000, 0x1000);
+        program.symbols.insert(0x1000, "main".to_string());
+        program.symbols.insert(0x1024, "syscall_halt".to_string());
+        // The function which called `syscall_halt` is reported.
+        let expected = ExecutionError::NonZeroExitCode {
+            pc: 0x102c,
+            clk: 10,
+            exit_code: 1,
+            function: Some("main".to_string()),
+            message: Some("boom".to_string()),
+        };
+
+        let mut runtime = Runtime::new(program.clone());
+        assert_eq!(runtime.run().unwrap_err(), expected);
+        assert_eq!(
+            expected.to_string(),
+            "program halted with non-zero exit code 1 in `main`: boom (pc=0x102c, clk=10)"
+        );
+
+        let mut runtime = Runtime::new(program);
+        runtime.fail_on_panic = false;
+        runtime.run().unwrap();
+        assert_eq!(runtime.stdout().exit_code, 1);
+        assert_eq!(runtime.panic, Some(expected));
+    }
+
+    #[test]
+    fn test_snapshot_resume() {
+        let mut expected = Runtime::new(fibonacci_program());
+        expected.run().unwrap();
+
+        let mut program = fibonacci_program();
+        program.symbols.insert(program.pc_start, "main".to_string());
+        let options = ProverOptions::default().with_shard_size(1 << 10);
+        let limits = ExecutionLimits::default().with_max_cycles(1 << 30);
+        let mut runtime = Runtime::with_options(program, &options);
+        runtime.limits = limits;
+        assert!(!runtime.run_cycles(1000).unwrap());
+        assert_eq!(runtime.state.global_clk, 1000);
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        runtime.snapshot().unwrap().save(file.path()).unwrap();
+        let snapshot = RuntimeSnapshot::load(file.path()).unwrap();
+
+        let mut runtime = Runtime::resume(snapshot);
+        assert_eq!(runtime.shard_size, (1 << 10) * 4);
+        assert_eq!(runtime.limits, limits);
+        assert_eq!(
+            runtime.program.symbols.get(&runtime.program.pc_start),
+            Some(&"main".to_string())
+        );
+        assert!(runtime.run_cycles(u64::MAX).unwrap());
+        assert_eq!(runtime.state.global_clk, expected.state.global_clk);
+        assert_eq!(runtime.registers(), expected.registers());
+        assert_eq!(
+            runtime.record.cpu_events.len(),
+            expected.record.cpu_events.len()
+        );
+        assert_eq!(runtime.state.output_stream, expected.state.output_stream);
+    }
+
+    #[test]
+    fn test_profiler() {
+        let program = fibonacci_program();
+        assert!(!program.symbols.is_empty());
+        let mut runtime = Runtime::new(program);
+        runtime.profiler = Some(Profiler::new());
+        runtime.run().unwrap();
+
+        let profiler = runtime.profiler.as_ref().unwrap();
+        assert_eq!(profiler.total_cycles(), runtime.state.global_clk as u64);
+
+        let mut collapsed = Vec::new();
+        profiler
+            .write(&runtime.program, ProfileFormat::Collapsed, &mut collapsed)
+            .unwrap();
+        let collapsed = String::from_utf8(collapsed).unwrap();
+        let total = collapsed
+            .lines()
+            .map(|line| line.rsplit_once(' ').unwrap().1.parse::<u64>().unwrap())
+            .sum::<u64>();
+        assert_eq!(total, profiler.total_cycles());
+        assert!(collapsed.lines().any(|line| line
+            .rsplit_once(' ')
+            .unwrap()
+            .0
+            .split(';')
+            .any(|f| f == "main")));
+
+        let mut speedscope = Vec::new();
+        profiler
+            .write(&runtime.program, ProfileFormat::Speedscope, &mut speedscope)
+            .unwrap();
+        let speedscope: serde_json::Value = serde_json::from_slice(&speedscope).unwrap();
+        assert_eq!(
+            speedscope["profiles"][0]["endValue"].as_u64(),
+            Some(profiler.total_cycles())
+        );
+    }
+
+    #[test]
+    fn test_profile_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("profile.folded");
+        let options = ProverOptions::default().with_profile_file(&path);
+        let mut runtime = Runtime::with_options(fibonacci_program(), &options);
+        runtime.run().unwrap();
+        assert!(!std::fs::read_to_string(&path).unwrap().is_empty());
+
+        // A profile which cannot be written does not fail the execution.
+        let path = dir.path().join("missing").join("profile.folded");
+        let options = ProverOptions::default().with_profile_file(path);
+        let mut runtime = Runtime::with_options(fibonacci_program(), &options);
+        runtime.run().unwrap();
+    }
+
+    #[test]
+    fn test_execute_shard() {
+        let mut expected = Runtime::new(fibonacci_program());
+        expected.run().unwrap();
+
+        let options = ProverOptions::default().with_shard_size(1 << 8);
+        let mut runtime = Runtime::with_options(fibonacci_program(), &options);
+        assert_eq!(runtime.shard_size, 1 << 10);
+        let mut records = Vec::new();
+        while let Some(record) = runtime.execute_shard().unwrap() {
+            records.push(record);
+        }
+        assert!(records.len() > 1);
+        for (i, record) in records.iter().enumerate() {
+            let shard = i as u32 + 1;
+            assert!(record.cpu_events.iter().all(|event| event.shard == shard));
+        }
+        assert_eq!(
+            records.iter().map(|r| r.cpu_events.len()).sum::<usize>(),
+            expected.record.cpu_events.len()
+        );
+        let (last, rest) = records.split_last().unwrap();
+        assert!(rest.iter().all(|r| r.last_memory_record.is_empty()));
+        assert_eq!(
+            last.last_memory_record.len(),
+            expected.record.last_memory_record.len()
+        );
+    }
+
+    #[test]
+    fn test_unsupported_syscall() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 42, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        let err = runtime.run().unwrap_err();
+        assert_eq!(
+            err,
+            ExecutionError::UnsupportedSyscall {
+                pc: 4,
+                clk: 1,
+                syscall_id: 42
+            }
+        );
+    }
+
+    #[test]
+    fn test_input_exhausted() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 101, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, 4, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 11, false, false),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.write_stdin_slice(&[1, 2]);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(
+            err,
+            ExecutionError::InputExhausted { pc: 8, fd: 0, .. }
+        ));
+    }
+
+    #[test]
+    fn test_unaligned_memory_access() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::LW, 28, 29, 2, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        let err = runtime.run().unwrap_err();
+        assert_eq!(
+            err,
+            ExecutionError::UnalignedMemoryAccess {
+                pc: 4,
+                clk: 1,
+                addr: 0x27654322
+            }
+        );
+    }
+
+    #[test]
+    fn test_cycle_limit() {
+        // An infinite loop: jal x0, 0
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::JAL, 0, 0, 0, true, true),
+        ];
+        let limits = ExecutionLimits::default().with_max_cycles(100);
+        let options = ProverOptions::default().with_limits(limits);
+        let mut runtime = Runtime::with_options(Program::new(instructions, 0, 0), &options);
+        let err = runtime.run().unwrap_err();
+        assert_eq!(
+            err,
+            ExecutionError::CycleLimitExceeded {
+                pc: 4,
+                clk: 100,
+                max_cycles: 100
+            }
+        );
+    }
+
+    #[test]
+    fn test_cancelled() {
+        // An infinite loop: jal x0, 0
+        let instructions = vec![Instruction::new(Opcode::JAL, 0, 0, 0, true, true)];
+        let token = CancellationToken::new();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let options = ProverOptions::default()
+            .with_shard_size(1 << 4)
+            .with_observer(sender)
+            .with_cancellation_token(token.clone());
+        token.cancel();
+        let mut runtime = Runtime::with_options(Program::new(instructions, 0, 0), &options);
+        let err = runtime.run().unwrap_err();
+        assert!(matches!(err, ExecutionError::Cancelled { pc: 0, .. }));
+        assert!(err.clk() <= runtime.shard_size);
+        let events = receiver.try_iter().collect::<Vec<_>>();
+        assert_eq!(
+            events,
+            vec![ProverEvent::Executed {
+                cycles: err.clk() as u64
+            }]
+        );
+    }
+
+    #[test]
+    fn test_memory_limit() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::SW, 0, 29, 0, false, true),
+            Instruction::new(Opcode::SW, 0, 29, 4, false, true),
+            Instruction::new(Opcode::SW, 0, 29, 8, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        // The program touches registers x0 and x29 and three memory words.
+        runtime.limits = ExecutionLimits::default().with_max_memory_words(4);
+        let err = runtime.run().unwrap_err();
+        assert_eq!(
+            err,
+            ExecutionError::MemoryLimitExceeded {
+                pc: 12,
+                clk: 3,
+                max_memory_words: 4
+            }
+        );
+
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 0x27654320, false, true),
+            Instruction::new(Opcode::SW, 0, 29, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.limits = ExecutionLimits::default().with_max_memory_words(4);
+        runtime.run().unwrap();
+    }
+
+    #[test]
+    fn test_memory_limit_with_memory_image() {
+        let instructions = vec![Instruction::new(Opcode::LW, 29, 0, 0x1000, false, true)];
+        let mut program = Program::new(instructions, 0, 0);
+        program.memory_image = (0..16).map(|i| (0x1000 + 4 * i, i)).collect();
+
+        // The program touches registers x0 and x29 and one word of its memory image.
+        let mut runtime = Runtime::new(program.clone());
+        runtime.limits = ExecutionLimits::default().with_max_memory_words(3);
+        runtime.run().unwrap();
+        assert_eq!(runtime.state.touched_memory_words, 3);
+
+        let mut runtime = Runtime::new(program);
+        runtime.limits = ExecutionLimits::default().with_max_memory_words(2);
+        let err = runtime.run().unwrap_err();
+        assert_eq!(
+            err,
+            ExecutionError::MemoryLimitExceeded {
+                pc: 0,
+                clk: 0,
+                max_memory_words: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_ebreak() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::EBREAK, 0, 0, 0, false, false),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        let err = runtime.run().unwrap_err();
+        assert_eq!(err, ExecutionError::Breakpoint { pc: 4, clk: 1 });
+    }
+
+    #[test]
+    fn test_unimp() {
+        let instructions = vec![Instruction::new(Opcode::UNIMP, 0, 0, 0, true, true)];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        let err = runtime.run().unwrap_err();
+        assert_eq!(err, ExecutionError::Unimplemented { pc: 0, clk: 0 });
+    }
+
     #[test]
     fn test_add() {
         // main:
@@ -978,7 +1976,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 42);
     }
 
@@ -995,7 +1993,7 @@ pub mod tests {
         let program = Program::new(instructions, 0, 0);
 
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 32);
     }
 
@@ -1012,7 +2010,7 @@ pub mod tests {
         let program = Program::new(instructions, 0, 0);
 
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 32);
     }
 
@@ -1030,7 +2028,7 @@ pub mod tests {
 
         let mut runtime = Runtime::new(program);
 
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 37);
     }
 
@@ -1047,7 +2045,7 @@ pub mod tests {
         let program = Program::new(instructions, 0, 0);
 
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 5);
     }
 
@@ -1064,7 +2062,7 @@ pub mod tests {
         let program = Program::new(instructions, 0, 0);
 
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 1184);
     }
 
@@ -1081,7 +2079,7 @@ pub mod tests {
         let program = Program::new(instructions, 0, 0);
 
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 1);
     }
 
@@ -1098,7 +2096,7 @@ pub mod tests {
         let program = Program::new(instructions, 0, 0);
 
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 1);
     }
 
@@ -1115,7 +2113,7 @@ pub mod tests {
         let program = Program::new(instructions, 0, 0);
 
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 0);
     }
 
@@ -1132,7 +2130,7 @@ pub mod tests {
         let program = Program::new(instructions, 0, 0);
 
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 0);
     }
 
@@ -1149,7 +2147,7 @@ pub mod tests {
         let program = Program::new(instructions, 0, 0);
 
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 84);
     }
 
@@ -1165,7 +2163,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 5 - 1 + 4);
     }
 
@@ -1181,7 +2179,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 10);
     }
 
@@ -1197,7 +2195,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 47);
     }
 
@@ -1213,7 +2211,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 0);
     }
 
@@ -1227,7 +2225,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 80);
     }
 
@@ -1241,7 +2239,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 2);
     }
 
@@ -1255,7 +2253,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 2);
     }
 
@@ -1269,7 +2267,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 0);
     }
 
@@ -1283,7 +2281,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.register(Register::X31), 0);
     }
 
@@ -1302,7 +2300,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.registers()[Register::X5 as usize], 8);
         assert_eq!(runtime.registers()[Register::X11 as usize], 100);
         assert_eq!(runtime.state.pc, 108);
@@ -1316,7 +2314,7 @@ pub mod tests {
         ];
         let program = Program::new(instructions, 0, 0);
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         assert_eq!(runtime.registers()[Register::X12 as usize], expected);
     }
 
@@ -1534,7 +2532,7 @@ pub mod tests {
     fn test_simple_memory_program_run() {
         let program = simple_memory_program();
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
 
         // Assert SW & LW case
         assert_eq!(runtime.register(Register::X28), 0x12348765);
@@ -0,0 +1,295 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Result, Write};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use super::{Instruction, Opcode, Program, Register};
+
+/// The output formats supported by the `Profiler`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFormat {
+    /// One line per function stack with its cycle count, as consumed by `inferno` and
+    /// `flamegraph.pl`.
+    Collapsed,
+
+    /// A sampled profile in the speedscope JSON format (https://www.speedscope.app).
+    Speedscope,
+}
+
+impl ProfileFormat {
+    /// The format for a profile written to `path`: speedscope for `.json` files and collapsed
+    /// stacks otherwise.
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension() {
+            Some(extension) if extension == "json" => ProfileFormat::Speedscope,
+            _ => ProfileFormat::Collapsed,
+        }
+    }
+}
+
+/// A node of the call tree, i.e. a function called from the stack of its parent.
+#[derive(Debug, Clone)]
+struct StackNode {
+    parent: usize,
+    function: u32,
+    cycles: u64,
+}
+
+/// A function that has been called and has not yet returned.
+#[derive(Debug, Clone, Copy)]
+struct Frame {
+    node: usize,
+    return_addr: u32,
+}
+
+/// The index of the root of the call tree, which does not belong to any function.
+const ROOT: usize = 0;
+
+/// Attributes the cycles of a program to the stacks of functions they were executed in.
+///
+/// Calls and returns are recovered from `JAL` and `JALR` instructions: a jump which links `ra` is a
+/// call, a jump to the return address of an active call is a return and a jump without link to the
+/// start of another function is a tail call. Functions are named from the symbols of the ELF file,
+/// so the program should not be stripped.
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    nodes: Vec<StackNode>,
+    children: HashMap<(usize, u32), usize>,
+    frames: Vec<Frame>,
+    output: Option<PathBuf>,
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Profiler {
+    /// Create a profiler which collects the profile in memory.
+    pub fn new() -> Self {
+        Self {
+            nodes: vec![StackNode {
+                parent: ROOT,
+                function: 0,
+                cycles: 0,
+            }],
+            children: HashMap::new(),
+            frames: Vec::new(),
+            output: None,
+        }
+    }
+
+    /// Write the profile to `path` once the program has finished, in the format given by
+    /// `ProfileFormat::from_path`.
+    pub fn with_output(mut self, path: impl Into<PathBuf>) -> Self {
+        self.output = Some(path.into());
+        self
+    }
+
+    /// The total number of cycles recorded so far.
+    pub fn total_cycles(&self) -> u64 {
+        self.nodes.iter().map(|node| node.cycles).sum()
+    }
+
+    /// Record the execution of `instruction` at `pc`, after which the program continued at
+    /// `next_pc`.
+    pub fn record(&mut self, program: &Program, pc: u32, next_pc: u32, instruction: &Instruction) {
+        if self.frames.is_empty() {
+            let node = self.child(ROOT, program.function_at(pc).unwrap_or(pc));
+            self.frames.push(Frame {
+                node,
+                return_addr: u32::MAX,
+            });
+        }
+        let top = *self.frames.last().unwrap();
+        self.nodes[top.node].cycles += 1;
+
+        let rd = match instruction.opcode {
+            Opcode::JAL => instruction.j_type().0,
+            Opcode::JALR => instruction.i_type().0,
+            _ => return,
+        };
+        if rd == Register::X1 {
+            let node = self.child(top.node, program.function_at(next_pc).unwrap_or(next_pc));
+            self.frames.push(Frame {
+                node,
+                return_addr: pc.wrapping_add(4),
+            });
+        } else if rd == Register::X0 {
+            if let Some(depth) = self
+                .frames
+                .iter()
+                .rposition(|frame| frame.return_addr == next_pc)
+            {
+                self.frames.truncate(depth);
+            } else if program.symbols.contains_key(&next_pc)
+                && self.nodes[top.node].function != next_pc
+            {
+                let parent = self.nodes[top.node].parent;
+                self.frames.last_mut().unwrap().node = self.child(parent, next_pc);
+            }
+        }
+    }
+
+    /// Write the profile in the given format, naming functions with the symbols of `program`.
+    pub fn write<W: Write>(
+        &self,
+        program: &Program,
+        format: ProfileFormat,
+        writer: W,
+    ) -> Result<()> {
+        match format {
+            ProfileFormat::Collapsed => self.write_collapsed(program, writer),
+            ProfileFormat::Speedscope => self.write_speedscope(program, writer),
+        }
+    }
+
+    /// Write the profile to `path`, in the format given by `ProfileFormat::from_path`.
+    pub fn save(&self, program: &Program, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(program, ProfileFormat::from_path(path), &mut writer)?;
+        writer.flush()
+    }
+
+    /// Write the profile to the output set with `Profiler::with_output`, if any.
+    pub(crate) fn flush(&self, program: &Program) -> Result<()> {
+        match &self.output {
+            Some(path) => self.save(program, path),
+            None => Ok(()),
+        }
+    }
+
+    /// The index of the node for `function` called from the stack of `parent`.
+    fn child(&mut self, parent: usize, function: u32) -> usize {
+        let nodes = &mut self.nodes;
+        *self.children.entry((parent, function)).or_insert_with(|| {
+            nodes.push(StackNode {
+                parent,
+                function,
+                cycles: 0,
+            });
+            nodes.len() - 1
+        })
+    }
+
+    /// The functions of the stack ending at `node`, starting from the outermost one.
+    fn stack(&self, mut node: usize) -> Vec<u32> {
+        let mut stack = Vec::new();
+        while node != ROOT {
+            stack.push(self.nodes[node].function);
+            node = self.nodes[node].parent;
+        }
+        stack.reverse();
+        stack
+    }
+
+    /// The stacks which executed at least one cycle, with their cycle counts.
+    fn samples(&self) -> impl Iterator<Item = (Vec<u32>, u64)> + '_ {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| node.cycles > 0)
+            .map(|(i, node)| (self.stack(i), node.cycles))
+    }
+
+    fn write_collapsed<W: Write>(&self, program: &Program, mut writer: W) -> Result<()> {
+        let mut lines = self
+            .samples()
+            .map(|(stack, cycles)| {
+                let names = stack
+                    .iter()
+                    .map(|function| function_name(program, *function))
+                    .collect::<Vec<_>>();
+                format!("{} {}", names.join(";"), cycles)
+            })
+            .collect::<Vec<_>>();
+        lines.sort();
+        for line in lines {
+            writeln!(writer, "{}", line)?;
+        }
+        Ok(())
+    }
+
+    fn write_speedscope<W: Write>(&self, program: &Program, mut writer: W) -> Result<()> {
+        let mut frames = Vec::new();
+        let mut frame_ids = HashMap::new();
+        let mut samples = Vec::new();
+        let mut weights = Vec::new();
+        for (stack, cycles) in self.samples() {
+            let sample = stack
+                .iter()
+                .map(|function| {
+                    *frame_ids.entry(*function).or_insert_with(|| {
+                        frames.push(SpeedscopeFrame {
+                            name: function_name(program, *function),
+                        });
+                        frames.len() - 1
+                    })
+                })
+                .collect();
+            samples.push(sample);
+            weights.push(cycles);
+        }
+
+        let file = SpeedscopeFile {
+            schema: "https://www.speedscope.app/file-format-schema.json",
+            exporter: "sp1",
+            shared: SpeedscopeShared { frames },
+            profiles: vec![SpeedscopeProfile {
+                kind: "sampled",
+                name: "cycles",
+                unit: "none",
+                start_value: 0,
+                end_value: self.total_cycles(),
+                samples,
+                weights,
+            }],
+        };
+        let json = serde_json::to_string(&file).expect("failed to serialize profile");
+        writer.write_all(json.as_bytes())
+    }
+}
+
+/// The name of the function starting at `function`, or its address if it has no symbol.
+fn function_name(program: &Program, function: u32) -> String {
+    match program.symbols.get(&function) {
+        Some(name) => name.clone(),
+        None => format!("0x{:08x}", function),
+    }
+}
+
+#[derive(Serialize)]
+struct SpeedscopeFile {
+    #[serde(rename = "$schema")]
+    schema: &'static str,
+    exporter: &'static str,
+    shared: SpeedscopeShared,
+    profiles: Vec<SpeedscopeProfile>,
+}
+
+#[derive(Serialize)]
+struct SpeedscopeShared {
+    frames: Vec<SpeedscopeFrame>,
+}
+
+#[derive(Serialize)]
+struct SpeedscopeFrame {
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SpeedscopeProfile {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    name: &'static str,
+    unit: &'static str,
+    start_value: u64,
+    end_value: u64,
+    samples: Vec<Vec<usize>>,
+    weights: Vec<u64>,
+}
@@ -17,4 +17,17 @@ pub struct Program {
 
     /// The initial memory image, useful for global constants.
     pub memory_image: BTreeMap<u32, u32>,
+
+    /// The names of the functions of the program, keyed by their start address.
+    ///
+    /// These are only used for debugging and profiling, so they are not serialized.
+    #[serde(skip)]
+    pub symbols: BTreeMap<u32, String>,
+}
+
+impl Program {
+    /// The start address of the function containing `pc`, if the program has symbols for it.
+    pub fn function_at(&self, pc: u32) -> Option<u32> {
+        self.symbols.range(..=pc).next_back().map(|(addr, _)| *addr)
+    }
 }
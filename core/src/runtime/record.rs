@@ -5,6 +5,7 @@ use std::sync::Arc;
 
 use super::program::Program;
 use super::Opcode;
+use crate::air::PublicValues;
 use crate::alu::AluEvent;
 use crate::bytes::{ByteLookupEvent, ByteOpcode};
 use crate::cpu::{CpuEvent, MemoryRecordEnum};
@@ -12,11 +13,13 @@ use crate::field::event::FieldEvent;
 use crate::runtime::MemoryRecord;
 use crate::syscall::precompiles::blake3::Blake3CompressInnerEvent;
 use crate::syscall::precompiles::edwards::EdDecompressEvent;
+use crate::syscall::precompiles::input::{ReadInputEvent, READ_INPUT_EVENT_WORDS};
 use crate::syscall::precompiles::k256::K256DecompressEvent;
 use crate::syscall::precompiles::keccak256::KeccakPermuteEvent;
 use crate::syscall::precompiles::sha256::{ShaCompressEvent, ShaExtendEvent};
 use crate::syscall::precompiles::{ECAddEvent, ECDoubleEvent};
-use crate::utils::env;
+use crate::utils::ProverOptions;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 /// A record of the execution of a program. Contains event data for everything that happened during
@@ -80,11 +83,20 @@ pub struct ExecutionRecord {
 
     pub blake3_compress_inner_events: Vec<Blake3CompressInnerEvent>,
 
+    pub read_input_events: Vec<ReadInputEvent>,
+
+    /// The events of the precompiles registered with `Precompiles::register`, keyed by their syscall
+    /// number. Each event is encoded with bincode.
+    pub custom_events: BTreeMap<u32, Vec<Vec<u8>>>,
+
     /// Information needed for global chips. This shouldn't really be here but for legacy reasons,
     /// we keep this information in this struct for now.
     pub first_memory_record: Vec<(u32, MemoryRecord, u32)>,
     pub last_memory_record: Vec<(u32, MemoryRecord, u32)>,
     pub program_memory_record: Vec<(u32, MemoryRecord, u32)>,
+
+    /// The public values committed by the program, which are shared by every shard.
+    pub public_values: PublicValues<u32>,
 }
 
 pub struct ShardingConfig {
@@ -101,17 +113,26 @@ pub struct ShardingConfig {
     pub keccak_len: usize,
     pub weierstrass_add_len: usize,
     pub weierstrass_double_len: usize,
+    pub sha_extend_len: usize,
+    pub sha_compress_len: usize,
+    pub ed_add_len: usize,
+    pub ed_decompress_len: usize,
+    pub k256_decompress_len: usize,
+    pub blake3_compress_inner_len: usize,
+    /// The number of `READ_INPUT` events in a shard, each of which writes at most
+    /// `READ_INPUT_EVENT_WORDS` words with one row per word.
+    pub read_input_len: usize,
+    pub custom_len: usize,
 }
 
 impl ShardingConfig {
     pub const fn shard_size(&self) -> usize {
         self.shard_size
     }
-}
 
-impl Default for ShardingConfig {
-    fn default() -> Self {
-        let shard_size = env::shard_size();
+    /// A configuration for shards of `options.shard_size` cycles.
+    pub fn new(options: &ProverOptions) -> Self {
+        let shard_size = options.shard_size;
         Self {
             shard_size,
             add_len: shard_size,
@@ -126,10 +147,24 @@ impl Default for ShardingConfig {
             keccak_len: shard_size,
             weierstrass_add_len: shard_size,
             weierstrass_double_len: shard_size,
+            sha_extend_len: shard_size,
+            sha_compress_len: shard_size,
+            ed_add_len: shard_size,
+            ed_decompress_len: shard_size,
+            k256_decompress_len: shard_size,
+            blake3_compress_inner_len: shard_size,
+            read_input_len: (shard_size / READ_INPUT_EVENT_WORDS).max(1),
+            custom_len: shard_size,
         }
     }
 }
 
+impl Default for ShardingConfig {
+    fn default() -> Self {
+        Self::new(&ProverOptions::default())
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct ShardStats {
     pub nb_cpu_events: usize,
@@ -150,6 +185,31 @@ pub struct ShardStats {
     pub nb_weierstrass_add_events: usize,
     pub nb_weierstrass_double_events: usize,
     pub nb_k256_decompress_events: usize,
+    pub nb_read_input_events: usize,
+}
+
+/// Adds `events` to `shards` in chunks of `chunk_len` events, the first chunk to the first shard
+/// and so on. A shard without CPU events is appended for each chunk past the last shard.
+fn shard_events<T: Clone>(
+    events: Vec<T>,
+    chunk_len: usize,
+    shards: &mut Vec<ExecutionRecord>,
+    shard_events: impl Fn(&mut ExecutionRecord) -> &mut Vec<T>,
+) {
+    for (i, chunk) in events.chunks(chunk_len).enumerate() {
+        if i == shards.len() {
+            let mut shard = ExecutionRecord {
+                index: (i + 1) as u32,
+                ..Default::default()
+            };
+            if let Some(first) = shards.first() {
+                shard.program = first.program.clone();
+                shard.public_values = first.public_values;
+            }
+            shards.push(shard);
+        }
+        shard_events(&mut shards[i]).extend_from_slice(chunk);
+    }
 }
 
 impl ExecutionRecord {
@@ -175,136 +235,184 @@ impl ExecutionRecord {
             shard.index = (index + 1) as u32;
             shard.cpu_events = self.cpu_events.split_off(start);
             shard.program = self.program.clone();
+            shard.public_values = self.public_values;
         }
 
-        // Shard all the other events according to the configuration.
+        // Shard all the other events according to the configuration. Events which do not fit in
+        // the shards of the CPU events are put in extra shards appended at the end.
 
         // Shard the ADD events.
-        for (add_chunk, shard) in take(&mut self.add_events)
-            .chunks_mut(config.add_len)
-            .zip(shards.iter_mut())
-        {
-            shard.add_events.extend_from_slice(add_chunk);
-        }
+        shard_events(
+            take(&mut self.add_events),
+            config.add_len,
+            &mut shards,
+            |shard| &mut shard.add_events,
+        );
 
         // Shard the MUL events.
-        for (mul_chunk, shard) in take(&mut self.mul_events)
-            .chunks_mut(config.mul_len)
-            .zip(shards.iter_mut())
-        {
-            shard.mul_events.extend_from_slice(mul_chunk);
-        }
+        shard_events(
+            take(&mut self.mul_events),
+            config.mul_len,
+            &mut shards,
+            |shard| &mut shard.mul_events,
+        );
 
         // Shard the SUB events.
-        for (sub_chunk, shard) in take(&mut self.sub_events)
-            .chunks_mut(config.sub_len)
-            .zip(shards.iter_mut())
-        {
-            shard.sub_events.extend_from_slice(sub_chunk);
-        }
+        shard_events(
+            take(&mut self.sub_events),
+            config.sub_len,
+            &mut shards,
+            |shard| &mut shard.sub_events,
+        );
 
         // Shard the bitwise events.
-        for (bitwise_chunk, shard) in take(&mut self.bitwise_events)
-            .chunks_mut(config.bitwise_len)
-            .zip(shards.iter_mut())
-        {
-            shard.bitwise_events.extend_from_slice(bitwise_chunk);
-        }
+        shard_events(
+            take(&mut self.bitwise_events),
+            config.bitwise_len,
+            &mut shards,
+            |shard| &mut shard.bitwise_events,
+        );
 
         // Shard the shift left events.
-        for (shift_left_chunk, shard) in take(&mut self.shift_left_events)
-            .chunks_mut(config.shift_left_len)
-            .zip(shards.iter_mut())
-        {
-            shard.shift_left_events.extend_from_slice(shift_left_chunk);
-        }
+        shard_events(
+            take(&mut self.shift_left_events),
+            config.shift_left_len,
+            &mut shards,
+            |shard| &mut shard.shift_left_events,
+        );
 
         // Shard the shift right events.
-        for (shift_right_chunk, shard) in take(&mut self.shift_right_events)
-            .chunks_mut(config.shift_right_len)
-            .zip(shards.iter_mut())
-        {
-            shard
-                .shift_right_events
-                .extend_from_slice(shift_right_chunk);
-        }
+        shard_events(
+            take(&mut self.shift_right_events),
+            config.shift_right_len,
+            &mut shards,
+            |shard| &mut shard.shift_right_events,
+        );
 
         // Shard the divrem events.
-        for (divrem_chunk, shard) in take(&mut self.divrem_events)
-            .chunks_mut(config.divrem_len)
-            .zip(shards.iter_mut())
-        {
-            shard.divrem_events.extend_from_slice(divrem_chunk);
-        }
+        shard_events(
+            take(&mut self.divrem_events),
+            config.divrem_len,
+            &mut shards,
+            |shard| &mut shard.divrem_events,
+        );
 
         // Shard the LT events.
-        for (lt_chunk, shard) in take(&mut self.lt_events)
-            .chunks_mut(config.lt_len)
-            .zip(shards.iter_mut())
-        {
-            shard.lt_events.extend_from_slice(lt_chunk);
-        }
+        shard_events(
+            take(&mut self.lt_events),
+            config.lt_len,
+            &mut shards,
+            |shard| &mut shard.lt_events,
+        );
 
         // Shard the field events.
-        take(&mut self.field_events)
-            .into_iter()
-            .enumerate()
-            .for_each(|(i, event)| {
-                let shard = &mut shards[i / config.field_len];
-                shard.field_events.push(event);
-            });
+        shard_events(
+            take(&mut self.field_events),
+            config.field_len,
+            &mut shards,
+            |shard| &mut shard.field_events,
+        );
 
         // Keccak-256 permute events.
-        for (keccak_chunk, shard) in take(&mut self.keccak_permute_events)
-            .chunks_mut(config.keccak_len)
-            .zip(shards.iter_mut())
-        {
-            shard.keccak_permute_events.extend_from_slice(keccak_chunk);
-        }
+        shard_events(
+            take(&mut self.keccak_permute_events),
+            config.keccak_len,
+            &mut shards,
+            |shard| &mut shard.keccak_permute_events,
+        );
 
         // Weierstrass curve add events.
-        for (weierstrass_add_chunk, shard) in take(&mut self.weierstrass_add_events)
-            .chunks_mut(config.weierstrass_add_len)
-            .zip(shards.iter_mut())
-        {
-            shard
-                .weierstrass_add_events
-                .extend_from_slice(weierstrass_add_chunk);
-        }
+        shard_events(
+            take(&mut self.weierstrass_add_events),
+            config.weierstrass_add_len,
+            &mut shards,
+            |shard| &mut shard.weierstrass_add_events,
+        );
 
         // Weierstrass curve double events.
-        for (weierstrass_double_chunk, shard) in take(&mut self.weierstrass_double_events)
-            .chunks_mut(config.weierstrass_double_len)
-            .zip(shards.iter_mut())
-        {
-            shard
-                .weierstrass_double_events
-                .extend_from_slice(weierstrass_double_chunk);
-        }
-
-        // Put the precompile events in the first shard.
-        let first = shards.first_mut().unwrap();
+        shard_events(
+            take(&mut self.weierstrass_double_events),
+            config.weierstrass_double_len,
+            &mut shards,
+            |shard| &mut shard.weierstrass_double_events,
+        );
 
         // SHA-256 extend events.
-        first.sha_extend_events = std::mem::take(&mut self.sha_extend_events);
+        shard_events(
+            take(&mut self.sha_extend_events),
+            config.sha_extend_len,
+            &mut shards,
+            |shard| &mut shard.sha_extend_events,
+        );
 
         // SHA-256 compress events.
-        first.sha_compress_events = std::mem::take(&mut self.sha_compress_events);
+        shard_events(
+            take(&mut self.sha_compress_events),
+            config.sha_compress_len,
+            &mut shards,
+            |shard| &mut shard.sha_compress_events,
+        );
 
         // Edwards curve add events.
-        first.ed_add_events = std::mem::take(&mut self.ed_add_events);
+        shard_events(
+            take(&mut self.ed_add_events),
+            config.ed_add_len,
+            &mut shards,
+            |shard| &mut shard.ed_add_events,
+        );
 
         // Edwards curve decompress events.
-        first.ed_decompress_events = std::mem::take(&mut self.ed_decompress_events);
+        shard_events(
+            take(&mut self.ed_decompress_events),
+            config.ed_decompress_len,
+            &mut shards,
+            |shard| &mut shard.ed_decompress_events,
+        );
 
         // K256 curve decompress events.
-        first.k256_decompress_events = std::mem::take(&mut self.k256_decompress_events);
-
-        // Blake3 compress events .
-        first.blake3_compress_inner_events = std::mem::take(&mut self.blake3_compress_inner_events);
+        shard_events(
+            take(&mut self.k256_decompress_events),
+            config.k256_decompress_len,
+            &mut shards,
+            |shard| &mut shard.k256_decompress_events,
+        );
+
+        // Blake3 compress events.
+        shard_events(
+            take(&mut self.blake3_compress_inner_events),
+            config.blake3_compress_inner_len,
+            &mut shards,
+            |shard| &mut shard.blake3_compress_inner_events,
+        );
+
+        // Read input events.
+        shard_events(
+            take(&mut self.read_input_events),
+            config.read_input_len,
+            &mut shards,
+            |shard| &mut shard.read_input_events,
+        );
+
+        // Custom precompile events, chunked separately for each precompile.
+        for (syscall_id, events) in take(&mut self.custom_events) {
+            shard_events(events, config.custom_len, &mut shards, |shard| {
+                shard.custom_events.entry(syscall_id).or_default()
+            });
+        }
 
-        // Put all byte lookups in the first shard (as the table size is fixed)
-        first.byte_lookups = std::mem::take(&mut self.byte_lookups);
+        // Byte lookups are generated for each shard by `RiscvStark::shard` from the events of the
+        // shard. Any recorded before sharding are put in the first shard.
+        shards.first_mut().unwrap().byte_lookups = take(&mut self.byte_lookups);
+
+        // Only the shard of the last CPU event, which is the `HALT` syscall of a finished execution,
+        // has halted.
+        for (index, shard) in shards.iter_mut().enumerate() {
+            shard.public_values.halted = if index + 1 == num_shards {
+                self.public_values.halted
+            } else {
+                0
+            };
+        }
 
         // Put the memory records in the last shard.
         let last_shard = shards.last_mut().unwrap();
@@ -378,6 +486,25 @@ impl ExecutionRecord {
         }
     }
 
+    /// Adds an event of the precompile registered for `syscall_id`.
+    pub fn add_custom_event<E: Serialize>(&mut self, syscall_id: u32, event: &E) {
+        let event = bincode::serialize(event).expect("failed to serialize custom event");
+        self.custom_events
+            .entry(syscall_id)
+            .or_default()
+            .push(event);
+    }
+
+    /// The events of the precompile registered for `syscall_id`.
+    pub fn custom_events<E: DeserializeOwned>(&self, syscall_id: u32) -> Vec<E> {
+        self.custom_events
+            .get(&syscall_id)
+            .into_iter()
+            .flatten()
+            .map(|event| bincode::deserialize(event).expect("failed to deserialize custom event"))
+            .collect()
+    }
+
     pub fn add_byte_lookup_events(&mut self, blu_events: Vec<ByteLookupEvent>) {
         for blu_event in blu_events.iter() {
             self.add_byte_lookup_event(*blu_event);
@@ -455,6 +582,7 @@ impl ExecutionRecord {
             nb_weierstrass_add_events: self.weierstrass_add_events.len(),
             nb_weierstrass_double_events: self.weierstrass_double_events.len(),
             nb_k256_decompress_events: self.k256_decompress_events.len(),
+            nb_read_input_events: self.read_input_events.len(),
         }
     }
 
@@ -489,6 +617,13 @@ impl ExecutionRecord {
             .append(&mut other.k256_decompress_events);
         self.blake3_compress_inner_events
             .append(&mut other.blake3_compress_inner_events);
+        self.read_input_events.append(&mut other.read_input_events);
+        for (syscall_id, mut events) in std::mem::take(&mut other.custom_events) {
+            self.custom_events
+                .entry(syscall_id)
+                .or_default()
+                .append(&mut events);
+        }
 
         for (event, mult) in other.byte_lookups.iter_mut() {
             self.byte_lookups
@@ -513,3 +648,50 @@ pub struct CpuRecord {
     pub c: Option<MemoryRecordEnum>,
     pub memory: Option<MemoryRecordEnum>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::runtime::tests::simple_program;
+    use crate::runtime::Runtime;
+
+    #[test]
+    fn test_shard_overflow() {
+        let mut runtime = Runtime::new(simple_program());
+        runtime.run().unwrap();
+        let mut record = take(&mut runtime.record);
+        assert_eq!(record.cpu_events.len(), 3);
+
+        // More events than fit in the single shard of the CPU events.
+        record.add_events = (0..10)
+            .map(|i| AluEvent::new(i, Opcode::ADD, 0, 0, 0))
+            .collect();
+        record.field_events = (0..20).map(|i| FieldEvent::new(true, i, i + 1)).collect();
+        record.custom_events.insert(0x200, vec![vec![0]; 9]);
+        record.public_values.halted = 1;
+
+        let config = ShardingConfig::new(&ProverOptions::default().with_shard_size(4));
+        let shards = record.shard(&config);
+
+        assert_eq!(shards.len(), 3);
+        for (i, shard) in shards.iter().enumerate() {
+            assert_eq!(shard.index, (i + 1) as u32);
+            assert_eq!(shard.program.instructions.len(), 3);
+        }
+        let lens = |f: fn(&ExecutionRecord) -> usize| shards.iter().map(f).collect::<Vec<_>>();
+        assert_eq!(lens(|shard| shard.cpu_events.len()), vec![3, 0, 0]);
+        assert_eq!(lens(|shard| shard.add_events.len()), vec![4, 4, 2]);
+        assert_eq!(lens(|shard| shard.field_events.len()), vec![16, 4, 0]);
+        assert_eq!(
+            lens(|shard| shard.custom_events.get(&0x200).map_or(0, Vec::len)),
+            vec![4, 4, 1]
+        );
+        assert_eq!(shards[2].add_events[1].clk, 9);
+
+        // Only the shard of the last CPU event has halted.
+        assert_eq!(
+            lens(|shard| shard.public_values.halted as usize),
+            vec![1, 0, 0]
+        );
+    }
+}
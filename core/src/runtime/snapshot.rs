@@ -0,0 +1,89 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use bincode::Error;
+use serde::{Deserialize, Serialize};
+
+use super::{CallStack, ExecutionError, ExecutionLimits, ExecutionRecord, ExecutionState};
+
+/// A checkpoint of a paused `Runtime`, from which execution can be resumed with `Runtime::resume`.
+///
+/// The snapshot contains the program, the execution state, the events collected so far and the
+/// configuration of the runtime.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeSnapshot {
+    /// The state of the program's execution.
+    pub state: ExecutionState,
+
+    /// The events collected so far, including the program.
+    pub record: ExecutionRecord,
+
+    /// The symbols of the program, which are not serialized with the program in the record.
+    pub symbols: BTreeMap<u32, String>,
+
+    /// The maximum size of each shard.
+    pub shard_size: u32,
+
+    /// The limits on the cycles and memory the program may use.
+    pub limits: ExecutionLimits,
+
+    /// Whether the runtime fails on panic or not.
+    pub fail_on_panic: bool,
+
+    /// The functions the program is executing.
+    pub call_stack: CallStack,
+
+    /// The cycle tracker of the runtime.
+    pub cycle_tracker: HashMap<String, (u32, u32)>,
+
+    /// The pending stdout and stderr output of the runtime.
+    pub io_buf: HashMap<u32, String>,
+
+    /// The text the program wrote to stdout and stderr so far.
+    pub program_stdout: String,
+    pub program_stderr: String,
+
+    /// The last output the program wrote to stderr.
+    pub last_stderr: Option<String>,
+
+    /// The error describing why the program halted with a non-zero exit code, if it did so.
+    pub panic: Option<ExecutionError>,
+}
+
+impl RuntimeSnapshot {
+    /// Saves the snapshot to the given path.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let file = File::create(path)?;
+        bincode::serialize_into(BufWriter::new(file), self)
+    }
+
+    /// Loads a snapshot previously saved with `RuntimeSnapshot::save`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        bincode::deserialize_from(BufReader::new(file))
+    }
+}
+
+/// An error that occurred while taking a snapshot of a runtime.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// The runtime is inside an unconstrained block, whose changes are only reverted on exit.
+    Unconstrained { pc: u32, clk: u32 },
+}
+
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SnapshotError::Unconstrained { pc, clk } => write!(
+                f,
+                "cannot snapshot the runtime inside an unconstrained block (pc=0x{:x}, clk={})",
+                pc, clk
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
@@ -1,10 +1,11 @@
 use hashbrown::HashMap;
 use nohash_hasher::BuildNoHashHasher;
+use serde::{Deserialize, Serialize};
 
 use super::{CpuRecord, ExecutionRecord};
 
 /// Holds data describing the current state of a program's execution.
-#[derive(Debug, Clone, Default)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ExecutionState {
     /// The global clock keeps track of how many instrutions have been executed through all shards.
     pub global_clk: u32,
@@ -23,12 +24,44 @@ pub struct ExecutionState {
     /// + timestamp that each memory address was accessed.
     pub memory: HashMap<u32, (u32, u32, u32), BuildNoHashHasher<u32>>,
 
+    /// The number of distinct memory words, including registers, accessed by the program. Unlike
+    /// `memory`, this does not count the words of the memory image which were never accessed.
+    pub touched_memory_words: usize,
+
     /// A stream of input values (global to the entire program).
     pub input_stream: Vec<u8>,
 
     /// A ptr to the current position in the input stream incremented by LWA opcode.
     pub input_stream_ptr: usize,
 
+    /// A stream of public input values, whose digest is committed by the program.
+    pub public_input_stream: Vec<u8>,
+
+    /// A ptr to the current position in the public input stream incremented by LWA opcode.
+    pub public_input_stream_ptr: usize,
+
+    /// A stream of hints written by the program, typically inside unconstrained blocks, which the
+    /// program reads back through `FD_HINT`.
+    pub hint_stream: Vec<u8>,
+
+    /// A ptr to the current position in the hint stream, advanced as the program reads hints
+    /// through `FD_HINT` with the `LWA` syscall.
+    pub hint_stream_ptr: usize,
+
+    /// The response of the last host call or environment variable lookup, which the program reads
+    /// through `FD_HOST_RESPONSE`.
+    pub host_response: Vec<u8>,
+
+    /// A ptr to the current position in the host response.
+    pub host_response_ptr: usize,
+
+    /// The environment variables which the program can look up with the `GETENV` syscall.
+    pub env: HashMap<String, String>,
+
+    /// The seed of the program's random number generator, which it reads with the `RNG_SEED`
+    /// syscall.
+    pub rng_seed: u64,
+
     /// A stream of output values from the program (global to entire program).
     pub output_stream: Vec<u8>,
 
@@ -45,8 +78,17 @@ impl ExecutionState {
             clk: 0,
             pc: pc_start,
             memory: HashMap::default(),
+            touched_memory_words: 0,
             input_stream: Vec::new(),
             input_stream_ptr: 0,
+            public_input_stream: Vec::new(),
+            public_input_stream_ptr: 0,
+            hint_stream: Vec::new(),
+            hint_stream_ptr: 0,
+            host_response: Vec::new(),
+            host_response_ptr: 0,
+            env: HashMap::new(),
+            rng_seed: 0,
             output_stream: Vec::new(),
             output_stream_ptr: 0,
         }
@@ -65,6 +107,9 @@ pub(crate) struct ForkState {
     /// Original program counter
     pub(crate) pc: u32,
 
+    /// Original number of touched memory words
+    pub(crate) touched_memory_words: usize,
+
     /// Only contains the original memory values for addresses that have been modified
     pub(crate) memory_diff: HashMap<u32, Option<(u32, u32, u32)>, BuildNoHashHasher<u32>>,
 
@@ -1,17 +1,19 @@
 use std::collections::HashMap;
 use std::rc::Rc;
 
-use crate::runtime::{Register, Runtime};
+use crate::runtime::{ExecutionError, Register, Runtime};
 use crate::syscall::precompiles::blake3::Blake3CompressInnerChip;
 use crate::syscall::precompiles::edwards::EdAddAssignChip;
 use crate::syscall::precompiles::edwards::EdDecompressChip;
+use crate::syscall::precompiles::input::ReadInputChip;
 use crate::syscall::precompiles::k256::K256DecompressChip;
 use crate::syscall::precompiles::keccak256::KeccakPermuteChip;
 use crate::syscall::precompiles::sha256::{ShaCompressChip, ShaExtendChip};
 use crate::syscall::precompiles::weierstrass::WeierstrassAddAssignChip;
 use crate::syscall::precompiles::weierstrass::WeierstrassDoubleAssignChip;
 use crate::syscall::{
-    SyscallEnterUnconstrained, SyscallExitUnconstrained, SyscallHalt, SyscallLWA, SyscallWrite,
+    SyscallCommit, SyscallEnterUnconstrained, SyscallExitUnconstrained, SyscallGetenv, SyscallHalt,
+    SyscallHostCall, SyscallLWA, SyscallRngSeed, SyscallWrite,
 };
 use crate::utils::ec::edwards::ed25519::{Ed25519, Ed25519Parameters};
 use crate::utils::ec::weierstrass::secp256k1::Secp256k1;
@@ -60,13 +62,28 @@ pub enum SyscallCode {
     /// Executes the `BLAKE3_COMPRESS_INNER` precompile.
     BLAKE3_COMPRESS_INNER = 112,
 
+    /// Commits a word of the public values digests.
+    COMMIT = 113,
+
+    /// Sends a request to a callback registered on the host.
+    HOST_CALL = 114,
+
+    /// Writes bytes of an input stream to memory.
+    READ_INPUT = 115,
+
+    /// Looks up an environment variable set on the host.
+    GETENV = 116,
+
+    /// Returns a word of the seed of the random number generator.
+    RNG_SEED = 117,
+
     WRITE = 999,
 }
 
 impl SyscallCode {
-    /// Create a syscall from a u32.
-    pub fn from_u32(value: u32) -> Self {
-        match value {
+    /// Create a syscall from a u32, returning `None` if the syscall is not known.
+    pub fn from_u32(value: u32) -> Option<Self> {
+        let syscall = match value {
             100 => SyscallCode::HALT,
             101 => SyscallCode::LWA,
             102 => SyscallCode::SHA_EXTEND,
@@ -80,15 +97,21 @@ impl SyscallCode {
             110 => SyscallCode::ENTER_UNCONSTRAINED,
             111 => SyscallCode::EXIT_UNCONSTRAINED,
             112 => SyscallCode::BLAKE3_COMPRESS_INNER,
+            113 => SyscallCode::COMMIT,
+            114 => SyscallCode::HOST_CALL,
+            115 => SyscallCode::READ_INPUT,
+            116 => SyscallCode::GETENV,
+            117 => SyscallCode::RNG_SEED,
             999 => SyscallCode::WRITE,
-            _ => panic!("invalid syscall number: {}", value),
-        }
+            _ => return None,
+        };
+        Some(syscall)
     }
 }
 
 pub trait Syscall {
     /// Execute the syscall and return the resulting value of register a0.
-    fn execute(&self, ctx: &mut SyscallContext) -> u32;
+    fn execute(&self, ctx: &mut SyscallContext) -> Result<u32, ExecutionError>;
 
     /// The number of extra cycles that the syscall takes to execute. Unless this syscall is complex
     /// and requires many cycles, this should be zero.
@@ -126,6 +149,26 @@ impl<'a> SyscallContext<'a> {
         self.rt.state.current_shard
     }
 
+    /// The program counter of the `ecall` instruction that invoked the syscall.
+    pub fn pc(&self) -> u32 {
+        self.rt.state.pc
+    }
+
+    /// The number of instructions executed before the syscall.
+    pub fn global_clk(&self) -> u32 {
+        self.rt.state.global_clk
+    }
+
+    /// Creates an error for a syscall which was invoked with invalid arguments.
+    pub fn invalid_argument(&self, description: impl Into<String>) -> ExecutionError {
+        ExecutionError::InvalidSyscallArgument {
+            pc: self.pc(),
+            clk: self.global_clk(),
+            syscall_id: self.rt.register(Register::X5),
+            description: description.into(),
+        }
+    }
+
     pub fn mr(&mut self, addr: u32) -> (MemoryReadRecord, u32) {
         let record = self.rt.mr(addr, self.current_shard, self.clk);
         (record, record.value)
@@ -165,6 +208,27 @@ impl<'a> SyscallContext<'a> {
         self.rt.byte(addr)
     }
 
+    /// Reads `len` bytes starting at `addr` without memory records, failing if the buffer wraps
+    /// around the address space or lies outside the valid memory.
+    pub fn bytes_unsafe(&self, addr: u32, len: u32) -> Result<Vec<u8>, ExecutionError> {
+        if len == 0 {
+            return Ok(Vec::new());
+        }
+        let out_of_bounds = || {
+            self.invalid_argument(format!(
+                "buffer of {} bytes at 0x{:x} is outside the valid memory",
+                len, addr
+            ))
+        };
+        let end = addr.checked_add(len - 1).ok_or_else(out_of_bounds)?;
+        // The valid memory is contiguous, so the whole buffer is valid if both ends are.
+        self.rt
+            .validate_memory_access(addr, 1)
+            .and_then(|_| self.rt.validate_memory_access(end, 1))
+            .map_err(|_| out_of_bounds())?;
+        Ok((addr..=end).map(|addr| self.rt.byte(addr)).collect())
+    }
+
     pub fn word_unsafe(&self, addr: u32) -> u32 {
         self.rt.word(addr)
     }
@@ -226,6 +290,11 @@ pub fn default_syscall_map() -> HashMap<SyscallCode, Rc<dyn Syscall>> {
         Rc::new(SyscallExitUnconstrained::new()),
     );
     syscall_map.insert(SyscallCode::WRITE, Rc::new(SyscallWrite::new()));
+    syscall_map.insert(SyscallCode::COMMIT, Rc::new(SyscallCommit::new()));
+    syscall_map.insert(SyscallCode::HOST_CALL, Rc::new(SyscallHostCall::new()));
+    syscall_map.insert(SyscallCode::READ_INPUT, Rc::new(ReadInputChip::new()));
+    syscall_map.insert(SyscallCode::GETENV, Rc::new(SyscallGetenv::new()));
+    syscall_map.insert(SyscallCode::RNG_SEED, Rc::new(SyscallRngSeed::new()));
 
     syscall_map
 }
@@ -2,7 +2,10 @@ use crate::air::MachineAir;
 pub use crate::air::SP1AirBuilder;
 use crate::memory::MemoryChipKind;
 use crate::runtime::ExecutionRecord;
+use crate::syscall::precompiles::custom::Precompiles;
 use p3_field::PrimeField32;
+use petgraph::algo::toposort;
+use petgraph::graph::DiGraph;
 pub use riscv_chips::*;
 
 /// A module for importing all the different RISC-V chips.
@@ -21,8 +24,10 @@ pub(crate) mod riscv_chips {
     pub use crate::memory::MemoryGlobalChip;
     pub use crate::program::ProgramChip;
     pub use crate::syscall::precompiles::blake3::Blake3CompressInnerChip;
+    pub use crate::syscall::precompiles::custom::CustomChip;
     pub use crate::syscall::precompiles::edwards::EdAddAssignChip;
     pub use crate::syscall::precompiles::edwards::EdDecompressChip;
+    pub use crate::syscall::precompiles::input::ReadInputChip;
     pub use crate::syscall::precompiles::k256::K256DecompressChip;
     pub use crate::syscall::precompiles::keccak256::KeccakPermuteChip;
     pub use crate::syscall::precompiles::sha256::ShaCompressChip;
@@ -90,13 +95,25 @@ pub enum RiscvAir<F: PrimeField32> {
     KeccakP(KeccakPermuteChip),
     /// A precompile for the Blake3 compression function.
     Blake3Compress(Blake3CompressInnerChip),
+    /// A precompile for writing bytes of the input streams to memory.
+    ReadInput(ReadInputChip),
+    /// A precompile registered by a downstream crate with `Precompiles::register`.
+    Custom(CustomChip<F>),
 }
 
 impl<F: PrimeField32> RiscvAir<F> {
     /// Get all the different RISC-V AIRs.
     pub fn get_all() -> Vec<Self> {
-        // The order of the chips is important, as it is used to determine the order of trace
-        // generation. In the future, we will detect that order automatically.
+        Self::with_precompiles(&Precompiles::new())
+    }
+
+    /// Get all the different RISC-V AIRs, including the chips of the given precompiles.
+    ///
+    /// The chips are ordered so that every chip comes before the chips it names in
+    /// `MachineAir::emits_events_to`, since this is the order in which their dependencies are
+    /// generated. Panics if chips emit events to each other in a cycle, or if the chip of a
+    /// precompile is defined over another field.
+    pub fn with_precompiles(precompiles: &Precompiles) -> Vec<Self> {
         let mut chips = vec![];
         let cpu = CpuChip::default();
         chips.push(RiscvAir::Cpu(cpu));
@@ -122,6 +139,11 @@ impl<F: PrimeField32> RiscvAir<F> {
         chips.push(RiscvAir::KeccakP(keccak_permute));
         let blake3_compress_inner = Blake3CompressInnerChip::new();
         chips.push(RiscvAir::Blake3Compress(blake3_compress_inner));
+        let read_input = ReadInputChip::new();
+        chips.push(RiscvAir::ReadInput(read_input));
+        for custom in precompiles.chips() {
+            chips.push(RiscvAir::Custom(custom));
+        }
         let add = AddChip::default();
         chips.push(RiscvAir::Add(add));
         let sub = SubChip::default();
@@ -149,7 +171,25 @@ impl<F: PrimeField32> RiscvAir<F> {
         let byte = ByteChip::default();
         chips.push(RiscvAir::ByteLookup(byte));
 
-        chips
+        let dependencies = chips
+            .iter()
+            .map(|chip| (chip.name(), chip.emits_events_to()))
+            .collect::<Vec<_>>();
+        match sort_by_dependencies(&dependencies, |(_, emits_events_to), (name, _)| {
+            emits_events_to.contains(name)
+        }) {
+            Ok(order) => {
+                let mut chips = chips.into_iter().map(Some).collect::<Vec<_>>();
+                order
+                    .into_iter()
+                    .map(|i| chips[i].take().unwrap())
+                    .collect()
+            }
+            Err(i) => panic!(
+                "chip {} emits events to chips which emit events back to it",
+                chips[i].name()
+            ),
+        }
     }
 
     /// Returns `true` if the given `shard` includes events for this AIR.
@@ -165,7 +205,9 @@ impl<F: PrimeField32> RiscvAir<F> {
             RiscvAir::Lt(_) => !shard.lt_events.is_empty(),
             RiscvAir::ShiftLeft(_) => !shard.shift_left_events.is_empty(),
             RiscvAir::ShiftRight(_) => !shard.shift_right_events.is_empty(),
-            RiscvAir::ByteLookup(_) => !shard.byte_lookups.is_empty(),
+            // Chips with preprocessed traces are included in every shard, since the verifying key
+            // commits to all of them.
+            RiscvAir::ByteLookup(_) => true,
             RiscvAir::FieldLTU(_) => !shard.field_events.is_empty(),
             RiscvAir::MemoryInit(_) => !shard.first_memory_record.is_empty(),
             RiscvAir::MemoryFinal(_) => !shard.last_memory_record.is_empty(),
@@ -179,6 +221,8 @@ impl<F: PrimeField32> RiscvAir<F> {
             RiscvAir::Secp256k1Double(_) => !shard.weierstrass_double_events.is_empty(),
             RiscvAir::KeccakP(_) => !shard.keccak_permute_events.is_empty(),
             RiscvAir::Blake3Compress(_) => !shard.blake3_compress_inner_events.is_empty(),
+            RiscvAir::ReadInput(_) => !shard.read_input_events.is_empty(),
+            RiscvAir::Custom(custom) => custom.included(shard),
         }
     }
 }
@@ -196,3 +240,69 @@ impl<F: PrimeField32> core::hash::Hash for RiscvAir<F> {
         self.name().hash(state);
     }
 }
+
+/// Orders `items` so that every item comes before the items it emits events to.
+///
+/// Returns the indices of the items in that order, or the index of an item in a cycle. Items which
+/// do not depend on each other are ordered by the depth-first search of `petgraph`'s `toposort`
+/// over the graph built in the given order, so the same items are always ordered the same way.
+fn sort_by_dependencies<T>(
+    items: &[T],
+    emits_events_to: impl Fn(&T, &T) -> bool,
+) -> Result<Vec<usize>, usize> {
+    let mut graph = DiGraph::<usize, ()>::new();
+    let nodes = (0..items.len())
+        .map(|i| graph.add_node(i))
+        .collect::<Vec<_>>();
+    for (i, item) in items.iter().enumerate() {
+        for (j, other) in items.iter().enumerate() {
+            if i != j && emits_events_to(item, other) {
+                graph.add_edge(nodes[i], nodes[j], ());
+            }
+        }
+    }
+
+    toposort(&graph, None)
+        .map(|order| order.into_iter().map(|node| graph[node]).collect())
+        .map_err(|cycle| graph[cycle.node_id()])
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_baby_bear::BabyBear;
+
+    use super::{sort_by_dependencies, RiscvAir};
+    use crate::air::MachineAir;
+
+    #[test]
+    fn test_chips_ordered_by_dependencies() {
+        let chips = RiscvAir::<BabyBear>::get_all();
+        let names = chips.iter().map(|chip| chip.name()).collect::<Vec<_>>();
+        for (i, chip) in chips.iter().enumerate() {
+            for name in chip.emits_events_to() {
+                let j = names.iter().position(|other| *other == name);
+                assert!(
+                    j.is_some(),
+                    "{} emits events to unknown chip {}",
+                    names[i],
+                    name
+                );
+                assert!(j > Some(i), "{} is ordered after {}", name, names[i]);
+            }
+        }
+
+        let again = RiscvAir::<BabyBear>::get_all();
+        assert!(again.iter().map(|chip| chip.name()).eq(names));
+    }
+
+    #[test]
+    fn test_sort_by_dependencies() {
+        // Each item emits events to the items it divides.
+        let items = [6, 3, 2, 1];
+        let order = sort_by_dependencies(&items, |a, b| b % a == 0).unwrap();
+        assert_eq!(order, vec![3, 2, 1, 0]);
+
+        let cycle = sort_by_dependencies(&[1, 2, 3], |a, b| a == &(b % 3 + 1)).unwrap_err();
+        assert!(cycle < 3);
+    }
+}
@@ -82,9 +82,9 @@ where
     /// Records the interactions and constraint degree from the air and crates a new chip.
     pub fn new(air: A) -> Self
     where
-        A: Air<InteractionBuilder<F>>,
+        A: MachineAir<F> + Air<InteractionBuilder<F>>,
     {
-        let mut builder = InteractionBuilder::new(air.width());
+        let mut builder = InteractionBuilder::new(air.preprocessed_width(), air.width());
         air.eval(&mut builder);
         let (sends, receives) = builder.interactions();
 
@@ -106,7 +106,7 @@ where
 
     pub fn generate_permutation_trace<EF: ExtensionField<F>>(
         &self,
-        preprocessed: &Option<RowMajorMatrix<F>>,
+        preprocessed: Option<&RowMajorMatrix<F>>,
         main: &RowMajorMatrix<F>,
         random_elements: &[EF],
     ) -> RowMajorMatrix<EF>
@@ -164,6 +164,10 @@ where
     fn generate_dependencies(&self, input: &ExecutionRecord, output: &mut ExecutionRecord) {
         self.air.generate_dependencies(input, output)
     }
+
+    fn emits_events_to(&self) -> Vec<String> {
+        self.air.emits_events_to()
+    }
 }
 
 // Implement AIR directly on Chip, evaluating both execution and permutation constraints.
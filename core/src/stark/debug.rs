@@ -7,7 +7,7 @@ use p3_field::{AbstractField, PrimeField32};
 use p3_field::{ExtensionField, Field};
 use p3_matrix::{dense::RowMajorMatrix, Matrix, MatrixRowSlices};
 
-use crate::air::{EmptyMessageBuilder, MachineAir, MultiTableAirBuilder};
+use crate::air::{EmptyMessageBuilder, MachineAir, MultiTableAirBuilder, PublicValuesAirBuilder};
 
 use super::{RiscvChip, StarkGenericConfig};
 
@@ -20,6 +20,7 @@ pub fn debug_constraints<SC: StarkGenericConfig>(
     main: &RowMajorMatrix<SC::Val>,
     perm: &RowMajorMatrix<SC::Challenge>,
     perm_challenges: &[SC::Challenge],
+    public_values: &[SC::Val],
 ) where
     SC::Val: PrimeField32,
 {
@@ -64,6 +65,7 @@ pub fn debug_constraints<SC: StarkGenericConfig>(
                 next: perm_next,
             },
             perm_challenges,
+            public_values,
             cumulative_sum,
             is_first_row: SC::Val::zero(),
             is_last_row: SC::Val::zero(),
@@ -105,6 +107,7 @@ pub struct DebugConstraintBuilder<'a, F: Field, EF: ExtensionField<F>> {
     pub(crate) perm: TwoRowMatrixView<'a, EF>,
     pub(crate) cumulative_sum: EF,
     pub(crate) perm_challenges: &'a [EF],
+    pub(crate) public_values: &'a [F],
     pub(crate) is_first_row: F,
     pub(crate) is_last_row: F,
     pub(crate) is_transition: F,
@@ -204,6 +207,16 @@ where
     }
 }
 
+impl<'a, F, EF> PublicValuesAirBuilder for DebugConstraintBuilder<'a, F, EF>
+where
+    F: Field,
+    EF: ExtensionField<F>,
+{
+    fn public_values(&self) -> &[Self::F] {
+        self.public_values
+    }
+}
+
 impl<'a, F: Field, EF: ExtensionField<F>> EmptyMessageBuilder
     for DebugConstraintBuilder<'a, F, EF>
 {
@@ -0,0 +1,895 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use p3_challenger::{CanObserve, FieldChallenger};
+use p3_maybe_rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    Com, LocalProver, PcsProverData, Proof, Prover, ProverError, ProvingKey, RiscvStark,
+    ShardMainData, ShardProof, StarkGenericConfig,
+};
+use crate::air::PublicValues;
+use crate::runtime::{ExecutionRecord, Program};
+use crate::utils::{ProverEvent, ProverOptions, StarkUtils};
+
+/// How often the coordinator checks for shard proofs and idle workers check for work units.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// What a worker needs to prove any shard of a job besides the shard itself, which the coordinator
+/// writes once per job.
+///
+/// Every shard is proven from the state of the challenger after it has observed the preprocessed
+/// commitment and then the main commitment and public values of each shard. The worker replays
+/// these observations to reach that state, so the job does not depend on how the challenger is
+/// implemented. The coordinator therefore only proves from a challenger which has observed nothing
+/// else, and the worker only proves with a proving key which has the coordinator's preprocessed
+/// commitment.
+#[derive(Serialize, Deserialize)]
+#[serde(bound(serialize = "Com<SC>: Serialize"))]
+#[serde(bound(deserialize = "Com<SC>: Deserialize<'de>"))]
+pub struct JobInputs<SC: StarkGenericConfig> {
+    /// The `StarkUtils::NAME` of the config the shards must be proven with.
+    pub config: String,
+
+    /// The preprocessed commitment of the coordinator's proving key.
+    pub preprocessed_commit: Com<SC>,
+
+    /// The program whose execution is proven, which the shards of the work units leave out.
+    pub program: Arc<Program>,
+
+    /// How long a worker may go without renewing its claim on a unit before the coordinator gives
+    /// the unit to another worker.
+    pub lease: Duration,
+
+    /// The main commitment of each shard, in order.
+    pub main_commits: Vec<Com<SC>>,
+
+    /// The public values observed with the main commitment of each shard.
+    pub public_values: Vec<PublicValues<u32>>,
+}
+
+impl<SC: StarkGenericConfig + StarkUtils> JobInputs<SC> {
+    /// A challenger in the state from which every shard is proven.
+    fn challenger(&self, config: &SC) -> SC::Challenger {
+        let mut challenger = config.challenger();
+        challenger.observe(self.preprocessed_commit.clone());
+        observe_shards::<SC>(&mut challenger, &self.main_commits, &self.public_values);
+        challenger
+    }
+}
+
+/// Checks that `challenger` has observed nothing but the preprocessed commitment of `pk`, which
+/// is the state the workers start from, by comparing a challenge sampled from each.
+fn check_challenger<SC: StarkGenericConfig + StarkUtils>(
+    config: &SC,
+    pk: &ProvingKey<SC>,
+    challenger: &SC::Challenger,
+) -> Result<(), ProverError>
+where
+    SC::Challenger: Clone,
+{
+    let mut expected = config.challenger();
+    expected.observe(pk.commit.clone());
+    let mut challenger = challenger.clone();
+    if expected.sample_ext_element::<SC::Challenge>()
+        != challenger.sample_ext_element::<SC::Challenge>()
+    {
+        return Err(ProverError::WorkUnit(
+            "the distributed prover needs a challenger which has only observed the preprocessed \
+             commitment"
+                .to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// A shard to be proven by a worker with the `JobInputs` of its job.
+#[derive(Serialize, Deserialize)]
+pub struct ShardWorkUnit {
+    /// The job the shard belongs to.
+    pub job: String,
+
+    /// The index of the shard in the proof.
+    pub index: usize,
+
+    /// The events of the shard, from which the worker regenerates its traces. The program is left
+    /// out, since it is in the inputs of the job.
+    pub shard: ExecutionRecord,
+}
+
+/// A prover which writes the shards as work units to the `work_dir` of the `ProverOptions` and
+/// waits for workers to prove them.
+///
+/// The coordinator, i.e. the process calling `RiscvStark::prove` with this prover, executes, shards
+/// and commits the execution. Workers, usually other processes or machines sharing the directory,
+/// run `DistributedProver::run_worker` to claim and prove the units, and the coordinator assembles
+/// their shard proofs into the proof. A unit is claimed by renaming its file, so any number of
+/// workers can share the directory.
+///
+/// A worker renews its claim while it proves the unit, and the coordinator puts the unit back in
+/// the queue if the claim is not renewed within the `work_lease` of the options, e.g. because the
+/// worker was stopped. The proof fails if it is not complete within the `work_deadline`. All the
+/// files of the proof are deleted once it is complete or has failed.
+pub struct DistributedProver<SC>(PhantomData<SC>);
+
+impl<SC> Prover<SC> for DistributedProver<SC>
+where
+    SC: StarkGenericConfig + StarkUtils + Send + Sync,
+    SC::Challenger: Clone,
+    Com<SC>: Send + Sync + Serialize + DeserializeOwned,
+    PcsProverData<SC>: Send + Sync,
+    ShardMainData<SC>: Serialize + DeserializeOwned,
+    ShardProof<SC>: DeserializeOwned,
+{
+    fn prove_shards(
+        machine: &RiscvStark<SC>,
+        pk: &ProvingKey<SC>,
+        shards: Vec<ExecutionRecord>,
+        challenger: &mut SC::Challenger,
+        options: &ProverOptions,
+    ) -> Result<Proof<SC>, ProverError> {
+        let config = machine.config();
+        check_challenger(config, pk, challenger)?;
+        let job = Job::new(work_dir(options)?);
+
+        // Only the main commitments are kept, since the workers regenerate the traces.
+        let main_commits = tracing::info_span!("commit main for all shards").in_scope(|| {
+            shards
+                .par_iter()
+                .enumerate()
+                .map(|(index, shard)| -> Result<_, ProverError> {
+                    options.check_cancelled()?;
+                    let data = LocalProver::<SC>::commit_main(config, machine, shard, index);
+                    options.observe(ProverEvent::ShardCommitted { shard: index });
+                    Ok(data.main_commit)
+                })
+                .collect::<Result<Vec<_>, ProverError>>()
+        })?;
+        let public_values = shards
+            .iter()
+            .map(|shard| shard.public_values)
+            .collect::<Vec<_>>();
+        observe_shards::<SC>(challenger, &main_commits, &public_values);
+
+        let program = shards
+            .first()
+            .map(|shard| shard.program.clone())
+            .unwrap_or_default();
+        tracing::info_span!("writing work units").in_scope(|| {
+            shards
+                .into_iter()
+                .enumerate()
+                .try_for_each(|(index, shard)| job.write_unit(index, shard))
+        })?;
+
+        Self::distribute(
+            &job,
+            JobInputs {
+                config: SC::NAME.to_string(),
+                preprocessed_commit: pk.commit.clone(),
+                program,
+                lease: options.work_lease,
+                main_commits,
+                public_values,
+            },
+            options,
+        )
+    }
+
+    fn prove_shards_streaming<I, E>(
+        machine: &RiscvStark<SC>,
+        pk: &ProvingKey<SC>,
+        records: I,
+        challenger: &mut SC::Challenger,
+        options: &ProverOptions,
+    ) -> Result<Proof<SC>, ProverError>
+    where
+        I: IntoIterator<Item = Result<ExecutionRecord, E>>,
+        ProverError: From<E>,
+    {
+        check_challenger(machine.config(), pk, challenger)?;
+        let job = Job::new(work_dir(options)?);
+
+        // The work unit of each shard is written as soon as the shard is committed, and queued
+        // once the commitments of all the shards are known.
+        let mut program = None;
+        let (main_commits, _, public_values) =
+            LocalProver::<SC>::commit_records(machine, records, options, |index, shard, _| {
+                program.get_or_insert_with(|| shard.program.clone());
+                job.write_unit(index, shard)
+            })?;
+        observe_shards::<SC>(challenger, &main_commits, &public_values);
+
+        Self::distribute(
+            &job,
+            JobInputs {
+                config: SC::NAME.to_string(),
+                preprocessed_commit: pk.commit.clone(),
+                program: program.unwrap_or_default(),
+                lease: options.work_lease,
+                main_commits,
+                public_values,
+            },
+            options,
+        )
+    }
+}
+
+impl<SC> DistributedProver<SC>
+where
+    SC: StarkGenericConfig + Send + Sync,
+    SC::Challenger: Clone,
+    Com<SC>: Send + Sync + Serialize + DeserializeOwned,
+    PcsProverData<SC>: Send + Sync,
+    ShardMainData<SC>: Serialize + DeserializeOwned,
+    ShardProof<SC>: DeserializeOwned,
+{
+    /// Writes the inputs of the job, queues the work units written by `Job::write_unit` and waits
+    /// until the workers have proven all of them.
+    fn distribute(
+        job: &Job,
+        inputs: JobInputs<SC>,
+        options: &ProverOptions,
+    ) -> Result<Proof<SC>, ProverError> {
+        let num_shards = inputs.main_commits.len();
+        write_atomically(&job.inputs_path(), &inputs)?;
+        for index in 0..num_shards {
+            fs::rename(
+                job.path(format!("{}.pending", index)),
+                job.path(format!("{}.unit", index)),
+            )
+            .map_err(work_unit_error)?;
+        }
+
+        // Collect the shard proofs as the workers write them.
+        let start = Instant::now();
+        let mut shard_proofs = (0..num_shards).map(|_| None).collect::<Vec<_>>();
+        let mut proven = 0;
+        while proven < num_shards {
+            options.check_cancelled()?;
+            if let Some(deadline) = options.work_deadline {
+                if start.elapsed() > deadline {
+                    return Err(ProverError::WorkUnit(format!(
+                        "the workers did not prove {} of the {} shards within {:?}",
+                        num_shards - proven,
+                        num_shards,
+                        deadline
+                    )));
+                }
+            }
+            job.requeue_stale_claims(inputs.lease, |index| {
+                shard_proofs.get(index).map_or(false, Option::is_some)
+            })?;
+            for (index, shard_proof) in shard_proofs.iter_mut().enumerate() {
+                if shard_proof.is_some() {
+                    continue;
+                }
+                let error_path = job.path(format!("{}.error", index));
+                if error_path.exists() {
+                    let error = read::<String>(&error_path)?;
+                    return Err(ProverError::WorkUnit(format!(
+                        "a worker failed to prove shard {}: {}",
+                        index, error
+                    )));
+                }
+                let proof_path = job.path(format!("{}.proof", index));
+                if proof_path.exists() {
+                    *shard_proof = Some(read::<ShardProof<SC>>(&proof_path)?);
+                    let _ = fs::remove_file(&proof_path);
+                    proven += 1;
+                    options.observe(ProverEvent::shard_proven(index, proven, num_shards, start));
+                }
+            }
+            if proven < num_shards {
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+
+        Ok(Proof {
+            shard_proofs: shard_proofs.into_iter().map(Option::unwrap).collect(),
+        })
+    }
+}
+
+impl<SC> DistributedProver<SC>
+where
+    SC: StarkGenericConfig + StarkUtils + Send + Sync,
+    SC::Challenger: Clone,
+    Com<SC>: Send + Sync + Serialize + DeserializeOwned,
+    PcsProverData<SC>: Send + Sync,
+    ShardMainData<SC>: Serialize + DeserializeOwned,
+    ShardProof<SC>: Serialize,
+{
+    /// Claims and proves the work units in `work_dir` until the cancellation token of `options`
+    /// is cancelled, and returns the number of shards it proved.
+    ///
+    /// The inputs of each job are read once, and the proving key of each program is generated once
+    /// while there are jobs for it. A shard which cannot be proven is reported to the coordinator,
+    /// which then fails.
+    pub fn run_worker(
+        machine: &RiscvStark<SC>,
+        work_dir: &Path,
+        options: &ProverOptions,
+    ) -> Result<usize, ProverError> {
+        let worker = unique_id();
+        let mut cache = WorkerCache::default();
+        let mut proven = 0;
+        while !options.cancellation_token.is_cancelled() {
+            let Some((claimed, name)) = claim(work_dir, &worker)? else {
+                thread::sleep(POLL_INTERVAL);
+                continue;
+            };
+            renew(&claimed);
+            let result = read::<ShardWorkUnit>(&claimed).and_then(|unit| {
+                let Some(job) = cache.job(machine, work_dir, &unit.job)? else {
+                    return Ok(None);
+                };
+                with_heartbeat(&claimed, job.inputs.lease, || {
+                    Self::prove_work_unit(machine, &job.pk, &job.inputs, unit)
+                })
+                .map(Some)
+            });
+
+            // The coordinator deletes the files of a job once it is complete or has failed, so
+            // nothing is written back for a job which has ended in the meantime.
+            let ended = !inputs_path(work_dir, job_of(&name)).exists();
+            match result {
+                Ok(Some(shard_proof)) if !ended => {
+                    write_atomically(&work_dir.join(format!("{}.proof", name)), &shard_proof)?;
+                    proven += 1;
+                }
+                Err(error) if !ended => {
+                    tracing::error!("failed to prove {}: {}", name, error);
+                    write_atomically(
+                        &work_dir.join(format!("{}.error", name)),
+                        &error.to_string(),
+                    )?;
+                }
+                _ => tracing::info!("dropping {}, whose job has ended", name),
+            }
+            // The claim is gone if the unit was given to another worker in the meantime.
+            let _ = fs::remove_file(&claimed);
+        }
+        Ok(proven)
+    }
+
+    /// Proves the shard of a work unit with the inputs of its job and the proving key of its
+    /// program.
+    pub fn prove_work_unit(
+        machine: &RiscvStark<SC>,
+        pk: &ProvingKey<SC>,
+        inputs: &JobInputs<SC>,
+        mut unit: ShardWorkUnit,
+    ) -> Result<ShardProof<SC>, ProverError> {
+        if inputs.config != SC::NAME {
+            return Err(ProverError::WorkUnit(format!(
+                "the work unit must be proven with {}, not {}",
+                inputs.config,
+                SC::NAME
+            )));
+        }
+        let commit = bincode::serialize(&pk.commit).map_err(work_unit_error)?;
+        if commit != bincode::serialize(&inputs.preprocessed_commit).map_err(work_unit_error)? {
+            return Err(ProverError::WorkUnit(
+                "the proving key does not match the preprocessed commitment of the job".to_string(),
+            ));
+        }
+        let (Some(main_commit), Some(public_values)) = (
+            inputs.main_commits.get(unit.index),
+            inputs.public_values.get(unit.index),
+        ) else {
+            return Err(ProverError::WorkUnit(format!(
+                "the work unit has no commitment for shard {}",
+                unit.index
+            )));
+        };
+
+        let config = machine.config();
+        let mut challenger = inputs.challenger(config);
+        unit.shard.program = inputs.program.clone();
+
+        // The traces must be the ones the coordinator committed to.
+        let mut data = tracing::info_span!("shard commit main", shard = unit.index)
+            .in_scope(|| LocalProver::<SC>::commit_main(config, machine, &unit.shard, unit.index));
+        let commit = bincode::serialize(&data.main_commit).map_err(work_unit_error)?;
+        if commit != bincode::serialize(main_commit).map_err(work_unit_error)? {
+            return Err(ProverError::WorkUnit(format!(
+                "the traces of shard {} do not match its main commitment",
+                unit.index
+            )));
+        }
+        data.public_values = public_values.to_vec();
+
+        let chips = machine.shard_chips(&unit.shard).collect::<Vec<_>>();
+        Ok(LocalProver::<SC>::prove_shard(
+            config,
+            pk,
+            &chips,
+            data,
+            &mut challenger,
+        ))
+    }
+}
+
+/// The inputs of a job and the proving key of its program, as loaded by a worker.
+struct CachedJob<SC: StarkGenericConfig> {
+    inputs: JobInputs<SC>,
+    pk: Rc<ProvingKey<SC>>,
+}
+
+/// The jobs a worker has proven units of, and the proving keys of their programs.
+struct WorkerCache<SC: StarkGenericConfig> {
+    jobs: HashMap<String, Rc<CachedJob<SC>>>,
+    /// The proving keys, keyed by the serialized program.
+    pks: HashMap<Vec<u8>, Rc<ProvingKey<SC>>>,
+}
+
+impl<SC: StarkGenericConfig> Default for WorkerCache<SC> {
+    fn default() -> Self {
+        Self {
+            jobs: HashMap::new(),
+            pks: HashMap::new(),
+        }
+    }
+}
+
+impl<SC> WorkerCache<SC>
+where
+    SC: StarkGenericConfig,
+    Com<SC>: DeserializeOwned,
+{
+    /// Returns the inputs and proving key of `job`, or `None` if the job has ended.
+    fn job(
+        &mut self,
+        machine: &RiscvStark<SC>,
+        work_dir: &Path,
+        job: &str,
+    ) -> Result<Option<Rc<CachedJob<SC>>>, ProverError> {
+        // Forget the jobs which have ended, and the proving keys no remaining job uses.
+        self.jobs
+            .retain(|job, _| inputs_path(work_dir, job).exists());
+        self.pks.retain(|_, pk| Rc::strong_count(pk) > 1);
+        if let Some(cached) = self.jobs.get(job) {
+            return Ok(Some(cached.clone()));
+        }
+
+        let path = inputs_path(work_dir, job);
+        let inputs = match read::<JobInputs<SC>>(&path) {
+            Ok(inputs) => inputs,
+            Err(_) if !path.exists() => return Ok(None),
+            Err(error) => return Err(error),
+        };
+        let program = bincode::serialize(&inputs.program).map_err(work_unit_error)?;
+        let pk = self
+            .pks
+            .entry(program)
+            .or_insert_with(|| Rc::new(machine.setup(inputs.program.as_ref()).0))
+            .clone();
+        let cached = Rc::new(CachedJob { inputs, pk });
+        self.jobs.insert(job.to_string(), cached.clone());
+        Ok(Some(cached))
+    }
+}
+
+/// The files of a proof in the work directory, which are all named `{id}-*` and deleted when it is
+/// dropped, i.e. once the proof is complete or has failed.
+struct Job<'a> {
+    work_dir: &'a Path,
+    id: String,
+}
+
+impl<'a> Job<'a> {
+    fn new(work_dir: &'a Path) -> Self {
+        Self {
+            work_dir,
+            id: unique_id(),
+        }
+    }
+
+    fn path(&self, name: impl Display) -> PathBuf {
+        self.work_dir.join(format!("{}-{}", self.id, name))
+    }
+
+    fn inputs_path(&self) -> PathBuf {
+        inputs_path(self.work_dir, &self.id)
+    }
+
+    /// Writes the work unit of a shard, which workers cannot claim until it is queued.
+    fn write_unit(&self, index: usize, mut shard: ExecutionRecord) -> Result<(), ProverError> {
+        shard.program = Arc::default();
+        let unit = ShardWorkUnit {
+            job: self.id.clone(),
+            index,
+            shard,
+        };
+        write_atomically(&self.path(format!("{}.pending", index)), &unit)
+    }
+
+    /// Puts the units whose claims have not been renewed within `lease` back in the queue, since
+    /// the workers which claimed them have presumably stopped, unless the shard is `proven`.
+    fn requeue_stale_claims(
+        &self,
+        lease: Duration,
+        proven: impl Fn(usize) -> bool,
+    ) -> Result<(), ProverError> {
+        let prefix = format!("{}-", self.id);
+        for entry in fs::read_dir(self.work_dir).map_err(work_unit_error)? {
+            let entry = entry.map_err(work_unit_error)?;
+            let file_name = entry.file_name();
+            let Some(claim) = file_name
+                .to_str()
+                .and_then(|name| name.strip_prefix(&prefix))
+                .and_then(|name| name.strip_suffix(".claimed"))
+            else {
+                continue;
+            };
+            let Some(index) = claim
+                .split_once('.')
+                .and_then(|(index, _)| index.parse::<usize>().ok())
+            else {
+                continue;
+            };
+            let renewed = entry.metadata().and_then(|metadata| metadata.modified());
+            let stale = renewed.map_or(false, |renewed| {
+                renewed.elapsed().map_or(false, |elapsed| elapsed > lease)
+            });
+            if stale && !proven(index) {
+                tracing::warn!(
+                    "requeueing shard {}, whose claim has not been renewed within {:?}",
+                    index,
+                    lease
+                );
+                let _ = fs::rename(entry.path(), self.path(format!("{}.unit", index)));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for Job<'_> {
+    fn drop(&mut self) {
+        let prefix = format!("{}-", self.id);
+        let Ok(entries) = fs::read_dir(self.work_dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if entry.file_name().to_string_lossy().starts_with(&prefix) {
+                let _ = fs::remove_file(entry.path());
+            }
+        }
+    }
+}
+
+/// Observes the main commitment and public values of each shard, as the coordinator and every
+/// worker do before proving the shards.
+fn observe_shards<SC: StarkGenericConfig>(
+    challenger: &mut SC::Challenger,
+    main_commits: &[Com<SC>],
+    public_values: &[PublicValues<u32>],
+) {
+    tracing::info_span!("observing all challenges").in_scope(|| {
+        for (commit, public_values) in main_commits.iter().zip(public_values) {
+            challenger.observe(commit.clone());
+            challenger.observe_slice(&public_values.to_vec::<SC::Val>());
+        }
+    });
+}
+
+fn work_dir(options: &ProverOptions) -> Result<&Path, ProverError> {
+    options.work_dir.as_deref().ok_or_else(|| {
+        ProverError::WorkUnit("the distributed prover needs a work directory".to_string())
+    })
+}
+
+fn inputs_path(work_dir: &Path, job: &str) -> PathBuf {
+    work_dir.join(format!("{}-inputs", job))
+}
+
+/// The job of a work unit named `{job}-{index}`.
+fn job_of(name: &str) -> &str {
+    name.rsplit_once('-').map_or(name, |(job, _)| job)
+}
+
+/// A name which is unique across the processes sharing a directory, for the files of a job or the
+/// claims of a worker.
+fn unique_id() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    format!("{}-{}", std::process::id(), nanos)
+}
+
+/// Claims a work unit in `work_dir` for `worker` by renaming it, which only one worker can do, and
+/// returns the path of the claimed unit and its name without the extension.
+fn claim(work_dir: &Path, worker: &str) -> Result<Option<(PathBuf, String)>, ProverError> {
+    for entry in fs::read_dir(work_dir).map_err(work_unit_error)? {
+        let path = entry.map_err(work_unit_error)?.path();
+        if path
+            .extension()
+            .map_or(true, |extension| extension != "unit")
+        {
+            continue;
+        }
+        let Some(name) = path.file_stem().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let claimed = work_dir.join(format!("{}.{}.claimed", name, worker));
+        if fs::rename(&path, &claimed).is_ok() {
+            return Ok(Some((claimed, name.to_string())));
+        }
+    }
+    Ok(None)
+}
+
+/// Renews the claim on a unit by updating the modification time of the claimed file. Nothing is
+/// done if the unit was given to another worker.
+fn renew(claimed: &Path) {
+    let _ = File::options()
+        .write(true)
+        .open(claimed)
+        .and_then(|file| file.set_modified(SystemTime::now()));
+}
+
+/// Runs `f` while renewing the claim on a unit four times per `lease` from another thread.
+fn with_heartbeat<T>(claimed: &Path, lease: Duration, f: impl FnOnce() -> T) -> T {
+    let (stop, stopped) = mpsc::channel::<()>();
+    thread::scope(|scope| {
+        scope.spawn(move || {
+            while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(lease / 4) {
+                renew(claimed);
+            }
+        });
+        let result = f();
+        drop(stop);
+        result
+    })
+}
+
+/// Writes `value` to a temporary file which is then renamed to `path`, so that it is never read
+/// while partially written.
+fn write_atomically<T: Serialize>(path: &Path, value: &T) -> Result<(), ProverError> {
+    let mut temp_path = path.as_os_str().to_owned();
+    temp_path.push(".tmp");
+    let mut writer = BufWriter::new(File::create(&temp_path).map_err(work_unit_error)?);
+    bincode::serialize_into(&mut writer, value).map_err(work_unit_error)?;
+    writer.flush().map_err(work_unit_error)?;
+    fs::rename(&temp_path, path).map_err(work_unit_error)
+}
+
+fn read<T: DeserializeOwned>(path: &Path) -> Result<T, ProverError> {
+    let file = File::open(path).map_err(work_unit_error)?;
+    bincode::deserialize_from(BufReader::new(file)).map_err(work_unit_error)
+}
+
+fn work_unit_error(error: impl Display) -> ProverError {
+    ProverError::WorkUnit(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+    use std::path::Path;
+    use std::process::{Child, Command, Stdio};
+    use std::thread;
+    use std::time::Duration;
+
+    use p3_baby_bear::BabyBear;
+    use p3_challenger::CanObserve;
+    use p3_field::AbstractField;
+
+    use super::{claim, DistributedProver, JobInputs, ShardWorkUnit, POLL_INTERVAL};
+    use crate::runtime::{ExecutionRecord, Instruction, Opcode, Program, Runtime};
+    use crate::stark::{Proof, ProverError, RiscvStark};
+    use crate::utils::{BabyBearBlake3, ProverOptions, StarkUtils};
+
+    /// The environment variable giving the work directory to `worker_process`.
+    const WORKER_DIR: &str = "SP1_TEST_WORKER_DIR";
+
+    /// A worker running in a child process, which is killed when dropped.
+    struct WorkerProcess(Child);
+
+    impl WorkerProcess {
+        /// Runs `worker_process` in a new process of this test binary.
+        fn spawn(work_dir: &Path) -> Self {
+            let child = Command::new(env::current_exe().unwrap())
+                .args([
+                    "stark::distributed::tests::worker_process",
+                    "--exact",
+                    "--ignored",
+                ])
+                .env(WORKER_DIR, work_dir)
+                .stdout(Stdio::null())
+                .spawn()
+                .unwrap();
+            Self(child)
+        }
+    }
+
+    impl Drop for WorkerProcess {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    /// Runs a worker on the directory given by the test which spawned this process, until it is
+    /// killed.
+    #[test]
+    #[ignore]
+    fn worker_process() {
+        let Some(work_dir) = env::var_os(WORKER_DIR) else {
+            return;
+        };
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        DistributedProver::run_worker(&machine, Path::new(&work_dir), &ProverOptions::default())
+            .unwrap();
+    }
+
+    fn countdown_program() -> Program {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 100, false, true),
+            Instruction::new(Opcode::ADD, 29, 29, (-1i32) as u32, false, true),
+            Instruction::new(Opcode::BNE, 29, 0, (-4i32) as u32, false, true),
+        ];
+        Program::new(instructions, 0, 0)
+    }
+
+    /// Proves `countdown_program` with the distributed prover, and verifies the proof if any.
+    fn prove(options: &ProverOptions) -> Result<Proof<BabyBearBlake3>, ProverError> {
+        let options = options.clone().with_shard_size(1 << 6);
+        let mut runtime = Runtime::with_options(countdown_program(), &options);
+        runtime.run().unwrap();
+
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine.prove::<DistributedProver<_>>(
+            &pk,
+            runtime.record,
+            &mut challenger,
+            &options,
+        )?;
+        assert!(proof.shard_proofs.len() > 1);
+
+        let mut challenger = machine.config().challenger();
+        machine.verify(&vk, &proof, &mut challenger).unwrap();
+        Ok(proof)
+    }
+
+    #[test]
+    fn test_distributed_prove() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let _workers = (0..3)
+            .map(|_| WorkerProcess::spawn(work_dir.path()))
+            .collect::<Vec<_>>();
+
+        prove(&ProverOptions::default().with_work_dir(work_dir.path())).unwrap();
+        assert_eq!(std::fs::read_dir(work_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_distributed_prove_requeues_stale_claims() {
+        let work_dir = tempfile::tempdir().unwrap();
+
+        // A worker which claims a unit and stops without proving it. The live worker is only
+        // started once the unit is claimed, so that it cannot take all the units first.
+        let stopped_worker = {
+            let work_dir = work_dir.path().to_path_buf();
+            thread::spawn(move || loop {
+                if let Some((claimed, _)) = claim(&work_dir, "stopped").unwrap() {
+                    return (claimed, WorkerProcess::spawn(&work_dir));
+                }
+                thread::sleep(POLL_INTERVAL);
+            })
+        };
+
+        let options = ProverOptions::default()
+            .with_work_dir(work_dir.path())
+            .with_work_lease(Duration::from_secs(1));
+        prove(&options).unwrap();
+        let (claimed, _worker) = stopped_worker.join().unwrap();
+        assert!(!claimed.exists());
+        assert_eq!(std::fs::read_dir(work_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_distributed_prove_deadline() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let options = ProverOptions::default()
+            .with_work_dir(work_dir.path())
+            .with_work_deadline(Duration::from_millis(500));
+        let result = prove(&options);
+        assert!(matches!(result, Err(ProverError::WorkUnit(_))));
+
+        // The inputs and units of the failed proof are withdrawn.
+        assert_eq!(std::fs::read_dir(work_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_distributed_prove_without_work_dir() {
+        let mut runtime = Runtime::new(Program::new(
+            vec![Instruction::new(Opcode::ADD, 29, 0, 5, false, true)],
+            0,
+            0,
+        ));
+        runtime.run().unwrap();
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, _) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let result = machine.prove::<DistributedProver<_>>(
+            &pk,
+            runtime.record,
+            &mut challenger,
+            &ProverOptions::default(),
+        );
+        assert!(matches!(result, Err(ProverError::WorkUnit(_))));
+    }
+
+    #[test]
+    fn test_distributed_prove_with_used_challenger() {
+        let work_dir = tempfile::tempdir().unwrap();
+        let options = ProverOptions::default().with_work_dir(work_dir.path());
+        let mut runtime = Runtime::with_options(countdown_program(), &options);
+        runtime.run().unwrap();
+
+        // The workers cannot reproduce a challenger which has observed anything else.
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, _) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        challenger.observe(BabyBear::one());
+        let result =
+            machine.prove::<DistributedProver<_>>(&pk, runtime.record, &mut challenger, &options);
+        assert!(matches!(result, Err(ProverError::WorkUnit(_))));
+        assert_eq!(std::fs::read_dir(work_dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_prove_work_unit_mismatch() {
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let program = countdown_program();
+        let (pk, _) = machine.setup(&program);
+        let other_program = Program::new(
+            vec![Instruction::new(Opcode::ADD, 29, 0, 5, false, true)],
+            0,
+            0,
+        );
+        let (other_pk, _) = machine.setup(&other_program);
+        let inputs = |config: &str| JobInputs {
+            config: config.to_string(),
+            preprocessed_commit: pk.commit.clone(),
+            program: program.clone().into(),
+            lease: Duration::from_secs(1),
+            main_commits: Vec::new(),
+            public_values: Vec::new(),
+        };
+        let unit = || ShardWorkUnit {
+            job: "job".to_string(),
+            index: 0,
+            shard: ExecutionRecord::default(),
+        };
+
+        // A worker with another config or proving key rejects the unit.
+        let result =
+            DistributedProver::prove_work_unit(&machine, &pk, &inputs("baby-bear-keccak"), unit());
+        assert!(matches!(result, Err(ProverError::WorkUnit(_))));
+        let result = DistributedProver::prove_work_unit(
+            &machine,
+            &other_pk,
+            &inputs(BabyBearBlake3::NAME),
+            unit(),
+        );
+        assert!(matches!(result, Err(ProverError::WorkUnit(_))));
+    }
+}
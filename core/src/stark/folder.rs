@@ -1,5 +1,5 @@
 use super::{PackedChallenge, PackedVal, StarkGenericConfig};
-use crate::air::{EmptyMessageBuilder, MultiTableAirBuilder};
+use crate::air::{EmptyMessageBuilder, MultiTableAirBuilder, PublicValuesAirBuilder};
 use p3_air::{AirBuilder, ExtensionBuilder, PairBuilder, PermutationAirBuilder, TwoRowMatrixView};
 use p3_field::AbstractField;
 
@@ -9,6 +9,7 @@ pub struct ProverConstraintFolder<'a, SC: StarkGenericConfig> {
     pub main: TwoRowMatrixView<'a, PackedVal<SC>>,
     pub perm: TwoRowMatrixView<'a, PackedChallenge<SC>>,
     pub perm_challenges: &'a [SC::Challenge],
+    pub public_values: &'a [SC::Val],
     pub cumulative_sum: SC::Challenge,
     pub is_first_row: PackedVal<SC>,
     pub is_last_row: PackedVal<SC>,
@@ -93,6 +94,12 @@ impl<'a, SC: StarkGenericConfig> PairBuilder for ProverConstraintFolder<'a, SC>
     }
 }
 
+impl<'a, SC: StarkGenericConfig> PublicValuesAirBuilder for ProverConstraintFolder<'a, SC> {
+    fn public_values(&self) -> &[Self::F] {
+        self.public_values
+    }
+}
+
 impl<'a, SC: StarkGenericConfig> EmptyMessageBuilder for ProverConstraintFolder<'a, SC> {}
 
 /// A folder for verifier constraints.
@@ -101,6 +108,7 @@ pub struct VerifierConstraintFolder<'a, SC: StarkGenericConfig> {
     pub main: TwoRowMatrixView<'a, SC::Challenge>,
     pub perm: TwoRowMatrixView<'a, SC::Challenge>,
     pub perm_challenges: &'a [SC::Challenge],
+    pub public_values: &'a [SC::Val],
     pub cumulative_sum: SC::Challenge,
     pub is_first_row: SC::Challenge,
     pub is_last_row: SC::Challenge,
@@ -181,4 +189,10 @@ impl<'a, SC: StarkGenericConfig> PairBuilder for VerifierConstraintFolder<'a, SC
     }
 }
 
+impl<'a, SC: StarkGenericConfig> PublicValuesAirBuilder for VerifierConstraintFolder<'a, SC> {
+    fn public_values(&self) -> &[Self::F] {
+        self.public_values
+    }
+}
+
 impl<'a, SC: StarkGenericConfig> EmptyMessageBuilder for VerifierConstraintFolder<'a, SC> {}
@@ -1,16 +1,26 @@
-use std::marker::PhantomData;
+use std::collections::HashMap;
 
 use crate::air::MachineAir;
+use crate::air::PublicValues;
 use crate::runtime::ExecutionRecord;
 use crate::runtime::Program;
 use crate::runtime::ShardingConfig;
+use crate::syscall::precompiles::custom::Precompiles;
+use crate::utils::{ProverEvent, ProverOptions};
 use p3_challenger::CanObserve;
+use p3_commit::Pcs;
 use p3_field::AbstractField;
 use p3_field::Field;
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::Dimensions;
+use p3_matrix::Matrix;
 
 use super::Chip;
+use super::Com;
+use super::PcsProverData;
 use super::Proof;
 use super::Prover;
+use super::ProverError;
 use super::RiscvAir;
 use super::StarkGenericConfig;
 use super::VerificationError;
@@ -27,28 +37,44 @@ pub struct RiscvStark<SC: StarkGenericConfig, A = RiscvAir<<SC as StarkGenericCo
     chips: Vec<Chip<SC::Val, A>>,
 }
 
-#[derive(Debug, Clone)]
+/// The proving key of a program, containing the preprocessed traces and their commitment.
 pub struct ProvingKey<SC: StarkGenericConfig> {
-    //TODO
-    marker: std::marker::PhantomData<SC>,
+    /// The commitment to the preprocessed traces.
+    pub commit: Com<SC>,
+    /// The preprocessed traces, in the order of the chips that have them.
+    pub traces: Vec<RowMajorMatrix<SC::Val>>,
+    /// The prover data for the preprocessed commitment.
+    pub data: PcsProverData<SC>,
+    /// A map from the chip name to the index of its preprocessed trace.
+    pub chip_ordering: HashMap<String, usize>,
 }
 
-#[derive(Debug, Clone)]
+/// The verifying key of a program, binding proofs to its preprocessed traces.
+#[derive(Clone)]
 pub struct VerifyingKey<SC: StarkGenericConfig> {
-    // TODO:
-    marker: std::marker::PhantomData<SC>,
+    /// The commitment to the preprocessed traces.
+    pub commit: Com<SC>,
+    /// The name and dimensions of each preprocessed trace, in commitment order.
+    pub chip_information: Vec<(String, Dimensions)>,
 }
 
 impl<SC: StarkGenericConfig> RiscvStark<SC> {
     /// Create a new RISC-V STARK machine.
     pub fn new(config: SC) -> Self {
+        Self::with_precompiles(config, &Precompiles::new())
+    }
+
+    /// Create a new RISC-V STARK machine which also proves the given precompiles.
+    ///
+    /// Panics if the chip of a precompile is not defined over the field of `config`.
+    pub fn with_precompiles(config: SC, precompiles: &Precompiles) -> Self {
         // The machine consists of a config (input) and a set of chips. The chip vector should
         // contain the chips in the order they are executed. Each chip's air is able to add events
         // to another chip's record (depending on interactions), so we order the chips by keeping
         // track of which chips receive events from which other chips.
 
         // First, get all the chips associated with this machine.
-        let chips = RiscvAir::get_all()
+        let chips = RiscvAir::with_precompiles(precompiles)
             .into_iter()
             .map(Chip::new)
             .collect::<Vec<_>>();
@@ -75,65 +101,132 @@ impl<SC: StarkGenericConfig> RiscvStark<SC> {
     ///
     /// Given a program, this function generates the proving and verifying keys. The keys correspond
     /// to the program code and other preprocessed colunms such as lookup tables.
-    pub fn setup(&self, _program: &Program) -> (ProvingKey<SC>, VerifyingKey<SC>) {
+    pub fn setup(&self, program: &Program) -> (ProvingKey<SC>, VerifyingKey<SC>) {
+        // Generate the preprocessed traces for the chips that have them.
+        let (chip_information, traces): (Vec<_>, Vec<_>) =
+            tracing::info_span!("generate preprocessed traces").in_scope(|| {
+                self.chips()
+                    .iter()
+                    .filter_map(|chip| {
+                        chip.generate_preprocessed_trace(program).map(|trace| {
+                            let dimensions = Dimensions {
+                                width: trace.width(),
+                                height: trace.height(),
+                            };
+                            ((chip.name(), dimensions), trace)
+                        })
+                    })
+                    .unzip()
+            });
+
+        // Commit to the preprocessed traces.
+        let (commit, data) = tracing::info_span!("commit preprocessed traces")
+            .in_scope(|| self.config.pcs().commit_batches(traces.clone()));
+
+        let chip_ordering = chip_information
+            .iter()
+            .enumerate()
+            .map(|(i, (name, _))| (name.clone(), i))
+            .collect::<HashMap<_, _>>();
+
         (
             ProvingKey {
-                marker: PhantomData,
+                commit: commit.clone(),
+                traces,
+                data,
+                chip_ordering,
             },
             VerifyingKey {
-                marker: PhantomData,
+                commit,
+                chip_information,
             },
         )
     }
 
     pub fn shard(
         &self,
-        mut record: ExecutionRecord,
+        record: ExecutionRecord,
         shard_config: &ShardingConfig,
     ) -> Vec<ExecutionRecord> {
         // Get the local and global chips.
         let chips = self.chips();
 
-        tracing::info!("Generating trace for each chip.");
         // Display the statistics about the workload. This is incomplete because it's run before
         // generate_trace, which can adds events to the record.
         tracing::info!(
             "Record stats before generate_trace (incomplete): {:#?}",
             record.stats()
         );
+        tracing::info!("Sharding execution record by chip.");
 
-        // Generate the trace for each chip to collect events emitted from chips with dependencies.
-        chips.iter().for_each(|chip| {
-            let mut output = ExecutionRecord::default();
-            output.index = record.index;
-            chip.generate_dependencies(&record, &mut output);
-            record.append(&mut output);
+        // For each chip, shard the events into segments.
+        let mut shards = record.shard(shard_config);
+
+        // Generate the trace for each chip of each shard to collect events emitted from chips with
+        // dependencies, so that they end up in the shard of the events they come from.
+        tracing::info!("Generating trace for each chip.");
+        shards.iter_mut().for_each(|shard| {
+            chips.iter().for_each(|chip| {
+                let mut output = ExecutionRecord::default();
+                output.index = shard.index;
+                chip.generate_dependencies(shard, &mut output);
+                shard.append(&mut output);
+            });
         });
 
         // Display the statistics about the workload after generate_trace.
-        tracing::info!("Record stats finalized {:#?}", record.stats());
-        tracing::info!("Sharding execution record by chip.");
-
-        // For each chip, shard the events into segments.
+        for shard in shards.iter() {
+            tracing::info!("Shard {} stats finalized {:#?}", shard.index, shard.stats());
+        }
 
-        record.shard(shard_config)
+        shards
     }
 
     /// Prove the execution record is valid.
     ///
     /// Given a proving key `pk` and a matching execution record `record`, this function generates
-    /// a STARK proof that the execution record is valid.
+    /// a STARK proof that the execution record is valid, sharded as set in `options`. The progress
+    /// is reported to the observer of `options`, and the proof fails with `ProverError::Cancelled`
+    /// if its cancellation token is cancelled. The execution itself is reported by the runtime
+    /// created with the same options.
     pub fn prove<P: Prover<SC>>(
         &self,
         pk: &ProvingKey<SC>,
         record: ExecutionRecord,
         challenger: &mut SC::Challenger,
-    ) -> Proof<SC> {
+        options: &ProverOptions,
+    ) -> Result<Proof<SC>, ProverError> {
         tracing::info!("Sharding the execution record.");
-        let shards = self.shard(record, &ShardingConfig::default());
+        let shards = self.shard(record, &ShardingConfig::new(options));
+        options.observe(ProverEvent::Sharded {
+            num_shards: shards.len(),
+        });
+
+        // Observe the preprocessed commitment so that all challenges depend on the program.
+        challenger.observe(pk.commit.clone());
 
         tracing::info!("Generating the shard proofs.");
-        P::prove_shards(self, pk, shards, challenger)
+        P::prove_shards(self, pk, shards, challenger, options)
+    }
+
+    /// Prove an execution whose records are produced one execution shard at a time, for example
+    /// by `Runtime::execute_shard`, without holding the events of the whole execution in memory.
+    pub fn prove_streaming<P: Prover<SC>, I, E>(
+        &self,
+        pk: &ProvingKey<SC>,
+        records: I,
+        challenger: &mut SC::Challenger,
+        options: &ProverOptions,
+    ) -> Result<Proof<SC>, ProverError>
+    where
+        I: IntoIterator<Item = Result<ExecutionRecord, E>>,
+        ProverError: From<E>,
+    {
+        // Observe the preprocessed commitment so that all challenges depend on the program.
+        challenger.observe(pk.commit.clone());
+
+        tracing::info!("Generating the shard proofs.");
+        P::prove_shards_streaming(self, pk, records, challenger, options)
     }
 
     pub const fn config(&self) -> &SC {
@@ -142,22 +235,48 @@ impl<SC: StarkGenericConfig> RiscvStark<SC> {
 
     pub fn verify(
         &self,
-        _vk: &VerifyingKey<SC>,
+        vk: &VerifyingKey<SC>,
         proof: &Proof<SC>,
         challenger: &mut SC::Challenger,
     ) -> Result<(), ProgramVerificationError>
     where
         SC::Challenger: Clone,
     {
+        // Observe the preprocessed commitment.
+        challenger.observe(vk.commit.clone());
+
         // TODO: Observe the challenges in a tree-like structure for easily verifiable reconstruction
         // in a map-reduce recursion setting.
         #[cfg(feature = "perf")]
         tracing::info_span!("observe challenges for all segments").in_scope(|| {
             proof.shard_proofs.iter().for_each(|proof| {
                 challenger.observe(proof.commitment.main_commit.clone());
+                challenger.observe_slice(&proof.public_values);
             });
         });
 
+        // A proof without shards proves nothing about the execution.
+        if proof.shard_proofs.is_empty() {
+            return Err(ProgramVerificationError::EmptyProof);
+        }
+
+        // Check that all shards expose the same public values, and that at most one has halted.
+        let public_values = proof
+            .shard_proofs
+            .iter()
+            .map(|shard_proof| PublicValues::<u32>::from_vec(&shard_proof.public_values))
+            .collect::<Option<Vec<_>>>()
+            .ok_or(ProgramVerificationError::PublicValuesMismatch)?;
+        let first = public_values[0].without_halted();
+        let num_halted = public_values.iter().filter(|pv| pv.halted == 1).count();
+        if num_halted > 1
+            || public_values
+                .iter()
+                .any(|pv| pv.halted > 1 || pv.without_halted() != first)
+        {
+            return Err(ProgramVerificationError::PublicValuesMismatch);
+        }
+
         // Verify the segment proofs.
         for (i, proof) in proof.shard_proofs.iter().enumerate() {
             tracing::info_span!("verifying segment", segment = i).in_scope(|| {
@@ -166,7 +285,7 @@ impl<SC: StarkGenericConfig> RiscvStark<SC> {
                     .iter()
                     .filter(|chip| proof.chip_ids.contains(&chip.name()))
                     .collect::<Vec<_>>();
-                Verifier::verify_shard(&self.config, &chips, &mut challenger.clone(), proof)
+                Verifier::verify_shard(&self.config, vk, &chips, &mut challenger.clone(), proof)
                     .map_err(ProgramVerificationError::InvalidSegmentProof)
             })?;
         }
@@ -193,22 +312,41 @@ pub enum ProgramVerificationError {
     InvalidGlobalProof(VerificationError),
     NonZeroCumulativeSum,
     DebugInteractionsFailed,
+    PublicValuesMismatch,
+    InvalidPublicValuesDigest,
+    EmptyProof,
+    NotHalted,
 }
 
 #[cfg(test)]
 #[allow(non_snake_case)]
 pub mod tests {
 
+    use crate::air::PublicValues;
     use crate::runtime::tests::ecall_lwa_program;
     use crate::runtime::tests::fibonacci_program;
     use crate::runtime::tests::simple_memory_program;
     use crate::runtime::tests::simple_program;
+    use crate::runtime::ExecutionError;
     use crate::runtime::Instruction;
     use crate::runtime::Opcode;
     use crate::runtime::Program;
+    use crate::runtime::Runtime;
+    use crate::runtime::ShardingConfig;
+    use crate::stark::LocalProver;
+    use crate::stark::ProgramVerificationError;
+    use crate::stark::ProverError;
+    use crate::stark::RiscvStark;
     use crate::utils;
     use crate::utils::run_test;
+    use crate::utils::run_test_with_options;
     use crate::utils::setup_logger;
+    use crate::utils::tests::SHA2_ELF;
+    use crate::utils::BabyBearBlake3;
+    use crate::utils::CancellationToken;
+    use crate::utils::ProverEvent;
+    use crate::utils::ProverOptions;
+    use crate::utils::StarkUtils;
 
     #[test]
     fn test_simple_prove() {
@@ -216,6 +354,188 @@ pub mod tests {
         run_test(program).unwrap();
     }
 
+    #[test]
+    #[cfg(feature = "perf")]
+    fn test_verify_wrong_program() {
+        let mut runtime = Runtime::new(simple_program());
+        runtime.run().unwrap();
+
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, _) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(
+                &pk,
+                runtime.record,
+                &mut challenger,
+                &ProverOptions::default(),
+            )
+            .unwrap();
+
+        // The verifying key of a different program must reject the proof.
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 5, false, true),
+            Instruction::new(Opcode::ADD, 30, 0, 37, false, true),
+            Instruction::new(Opcode::SUB, 31, 30, 29, false, false),
+        ];
+        let (_, vk) = machine.setup(&Program::new(instructions, 0, 0));
+        let mut challenger = machine.config().challenger();
+        assert!(machine.verify(&vk, &proof, &mut challenger).is_err());
+    }
+
+    #[test]
+    fn test_verify_empty_proof() {
+        let mut runtime = Runtime::new(simple_program());
+        runtime.run().unwrap();
+
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let mut proof = machine
+            .prove::<LocalProver<_>>(
+                &pk,
+                runtime.record,
+                &mut challenger,
+                &ProverOptions::default(),
+            )
+            .unwrap();
+
+        // Dropping every shard must not leave a proof which verifies trivially.
+        proof.shard_proofs.clear();
+        let mut challenger = machine.config().challenger();
+        assert!(matches!(
+            machine.verify(&vk, &proof, &mut challenger),
+            Err(ProgramVerificationError::EmptyProof)
+        ));
+    }
+
+    fn commit_program(public_values: &PublicValues<u32>) -> Program {
+        let mut instructions = vec![Instruction::new(Opcode::ADD, 5, 0, 113, false, true)];
+        for (i, word) in public_values.committed_words().enumerate() {
+            instructions.extend(vec![
+                Instruction::new(Opcode::ADD, 10, 0, i as u32, false, true),
+                Instruction::new(Opcode::ADD, 11, 0, *word, false, true),
+                Instruction::new(Opcode::ECALL, 10, 5, 11, false, false),
+            ]);
+        }
+        instructions.extend(vec![
+            Instruction::new(Opcode::ADD, 5, 0, 100, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, public_values.exit_code, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 11, false, false),
+        ]);
+        // Halting jumps to pc 0, so the program must start elsewhere.
+        Program::new(instructions, 0x1000, 0x1000)
+    }
+
+    #[test]
+    fn test_commit_prove() {
+        let public_values = PublicValues::from_io(b"public input", b"public values", 0);
+        let program = commit_program(&public_values);
+        run_test(program).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "perf")]
+    fn test_commit_wrong_public_values() {
+        let public_values = PublicValues::from_io(b"public input", b"public values", 0);
+        let mut runtime = Runtime::new(commit_program(&public_values));
+        runtime.run().unwrap();
+        assert_eq!(runtime.record.public_values, public_values);
+
+        // Claiming a different digest than the one committed by the program must be rejected.
+        runtime.record.public_values = PublicValues::from_io(b"other input", b"public values", 0);
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(
+                &pk,
+                runtime.record,
+                &mut challenger,
+                &ProverOptions::default(),
+            )
+            .unwrap();
+        let mut challenger = machine.config().challenger();
+        assert!(machine.verify(&vk, &proof, &mut challenger).is_err());
+    }
+
+    #[test]
+    fn test_prove_nonzero_exit_code() {
+        let public_values = PublicValues::from_io(b"public input", b"public values", 1);
+        let program = commit_program(&public_values);
+
+        // By default, a failing execution is an error.
+        let mut runtime = Runtime::with_options(program.clone(), &ProverOptions::default());
+        assert!(matches!(
+            runtime.run(),
+            Err(ExecutionError::NonZeroExitCode { exit_code: 1, .. })
+        ));
+
+        // It can be proven with its exit code when allowed.
+        let options = ProverOptions::default().with_allow_nonzero_exit(true);
+        let mut runtime = Runtime::with_options(program, &options);
+        runtime.run().unwrap();
+        let stdout = runtime.stdout();
+        assert_eq!(stdout.exit_code, 1);
+        assert!(matches!(
+            stdout.panic,
+            Some(ExecutionError::NonZeroExitCode { exit_code: 1, .. })
+        ));
+        assert_eq!(runtime.record.public_values, public_values);
+        run_test_with_options(runtime, &options).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "perf")]
+    fn test_wrong_exit_code() {
+        let public_values = PublicValues::from_io(b"public input", b"public values", 1);
+        let mut runtime = Runtime::new(commit_program(&public_values));
+        runtime.fail_on_panic = false;
+        runtime.run().unwrap();
+        assert_eq!(runtime.record.public_values, public_values);
+
+        // A failing execution must not be provable as a successful one.
+        runtime.record.public_values.exit_code = 0;
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(
+                &pk,
+                runtime.record,
+                &mut challenger,
+                &ProverOptions::default(),
+            )
+            .unwrap();
+        let mut challenger = machine.config().challenger();
+        assert!(machine.verify(&vk, &proof, &mut challenger).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "perf")]
+    fn test_halted_without_halt() {
+        let mut runtime = Runtime::new(simple_program());
+        runtime.run().unwrap();
+        assert_eq!(runtime.record.public_values.halted, 0);
+
+        // A program which never halts must not be provable as one which halted with any public
+        // values.
+        runtime.record.public_values = PublicValues::from_io(b"public input", b"public values", 0);
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(
+                &pk,
+                runtime.record,
+                &mut challenger,
+                &ProverOptions::default(),
+            )
+            .unwrap();
+        let mut challenger = machine.config().challenger();
+        assert!(machine.verify(&vk, &proof, &mut challenger).is_err());
+    }
+
     #[test]
     fn test_ecall_lwa_prove() {
         let program = ecall_lwa_program();
@@ -351,6 +671,145 @@ pub mod tests {
         run_test(program).unwrap();
     }
 
+    #[test]
+    fn test_prove_streaming() {
+        // A loop which decrements x29 from 1000 to zero, spanning several shards.
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 1000, false, true),
+            Instruction::new(Opcode::ADD, 29, 29, (-1i32) as u32, false, true),
+            Instruction::new(Opcode::BNE, 29, 0, (-4i32) as u32, false, true),
+        ];
+        let options = ProverOptions::default().with_shard_size(1 << 6);
+        let mut runtime = Runtime::with_options(Program::new(instructions, 0, 0), &options);
+
+        let config = BabyBearBlake3::new();
+        let machine = RiscvStark::new(config);
+        let (pk, vk) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let records = std::iter::from_fn(|| runtime.execute_shard().transpose());
+        let proof = machine
+            .prove_streaming::<LocalProver<_>, _, _>(&pk, records, &mut challenger, &options)
+            .unwrap();
+        assert!(proof.shard_proofs.len() > 1);
+
+        let mut challenger = machine.config().challenger();
+        machine.verify(&vk, &proof, &mut challenger).unwrap();
+    }
+
+    #[test]
+    fn test_prove_progress() {
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 29, 0, 100, false, true),
+            Instruction::new(Opcode::ADD, 29, 29, (-1i32) as u32, false, true),
+            Instruction::new(Opcode::BNE, 29, 0, (-4i32) as u32, false, true),
+        ];
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let options = ProverOptions::default()
+            .with_shard_size(1 << 6)
+            .with_observer(sender);
+        let mut runtime = Runtime::with_options(Program::new(instructions, 0, 0), &options);
+        runtime.run().unwrap();
+        let cycles = runtime.state.global_clk as u64;
+
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, _) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let proof = machine
+            .prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger, &options)
+            .unwrap();
+        let num_shards = proof.shard_proofs.len();
+        assert!(num_shards > 1);
+
+        let events = receiver.try_iter().collect::<Vec<_>>();
+        let executed = events
+            .iter()
+            .map_while(|event| match event {
+                ProverEvent::Executed { cycles } => Some(*cycles),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(executed.len() > 1);
+        assert!(executed.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!(executed.last(), Some(&cycles));
+        assert_eq!(events[executed.len()], ProverEvent::Sharded { num_shards });
+        let mut committed = events
+            .iter()
+            .filter_map(|event| match event {
+                ProverEvent::ShardCommitted { shard } => Some(*shard),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        committed.sort();
+        assert_eq!(committed, (0..num_shards).collect::<Vec<_>>());
+        let mut proven = events
+            .iter()
+            .filter_map(|event| match event {
+                ProverEvent::ShardProven { proven, .. } => Some(*proven),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        proven.sort();
+        assert_eq!(proven, (1..=num_shards).collect::<Vec<_>>());
+        assert!(events.iter().any(|event| matches!(
+            event,
+            ProverEvent::ShardProven { proven, remaining, .. }
+                if *proven == num_shards && remaining.is_zero()
+        )));
+    }
+
+    #[test]
+    fn test_prove_cancelled() {
+        let mut runtime = Runtime::new(fibonacci_program());
+        runtime.run().unwrap();
+        let token = CancellationToken::new();
+        let options = ProverOptions::default().with_cancellation_token(token.clone());
+        token.cancel();
+
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let (pk, _) = machine.setup(runtime.program.as_ref());
+        let mut challenger = machine.config().challenger();
+        let result =
+            machine.prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger, &options);
+        assert!(matches!(result, Err(ProverError::Cancelled)));
+    }
+
+    #[test]
+    fn test_shard_precompile_events() {
+        let program = Program::from(SHA2_ELF);
+        let mut runtime = Runtime::new(program);
+        runtime.run().unwrap();
+        let nb_sha_extend_events = runtime.record.sha_extend_events.len();
+        let nb_sha_compress_events = runtime.record.sha_compress_events.len();
+        assert!(nb_sha_extend_events > 0);
+
+        let config = ShardingConfig {
+            shard_size: 1 << 8,
+            sha_extend_len: 1,
+            sha_compress_len: 1,
+            ..Default::default()
+        };
+        let machine = RiscvStark::new(BabyBearBlake3::new());
+        let shards = machine.shard(runtime.record, &config);
+        assert!(shards.len() >= nb_sha_extend_events);
+        assert!(shards.iter().all(|shard| shard.sha_extend_events.len() <= 1
+            && shard.sha_compress_events.len() <= 1
+            && !shard.byte_lookups.is_empty()));
+        assert_eq!(
+            shards
+                .iter()
+                .map(|shard| shard.sha_extend_events.len())
+                .sum::<usize>(),
+            nb_sha_extend_events
+        );
+        assert_eq!(
+            shards
+                .iter()
+                .map(|shard| shard.sha_compress_events.len())
+                .sum::<usize>(),
+            nb_sha_compress_events
+        );
+    }
+
     #[test]
     fn test_simple_memory_program_prove() {
         let program = simple_memory_program();
@@ -2,6 +2,7 @@ mod air;
 mod chip;
 mod config;
 mod debug;
+mod distributed;
 mod folder;
 mod machine;
 mod permutation;
@@ -16,6 +17,7 @@ pub use air::*;
 pub use chip::*;
 pub use config::*;
 pub use debug::*;
+pub use distributed::*;
 pub use folder::*;
 pub use machine::*;
 pub use permutation::*;
@@ -14,13 +14,16 @@ use p3_util::log2_ceil_usize;
 use p3_util::log2_strict_usize;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::fmt::{Display, Formatter};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
 
 use super::util::decompose_and_flatten;
 use super::{types::*, StarkGenericConfig};
-use crate::air::MachineAir;
-use crate::runtime::ExecutionRecord;
-use crate::utils::env;
+use crate::air::{MachineAir, PublicValues};
+use crate::runtime::{ExecutionError, ExecutionRecord, ShardingConfig};
+use crate::utils::{ProverEvent, ProverOptions};
 
 #[cfg(not(feature = "perf"))]
 use crate::stark::debug_constraints;
@@ -35,13 +38,74 @@ fn chunk_vec<T>(mut vec: Vec<T>, chunk_size: usize) -> Vec<Vec<T>> {
     result
 }
 
+/// An error that occurred while executing or proving a program.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProverError {
+    /// The execution of the program failed.
+    Execution(ExecutionError),
+
+    /// The proof was cancelled through the cancellation token of its `ProverOptions`.
+    Cancelled,
+
+    /// A work unit or shard proof could not be exchanged with a worker of `DistributedProver`, or
+    /// the worker failed to prove it.
+    WorkUnit(String),
+
+    /// The main data of a shard could not be saved to or loaded from a temporary file.
+    Io(String),
+}
+
+impl From<ExecutionError> for ProverError {
+    fn from(error: ExecutionError) -> Self {
+        match error {
+            ExecutionError::Cancelled { .. } => ProverError::Cancelled,
+            error => ProverError::Execution(error),
+        }
+    }
+}
+
+impl Display for ProverError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProverError::Execution(error) => write!(f, "execution failed: {}", error),
+            ProverError::Cancelled => write!(f, "proof cancelled"),
+            ProverError::WorkUnit(description) => {
+                write!(f, "distributed proving failed: {}", description)
+            }
+            ProverError::Io(description) => {
+                write!(f, "failed to store shard main data: {}", description)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ProverError {}
+
+fn io_error(error: impl Display) -> ProverError {
+    ProverError::Io(error.to_string())
+}
+
 pub trait Prover<SC: StarkGenericConfig> {
     fn prove_shards(
         machine: &RiscvStark<SC>,
         pk: &ProvingKey<SC>,
         shards: Vec<ExecutionRecord>,
         challenger: &mut SC::Challenger,
-    ) -> Proof<SC>;
+        options: &ProverOptions,
+    ) -> Result<Proof<SC>, ProverError>;
+
+    /// Prove the execution records produced by `records` one execution shard at a time, so that
+    /// the events of a shard can be dropped before the next one is produced.
+    fn prove_shards_streaming<I, E>(
+        machine: &RiscvStark<SC>,
+        pk: &ProvingKey<SC>,
+        records: I,
+        challenger: &mut SC::Challenger,
+        options: &ProverOptions,
+    ) -> Result<Proof<SC>, ProverError>
+    where
+        I: IntoIterator<Item = Result<ExecutionRecord, E>>,
+        ProverError: From<E>;
 }
 
 impl<SC> Prover<SC> for LocalProver<SC>
@@ -59,23 +123,31 @@ where
         pk: &ProvingKey<SC>,
         shards: Vec<ExecutionRecord>,
         challenger: &mut SC::Challenger,
-    ) -> Proof<SC> {
+        options: &ProverOptions,
+    ) -> Result<Proof<SC>, ProverError> {
         tracing::info!("Generating and commiting traces for each shard.");
         // Generate and commit the traces for each segment.
-        let (shard_commits, shard_data) = Self::commit_shards(machine, &shards);
+        let (shard_commits, shard_data) = Self::commit_shards(machine, &shards, options)?;
 
-        // Observe the challenges for each segment.
+        // Observe the challenges and public values for each segment.
         tracing::info_span!("observing all challenges").in_scope(|| {
-            shard_commits.into_iter().for_each(|commitment| {
-                challenger.observe(commitment);
-            });
+            shard_commits
+                .into_iter()
+                .zip(shards.iter())
+                .for_each(|(commitment, shard)| {
+                    challenger.observe(commitment);
+                    challenger.observe_slice(&shard.public_values.to_vec::<SC::Val>());
+                });
         });
 
         // Generate a proof for each segment. Note that we clone the challenger so we can observe
         // identical global challenges across the segments.
         let chunk_size = std::cmp::max(shards.len() / num_cpus::get(), 1);
         let config = machine.config();
-        let reconstruct_commitments = env::reconstruct_commitments();
+        let reconstruct_commitments = options.reconstruct_commitments;
+        let num_shards = shards.len();
+        let proven = AtomicUsize::new(0);
+        let start = Instant::now();
         let shard_data_chunks = chunk_vec(shard_data, chunk_size);
         let shard_chunks = chunk_vec(shards, chunk_size);
         let shard_proofs = shard_data_chunks
@@ -87,23 +159,91 @@ where
                     .into_iter()
                     .zip(shards)
                     .enumerate()
-                    .map(|(j, (data, shard))| {
+                    .map(|(j, (data, shard))| -> Result<_, ProverError> {
+                        options.check_cancelled()?;
                         let idx = i * chunk_size + j;
                         let data = if reconstruct_commitments {
                             Self::commit_main(config, machine, &shard, idx)
                         } else {
-                            data.materialize()
-                                .expect("failed to materialize shard main data")
+                            data.materialize().map_err(io_error)?
                         };
                         let chips = machine.shard_chips(&shard).collect::<Vec<_>>();
-                        Self::prove_shard(config, pk, &chips, data, &mut challenger.clone())
+                        let proof =
+                            Self::prove_shard(config, pk, &chips, data, &mut challenger.clone());
+                        let proven = proven.fetch_add(1, Ordering::Relaxed) + 1;
+                        options.observe(ProverEvent::shard_proven(idx, proven, num_shards, start));
+                        Ok(proof)
                     })
-                    .collect::<Vec<_>>()
+                    .collect::<Result<Vec<_>, ProverError>>()
             })
+            .collect::<Result<Vec<_>, _>>()?
+            .into_iter()
             .flatten()
             .collect::<Vec<_>>();
 
-        Proof { shard_proofs }
+        Ok(Proof { shard_proofs })
+    }
+
+    fn prove_shards_streaming<I, E>(
+        machine: &RiscvStark<SC>,
+        pk: &ProvingKey<SC>,
+        records: I,
+        challenger: &mut SC::Challenger,
+        options: &ProverOptions,
+    ) -> Result<Proof<SC>, ProverError>
+    where
+        I: IntoIterator<Item = Result<ExecutionRecord, E>>,
+        ProverError: From<E>,
+    {
+        let config = machine.config();
+
+        // Only the main data of each shard is kept, on disk, since the events of a record are
+        // dropped once its shards are committed.
+        let (shard_commits, shard_data, public_values) =
+            Self::commit_records(machine, records, options, |_, _, data| {
+                let file = tempfile::tempfile().map_err(io_error)?;
+                tracing::info_span!("saving trace to disk")
+                    .in_scope(|| data.save(file))
+                    .map_err(io_error)
+            })?;
+        let num_shards = shard_data.len();
+        let public_values = public_values
+            .iter()
+            .map(|public_values| public_values.to_vec::<SC::Val>())
+            .collect::<Vec<_>>();
+        tracing::info_span!("observing all challenges").in_scope(|| {
+            for (commitment, public_values) in shard_commits.into_iter().zip(&public_values) {
+                challenger.observe(commitment);
+                challenger.observe_slice(public_values);
+            }
+        });
+
+        // Prove the shards one at a time to keep a single shard's traces in memory.
+        let start = Instant::now();
+        let shard_proofs = shard_data
+            .into_iter()
+            .enumerate()
+            .map(|(index, data)| -> Result<_, ProverError> {
+                options.check_cancelled()?;
+                let mut data = data.materialize().map_err(io_error)?;
+                data.public_values = public_values[index].clone();
+                let chips = machine
+                    .chips()
+                    .iter()
+                    .filter(|chip| data.chip_ids.contains(&chip.name()))
+                    .collect::<Vec<_>>();
+                let proof = Self::prove_shard(config, pk, &chips, data, &mut challenger.clone());
+                options.observe(ProverEvent::shard_proven(
+                    index,
+                    index + 1,
+                    num_shards,
+                    start,
+                ));
+                Ok(proof)
+            })
+            .collect::<Result<Vec<_>, ProverError>>()?;
+
+        Ok(Proof { shard_proofs })
     }
 }
 
@@ -118,6 +258,61 @@ where
     PcsProverData<SC>: Send + Sync,
     ShardMainData<SC>: Serialize + DeserializeOwned,
 {
+    /// Shards the records produced by `records` and commits to each shard as soon as its record is
+    /// produced, so that the events of a record can be dropped before the next one is produced.
+    ///
+    /// `keep` receives the index, events and main data of each shard, and returns what is kept of
+    /// them to prove the shard. Returns the main commitment, kept value and public values of each
+    /// shard. The public values are only known once the program has finished, so every shard
+    /// exposes those of the last record, apart from whether the shard has halted.
+    pub(crate) fn commit_records<I, E, T>(
+        machine: &RiscvStark<SC>,
+        records: I,
+        options: &ProverOptions,
+        mut keep: impl FnMut(usize, ExecutionRecord, ShardMainData<SC>) -> Result<T, ProverError>,
+    ) -> Result<(Vec<Com<SC>>, Vec<T>, Vec<PublicValues<u32>>), ProverError>
+    where
+        I: IntoIterator<Item = Result<ExecutionRecord, E>>,
+        ProverError: From<E>,
+        SC::Val: PrimeField32,
+    {
+        let config = machine.config();
+        let sharding_config = ShardingConfig::new(options);
+
+        let mut main_commits = Vec::new();
+        let mut kept = Vec::new();
+        let mut halted = Vec::new();
+        let mut public_values = PublicValues::default();
+        for record in records {
+            options.check_cancelled()?;
+            let record = record?;
+            public_values = record.public_values;
+            for mut shard in machine.shard(record, &sharding_config) {
+                options.check_cancelled()?;
+                let index = kept.len();
+                shard.index = (index + 1) as u32;
+                let data = tracing::info_span!("shard commit main", shard = index)
+                    .in_scope(|| Self::commit_main(config, machine, &shard, index));
+                main_commits.push(data.main_commit.clone());
+                halted.push(shard.public_values.halted);
+                kept.push(keep(index, shard, data)?);
+                options.observe(ProverEvent::ShardCommitted { shard: index });
+            }
+        }
+        let num_shards = kept.len();
+        tracing::info!("num_shards={}", num_shards);
+        options.observe(ProverEvent::Sharded { num_shards });
+
+        let public_values = halted
+            .into_iter()
+            .map(|halted| PublicValues {
+                halted,
+                ..public_values
+            })
+            .collect();
+        Ok((main_commits, kept, public_values))
+    }
+
     pub fn commit_main(
         config: &SC,
         machine: &RiscvStark<SC>,
@@ -151,13 +346,14 @@ where
             main_data,
             chip_ids,
             index,
+            public_values: shard.public_values.to_vec(),
         }
     }
 
     /// Prove the program for the given shard and given a commitment to the main data.
     pub fn prove_shard(
         config: &SC,
-        _pk: &ProvingKey<SC>,
+        pk: &ProvingKey<SC>,
         chips: &[&RiscvChip<SC>],
         shard_data: ShardMainData<SC>,
         challenger: &mut SC::Challenger,
@@ -170,6 +366,12 @@ where
         // Get the traces.
         let traces = &shard_data.traces;
 
+        // Get the index of the preprocessed trace of each chip, if it has one.
+        let preprocessed_indices = chips
+            .iter()
+            .map(|chip| pk.chip_ordering.get(&chip.name()).copied())
+            .collect::<Vec<_>>();
+
         let log_degrees = traces
             .iter()
             .map(|trace| log2_strict_usize(trace.height()))
@@ -195,9 +397,14 @@ where
             chips
                 .par_iter()
                 .zip(traces.par_iter())
-                .map(|(chip, main_trace)| {
-                    let perm_trace =
-                        chip.generate_permutation_trace(&None, main_trace, &permutation_challenges);
+                .zip(preprocessed_indices.par_iter())
+                .map(|((chip, main_trace), preprocessed_index)| {
+                    let preprocessed_trace = preprocessed_index.map(|i| &pk.traces[i]);
+                    let perm_trace = chip.generate_permutation_trace(
+                        preprocessed_trace,
+                        main_trace,
+                        &permutation_challenges,
+                    );
                     let cumulative_sum = perm_trace
                         .row_slice(main_trace.height() - 1)
                         .last()
@@ -239,6 +446,14 @@ where
 
         // For each chip, compute the quotient polynomial.
         let log_stride_for_quotient = config.pcs().log_blowup() - log_quotient_degree;
+        let preprocessed_ldes = tracing::info_span!("get preprocessed ldes").in_scope(|| {
+            config
+                .pcs()
+                .get_ldes(&pk.data)
+                .into_iter()
+                .map(|lde| lde.vertically_strided(1 << log_stride_for_quotient, 0))
+                .collect::<Vec<_>>()
+        });
         let main_ldes = tracing::info_span!("get main ldes").in_scope(|| {
             config
                 .pcs()
@@ -267,10 +482,12 @@ where
                         chips[i],
                         cumulative_sums[i],
                         log_degrees[i],
+                        preprocessed_indices[i].map(|j| &preprocessed_ldes[j]),
                         &main_ldes[i],
                         &permutation_ldes[i],
                         &permutation_challenges,
                         alpha,
+                        &shard_data.public_values,
                     )
                 })
                 .collect::<Vec<_>>()
@@ -326,6 +543,17 @@ where
                     .collect::<Vec<_>>()
             });
 
+        let preprocessed_opening_points =
+            tracing::info_span!("compute preprocessed opening points").in_scope(|| {
+                pk.traces
+                    .iter()
+                    .map(|trace| {
+                        let g = SC::Val::two_adic_generator(log2_strict_usize(trace.height()));
+                        vec![zeta, zeta * g]
+                    })
+                    .collect::<Vec<_>>()
+            });
+
         let zeta_quot_pow = zeta.exp_power_of_2(log_quotient_degree);
         let quotient_opening_points = (0..num_quotient_chunks)
             .map(|_| vec![zeta_quot_pow])
@@ -334,6 +562,7 @@ where
         let (openings, opening_proof) = tracing::info_span!("open multi batches").in_scope(|| {
             config.pcs().open_multi_batches(
                 &[
+                    (&pk.data, &preprocessed_opening_points),
                     (&shard_data.main_data, &trace_opening_points),
                     (&permutation_data, &trace_opening_points),
                     (&quotient_data, &quotient_opening_points),
@@ -345,7 +574,15 @@ where
         #[cfg(feature = "perf")]
         {
             // Collect the opened values for each chip.
-            let [main_values, permutation_values, quotient_values] = openings.try_into().unwrap();
+            let [preprocessed_values, main_values, permutation_values, quotient_values] =
+                openings.try_into().unwrap();
+            let preprocessed_opened_values = preprocessed_values
+                .into_iter()
+                .map(|op| {
+                    let [local, next] = op.try_into().unwrap();
+                    AirOpenedValues { local, next }
+                })
+                .collect::<Vec<_>>();
             let main_opened_values = main_values
                 .into_iter()
                 .map(|op| {
@@ -366,6 +603,7 @@ where
                 .collect::<Vec<_>>();
 
             let opened_values = izip!(
+                preprocessed_indices.iter(),
                 main_opened_values,
                 permutation_opened_values,
                 quotient_opened_values,
@@ -373,16 +611,20 @@ where
                 log_degrees
             )
             .map(
-                |(main, permutation, quotient, cumulative_sum, log_degree)| ChipOpenedValues {
-                    preprocessed: AirOpenedValues {
-                        local: vec![],
-                        next: vec![],
-                    },
-                    main,
-                    permutation,
-                    quotient,
-                    cumulative_sum,
-                    log_degree,
+                |(preprocessed_index, main, permutation, quotient, cumulative_sum, log_degree)| {
+                    ChipOpenedValues {
+                        preprocessed: preprocessed_index
+                            .map(|j| preprocessed_opened_values[j].clone())
+                            .unwrap_or(AirOpenedValues {
+                                local: vec![],
+                                next: vec![],
+                            }),
+                        main,
+                        permutation,
+                        quotient,
+                        cumulative_sum,
+                        log_degree,
+                    }
                 },
             )
             .collect::<Vec<_>>();
@@ -399,6 +641,7 @@ where
                 },
                 opening_proof,
                 chip_ids: chips.iter().map(|chip| chip.name()).collect::<Vec<_>>(),
+                public_values: shard_data.public_values,
             }
         }
 
@@ -408,10 +651,11 @@ where
             for i in 0..chips.len() {
                 debug_constraints::<SC>(
                     &chips[i],
-                    None,
+                    preprocessed_indices[i].map(|j| &pk.traces[j]),
                     &traces[i],
                     &permutation_traces[i],
                     &permutation_challenges,
+                    &shard_data.public_values,
                 );
             }
         });
@@ -422,16 +666,21 @@ where
             traces: traces.to_vec(),
             permutation_traces,
             chip_ids: chips.iter().map(|chip| chip.name()).collect::<Vec<_>>(),
+            public_values: shard_data.public_values,
         };
     }
 
     fn commit_shards<F, EF>(
         machine: &RiscvStark<SC>,
         shards: &[ExecutionRecord],
-    ) -> (
-        Vec<<SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment>,
-        Vec<ShardMainDataWrapper<SC>>,
-    )
+        options: &ProverOptions,
+    ) -> Result<
+        (
+            Vec<<SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment>,
+            Vec<ShardMainDataWrapper<SC>>,
+        ),
+        ProverError,
+    >
     where
         F: PrimeField + TwoAdicField + PrimeField32,
         EF: ExtensionField<F>,
@@ -446,8 +695,8 @@ where
         tracing::info!("num_shards={}", num_shards);
         // Get the number of shards that is the threshold for saving shards to disk instead of
         // keeping all the shards in memory.
-        let save_disk_threshold = env::save_disk_threshold();
-        let reconstruct_commitments = env::reconstruct_commitments();
+        let save_disk_threshold = options.save_disk_threshold;
+        let reconstruct_commitments = options.reconstruct_commitments;
         let (commitments, shard_main_data): (Vec<_>, Vec<_>) =
             tracing::info_span!("commit main for all shards").in_scope(|| {
                 let chunk_size = std::cmp::max(shards.len() / num_cpus::get(), 1);
@@ -458,30 +707,30 @@ where
                         shard_batch
                             .iter()
                             .enumerate()
-                            .map(|(j, shard)| {
+                            .map(|(j, shard)| -> Result<_, ProverError> {
+                                options.check_cancelled()?;
                                 let index = i * chunk_size + j;
                                 let data = tracing::info_span!("shard commit main", shard = index)
                                     .in_scope(|| Self::commit_main(config, machine, shard, index));
                                 let commitment = data.main_commit.clone();
-                                let file = tempfile::tempfile().unwrap();
                                 let data = if reconstruct_commitments {
                                     ShardMainDataWrapper::Empty()
                                 } else if num_shards > save_disk_threshold {
-                                    tracing::info_span!("saving trace to disk").in_scope(|| {
-                                        data.save(file).expect("failed to save shard main data")
-                                    })
+                                    let file = tempfile::tempfile().map_err(io_error)?;
+                                    tracing::info_span!("saving trace to disk")
+                                        .in_scope(|| data.save(file))
+                                        .map_err(io_error)?
                                 } else {
                                     data.to_in_memory()
                                 };
-                                (commitment, data)
+                                options.observe(ProverEvent::ShardCommitted { shard: index });
+                                Ok((commitment, data))
                             })
-                            .collect::<Vec<_>>()
+                            .collect::<Result<Vec<_>, ProverError>>()
                     })
-                    .flatten()
-                    .collect::<Vec<_>>()
-                    .into_iter()
-                    .unzip()
-            });
+                    .collect::<Result<Vec<_>, ProverError>>()
+                    .map(|batches| batches.into_iter().flatten().unzip())
+            })?;
 
         #[cfg(not(feature = "perf"))]
         {
@@ -501,6 +750,6 @@ where
             }
         }
 
-        (commitments, shard_main_data)
+        Ok((commitments, shard_main_data))
     }
 }
@@ -16,20 +16,23 @@ use p3_maybe_rayon::prelude::*;
 use super::{zerofier_coset::ZerofierOnCoset, StarkGenericConfig};
 
 #[allow(clippy::too_many_arguments)]
-pub fn quotient_values<SC, A, MainLde, PermLde>(
+pub fn quotient_values<SC, A, PreprocessedLde, MainLde, PermLde>(
     config: &SC,
     chip: &Chip<SC::Val, A>,
     cumulative_sum: SC::Challenge,
     degree_bits: usize,
+    preprocessed_lde: Option<&PreprocessedLde>,
     main_lde: &MainLde,
     permutation_lde: &PermLde,
     perm_challenges: &[SC::Challenge],
     alpha: SC::Challenge,
+    public_values: &[SC::Val],
 ) -> Vec<SC::Challenge>
 where
     A: StarkAir<SC>,
     SC: StarkGenericConfig,
     SC::Val: TwoAdicField,
+    PreprocessedLde: MatrixGet<SC::Val> + Sync,
     MainLde: MatrixGet<SC::Val> + Sync,
     PermLde: MatrixGet<SC::Val> + Sync,
 {
@@ -67,6 +70,23 @@ where
             let is_first_row = *PackedVal::<SC>::from_slice(&lagrange_first_evals[i_range.clone()]);
             let is_last_row = *PackedVal::<SC>::from_slice(&lagrange_last_evals[i_range]);
 
+            let (preprocessed_local, preprocessed_next): (Vec<_>, Vec<_>) = preprocessed_lde
+                .map(|lde| {
+                    (0..lde.width())
+                        .map(|col| {
+                            (
+                                PackedVal::<SC>::from_fn(|offset| {
+                                    lde.get(wrap(i_local_start + offset), col)
+                                }),
+                                PackedVal::<SC>::from_fn(|offset| {
+                                    lde.get(wrap(i_next_start + offset), col)
+                                }),
+                            )
+                        })
+                        .unzip()
+                })
+                .unwrap_or_default();
+
             let local: Vec<_> = (0..main_lde.width())
                 .map(|col| {
                     PackedVal::<SC>::from_fn(|offset| {
@@ -111,8 +131,8 @@ where
             let accumulator = PackedChallenge::<SC>::zero();
             let mut folder = ProverConstraintFolder {
                 preprocessed: TwoRowMatrixView {
-                    local: &[],
-                    next: &[],
+                    local: &preprocessed_local,
+                    next: &preprocessed_next,
                 },
                 main: TwoRowMatrixView {
                     local: &local,
@@ -123,6 +143,7 @@ where
                     next: &perm_next,
                 },
                 perm_challenges,
+                public_values,
                 cumulative_sum,
                 is_first_row,
                 is_last_row,
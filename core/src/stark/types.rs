@@ -42,6 +42,7 @@ pub struct ShardMainData<SC: StarkGenericConfig> {
     pub main_data: PcsProverData<SC>,
     pub chip_ids: Vec<String>,
     pub index: usize,
+    pub public_values: Vec<Val<SC>>,
 }
 
 impl<SC: StarkGenericConfig> ShardMainData<SC> {
@@ -51,6 +52,7 @@ impl<SC: StarkGenericConfig> ShardMainData<SC> {
         main_data: PcsProverData<SC>,
         chip_ids: Vec<String>,
         index: usize,
+        public_values: Vec<Val<SC>>,
     ) -> Self {
         Self {
             traces,
@@ -58,6 +60,7 @@ impl<SC: StarkGenericConfig> ShardMainData<SC> {
             main_data,
             chip_ids,
             index,
+            public_values,
         }
     }
 
@@ -142,6 +145,7 @@ pub struct ShardProof<SC: StarkGenericConfig> {
     pub opened_values: ShardOpenedValues<Challenge<SC>>,
     pub opening_proof: OpeningProof<SC>,
     pub chip_ids: Vec<String>,
+    pub public_values: Vec<Val<SC>>,
 }
 
 #[cfg(not(feature = "perf"))]
@@ -151,10 +155,12 @@ pub struct ShardProof<SC: StarkGenericConfig> {
     pub traces: Vec<ValMat<SC>>,
     pub permutation_traces: Vec<ChallengeMat<SC>>,
     pub chip_ids: Vec<String>,
+    pub public_values: Vec<Val<SC>>,
 }
 
 impl<T: Serialize> ShardOpenedValues<T> {
     pub fn into_values(self) -> OpenedValues<T> {
+        let mut preprocessed_vals = vec![];
         let mut main_vals = vec![];
         let mut permutation_vals = vec![];
         let mut quotient_vals = vec![];
@@ -162,18 +168,28 @@ impl<T: Serialize> ShardOpenedValues<T> {
         let to_values = |values: AirOpenedValues<T>| vec![values.local, values.next];
         for chip_values in self.chips {
             let ChipOpenedValues {
+                preprocessed,
                 main,
                 permutation,
                 quotient,
                 ..
             } = chip_values;
 
+            // Only chips with a preprocessed trace have openings in the preprocessed batch.
+            if !preprocessed.local.is_empty() {
+                preprocessed_vals.push(to_values(preprocessed));
+            }
             main_vals.push(to_values(main));
             permutation_vals.push(to_values(permutation));
             quotient_vals.push(vec![quotient]);
         }
 
-        vec![main_vals, permutation_vals, quotient_vals]
+        vec![
+            preprocessed_vals,
+            main_vals,
+            permutation_vals,
+            quotient_vals,
+        ]
     }
 }
 
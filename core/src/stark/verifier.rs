@@ -19,6 +19,7 @@ use super::folder::VerifierConstraintFolder;
 use super::types::*;
 use super::RiscvChip;
 use super::StarkGenericConfig;
+use super::VerifyingKey;
 
 use core::fmt::Display;
 
@@ -29,19 +30,55 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
     #[cfg(feature = "perf")]
     pub fn verify_shard(
         config: &SC,
+        vk: &VerifyingKey<SC>,
         chips: &[&RiscvChip<SC>],
         challenger: &mut SC::Challenger,
         proof: &ShardProof<SC>,
     ) -> Result<(), VerificationError> {
         use crate::air::MachineAir;
+        use crate::air::PV_NUM_ELEMENTS;
 
         let ShardProof {
             commitment,
             opened_values,
             opening_proof,
+            public_values,
             ..
         } = proof;
 
+        // Check that the shard exposes the expected number of public values.
+        if public_values.len() != PV_NUM_ELEMENTS {
+            return Err(VerificationError::InvalidPublicValuesLength);
+        }
+
+        // Check that the preprocessed chips of the shard match the verifying key.
+        let preprocessed_chips = chips
+            .iter()
+            .zip(opened_values.chips.iter())
+            .filter(|(chip, _)| chip.preprocessed_width() > 0)
+            .collect::<Vec<_>>();
+        if preprocessed_chips.len() != vk.chip_information.len()
+            || preprocessed_chips
+                .iter()
+                .zip(vk.chip_information.iter())
+                .any(|((chip, val), (name, dims))| {
+                    chip.name() != *name
+                        || chip.preprocessed_width() != dims.width
+                        || 1 << val.log_degree != dims.height
+                })
+        {
+            return Err(VerificationError::PreprocessedShapeMismatch);
+        }
+
+        let preprocessed_dims = vk
+            .chip_information
+            .iter()
+            .map(|(_, dims)| Dimensions {
+                width: dims.width,
+                height: dims.height,
+            })
+            .collect::<Vec<_>>();
+
         let (main_dims, perm_dims, quot_dims): (Vec<_>, Vec<_>, Vec<_>) = chips
             .iter()
             .zip(opened_values.chips.iter())
@@ -63,7 +100,7 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
             })
             .multiunzip();
 
-        let dims = &[main_dims, perm_dims, quot_dims];
+        let dims = &[preprocessed_dims, main_dims, perm_dims, quot_dims];
 
         let g_subgroups = opened_values
             .chips
@@ -97,6 +134,15 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
             .map(|g| vec![zeta, zeta * *g])
             .collect::<Vec<_>>();
 
+        let preprocessed_opening_points = vk
+            .chip_information
+            .iter()
+            .map(|(_, dims)| {
+                let g = SC::Val::two_adic_generator(dims.height.trailing_zeros() as usize);
+                vec![zeta, zeta * g]
+            })
+            .collect::<Vec<_>>();
+
         let quotient_opening_points = chips
             .iter()
             .map(|chip| vec![zeta.exp_power_of_2(chip.log_quotient_degree())])
@@ -106,6 +152,7 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
             .pcs()
             .verify_multi_batches(
                 &[
+                    (vk.commit.clone(), &preprocessed_opening_points),
                     (main_commit.clone(), &trace_opening_points),
                     (permutation_commit.clone(), &trace_opening_points),
                     (quotient_commit.clone(), &quotient_opening_points),
@@ -128,6 +175,7 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
                 zeta,
                 alpha,
                 &permutation_challenges,
+                public_values,
             )
             .map_err(|_| VerificationError::OodEvaluationMismatch(chip.name()))?;
         }
@@ -138,6 +186,7 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
     #[cfg(not(feature = "perf"))]
     pub fn verify_shard(
         _config: &SC,
+        _vk: &VerifyingKey<SC>,
         _chips: &[&RiscvChip<SC>],
         _challenger: &mut SC::Challenger,
         _proof: &ShardProof<SC>,
@@ -153,6 +202,7 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
         zeta: SC::Challenge,
         alpha: SC::Challenge,
         permutation_challenges: &[SC::Challenge],
+        public_values: &[SC::Val],
     ) -> Result<(), OodEvaluationMismatch> {
         let z_h = zeta.exp_power_of_2(opening.log_degree) - SC::Challenge::one();
         let is_first_row = z_h / (zeta - SC::Val::one());
@@ -205,6 +255,7 @@ impl<SC: StarkGenericConfig> Verifier<SC> {
             main: opening.main.view(),
             perm: perm_opening.view(),
             perm_challenges: permutation_challenges,
+            public_values,
             cumulative_sum: opening.cumulative_sum,
             is_first_row,
             is_last_row,
@@ -233,6 +284,10 @@ pub enum VerificationError {
     ///
     /// `constraints(zeta)` did not match `quotient(zeta) Z_H(zeta)`.
     OodEvaluationMismatch(String),
+    /// The preprocessed chips of the shard do not match the verifying key.
+    PreprocessedShapeMismatch,
+    /// The shard does not expose the expected number of public values.
+    InvalidPublicValuesLength,
 }
 
 impl Display for VerificationError {
@@ -244,6 +299,12 @@ impl Display for VerificationError {
             VerificationError::OodEvaluationMismatch(chip) => {
                 write!(f, "Out-of-domain evaluation mismatch on chip {}", chip)
             }
+            VerificationError::PreprocessedShapeMismatch => {
+                write!(f, "Preprocessed shape mismatch")
+            }
+            VerificationError::InvalidPublicValuesLength => {
+                write!(f, "Invalid public values length")
+            }
         }
     }
 }
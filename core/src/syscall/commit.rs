@@ -0,0 +1,32 @@
+use crate::air::PV_NUM_COMMITTED_WORDS;
+use crate::runtime::{ExecutionError, Register, Syscall, SyscallContext};
+
+/// Commits a word of the public values digests.
+///
+/// The index of the word is passed in `a0` and its value in `a1`. Indices below
+/// `PV_DIGEST_NUM_WORDS` refer to the digest of the public outputs and the remaining ones to the
+/// digest of the public inputs. The CPU constrains the value against the public values of the
+/// shard.
+pub struct SyscallCommit;
+
+impl SyscallCommit {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Syscall for SyscallCommit {
+    fn execute(&self, ctx: &mut SyscallContext) -> Result<u32, ExecutionError> {
+        let word_idx = ctx.register_unsafe(Register::X10) as usize;
+        let word = ctx.register_unsafe(Register::X11);
+        if word_idx >= PV_NUM_COMMITTED_WORDS {
+            return Err(ctx.invalid_argument(format!("invalid committed word index: {}", word_idx)));
+        }
+        *ctx.record_mut()
+            .public_values
+            .committed_words_mut()
+            .nth(word_idx)
+            .unwrap() = word;
+        Ok(0)
+    }
+}
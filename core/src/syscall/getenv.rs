@@ -0,0 +1,35 @@
+use crate::runtime::{ExecutionError, Register, Syscall, SyscallContext};
+
+/// Looks up an environment variable set on the host with `Runtime::set_env`.
+///
+/// The name of the variable is the `a1` bytes at address `a0`. At most `a2` bytes of its value
+/// replace the host response, from which the program reads them through `FD_HOST_RESPONSE`, and
+/// the length of the whole value is returned in `a0`, or `u32::MAX` if the variable is not set.
+pub struct SyscallGetenv;
+
+impl SyscallGetenv {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Syscall for SyscallGetenv {
+    fn execute(&self, ctx: &mut SyscallContext) -> Result<u32, ExecutionError> {
+        let name_ptr = ctx.register_unsafe(Register::X10);
+        let name_len = ctx.register_unsafe(Register::X11);
+        let max_len = ctx.register_unsafe(Register::X12) as usize;
+        let name = ctx.bytes_unsafe(name_ptr, name_len)?;
+        let Ok(name) = String::from_utf8(name) else {
+            return Err(ctx.invalid_argument("the name of the variable is not valid UTF-8"));
+        };
+
+        let Some(value) = ctx.rt.state.env.get(&name) else {
+            return Ok(u32::MAX);
+        };
+        let value = value.as_bytes();
+        let len = value.len() as u32;
+        ctx.rt.state.host_response = value[..value.len().min(max_len)].to_vec();
+        ctx.rt.state.host_response_ptr = 0;
+        Ok(len)
+    }
+}
@@ -1,4 +1,4 @@
-use crate::runtime::{Register, Syscall, SyscallContext};
+use crate::runtime::{ExecutionError, Register, Runtime, Syscall, SyscallContext};
 
 pub struct SyscallHalt;
 
@@ -9,15 +9,61 @@ impl SyscallHalt {
 }
 
 impl Syscall for SyscallHalt {
-    fn execute(&self, ctx: &mut SyscallContext) -> u32 {
+    fn execute(&self, ctx: &mut SyscallContext) -> Result<u32, ExecutionError> {
         let exit_code = ctx.register_unsafe(Register::X10);
-        if ctx.rt.fail_on_panic && exit_code != 0 {
-            panic!(
-                "RISC-V runtime halted during program execution with non-zero exit code {}. This likely means your program panicked during execution.",
-                exit_code
-            );
+        ctx.record_mut().public_values.exit_code = exit_code;
+        ctx.record_mut().public_values.halted = 1;
+        if exit_code != 0 {
+            let pc = ctx.pc();
+            let error = ExecutionError::NonZeroExitCode {
+                pc,
+                clk: ctx.global_clk(),
+                exit_code,
+                function: halting_function(ctx.rt, pc),
+                message: ctx.rt.last_stderr.clone(),
+            };
+            if ctx.rt.fail_on_panic {
+                return Err(error);
+            }
+            ctx.rt.panic = Some(error);
         }
         ctx.set_next_pc(0);
-        ctx.register_unsafe(Register::X10)
+        Ok(exit_code)
     }
 }
+
+/// The name of the function which made the program halt at `pc`.
+///
+/// The halt syscall is always invoked by `syscall_halt`, and by the panic machinery of the standard
+/// library for a panic, so these functions are skipped on the call stack to find the function of
+/// the program which called them.
+fn halting_function(rt: &Runtime, pc: u32) -> Option<String> {
+    let program = &rt.program;
+    let names = program
+        .function_at(pc)
+        .into_iter()
+        .chain(rt.call_stack.functions())
+        .filter_map(|function| program.symbols.get(&function))
+        .collect::<Vec<_>>();
+    names
+        .iter()
+        .find(|name| !is_runtime_function(name))
+        .or(names.first())
+        .map(|name| name.to_string())
+}
+
+/// Whether `name` is a function of the zkVM or the standard library, which are on the stack of every
+/// halt and panic.
+fn is_runtime_function(name: &str) -> bool {
+    const CRATES: [&str; 4] = ["core::", "std::", "alloc::", "sp1_zkvm::"];
+    const FUNCTIONS: [&str; 6] = [
+        "syscall_halt",
+        "sys_panic",
+        "rust_begin_unwind",
+        "rust_panic",
+        "__rust_start_panic",
+        "__rust_abort",
+    ];
+    let path = name.trim_start_matches('<');
+    CRATES.iter().any(|prefix| path.starts_with(prefix)) || FUNCTIONS.contains(&name)
+}
@@ -0,0 +1,34 @@
+use crate::runtime::{ExecutionError, Register, Syscall, SyscallContext};
+
+/// Sends a request to a callback registered on the host with `Runtime::register_host_callback` or
+/// `ProverOptions::with_host_callback`.
+///
+/// The tag of the callback is passed in `a0` and the request is the `a2` bytes at address `a1`.
+/// The response of the callback replaces the previous host response, from which the program reads
+/// it through `FD_HOST_RESPONSE`, and its length is returned in `a0`. The response is not
+/// constrained, so the program must check it like a hint.
+pub struct SyscallHostCall;
+
+impl SyscallHostCall {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Syscall for SyscallHostCall {
+    fn execute(&self, ctx: &mut SyscallContext) -> Result<u32, ExecutionError> {
+        let tag = ctx.register_unsafe(Register::X10);
+        let request_ptr = ctx.register_unsafe(Register::X11);
+        let request_len = ctx.register_unsafe(Register::X12);
+        let request = ctx.bytes_unsafe(request_ptr, request_len)?;
+
+        let Some(callback) = ctx.rt.host_callbacks.get_mut(&tag) else {
+            return Err(ctx.invalid_argument(format!("no host callback with tag {}", tag)));
+        };
+        let response = callback(&request);
+        let len = response.len() as u32;
+        ctx.rt.state.host_response = response;
+        ctx.rt.state.host_response_ptr = 0;
+        Ok(len)
+    }
+}
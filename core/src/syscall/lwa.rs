@@ -1,6 +1,22 @@
-use std::process::exit;
+use crate::runtime::{ExecutionError, Register, Syscall, SyscallContext};
 
-use crate::runtime::{Register, Syscall, SyscallContext};
+/// The file descriptor of the public input stream.
+///
+/// Bytes read through this file descriptor are hashed by the program and committed as part of the
+/// public values, while all other file descriptors read from the private input stream.
+pub const FD_PUBLIC_INPUT: u32 = 5;
+
+/// The file descriptor of the hint stream.
+///
+/// The program writes hints to this file descriptor, typically inside an unconstrained block, and
+/// reads them back from it. Hints are kept apart from the input streams provided by the host.
+pub const FD_HINT: u32 = 4;
+
+/// The file descriptor of the response of the last `HOST_CALL` or `GETENV` syscall.
+///
+/// Each of these syscalls replaces the response, so that the bytes the program did not read of a response
+/// or of the hint stream never end up in the next response.
+pub const FD_HOST_RESPONSE: u32 = 6;
 
 pub struct SyscallLWA;
 
@@ -11,23 +27,17 @@ impl SyscallLWA {
 }
 
 impl Syscall for SyscallLWA {
-    fn execute(&self, ctx: &mut SyscallContext) -> u32 {
-        // TODO: in the future this will be used for private vs. public inputs.
+    fn execute(&self, ctx: &mut SyscallContext) -> Result<u32, ExecutionError> {
         let a0 = Register::X10;
         let a1 = Register::X11;
-        let _ = ctx.register_unsafe(a0);
+        let fd = ctx.register_unsafe(a0);
         let num_bytes = ctx.register_unsafe(a1) as usize;
-        let mut read_bytes = [0u8; 4];
-        for i in 0..num_bytes {
-            if ctx.rt.state.input_stream_ptr >= ctx.rt.state.input_stream.len() {
-                tracing::error!(
-                    "Not enough input words were passed in. Use --input to pass in more words."
-                );
-                exit(1);
-            }
-            read_bytes[i] = ctx.rt.state.input_stream[ctx.rt.state.input_stream_ptr];
-            ctx.rt.state.input_stream_ptr += 1;
+        if num_bytes > 4 {
+            return Err(ctx.invalid_argument(format!("cannot load {} bytes in a word", num_bytes)));
         }
-        u32::from_le_bytes(read_bytes)
+        let bytes = ctx.rt.read_input(fd, num_bytes)?;
+        let mut read_bytes = [0u8; 4];
+        read_bytes[..num_bytes].copy_from_slice(&bytes);
+        Ok(u32::from_le_bytes(read_bytes))
     }
 }
@@ -1,10 +1,18 @@
+mod commit;
+mod getenv;
 mod halt;
+mod host_call;
 mod lwa;
 pub mod precompiles;
+mod rng_seed;
 mod unconstrained;
 mod write;
 
+pub use commit::*;
+pub use getenv::*;
 pub use halt::*;
+pub use host_call::*;
 pub use lwa::*;
+pub use rng_seed::*;
 pub use unconstrained::*;
 pub use write::*;
@@ -10,6 +10,7 @@ use p3_field::PrimeField;
 use p3_matrix::dense::RowMajorMatrix;
 
 use crate::air::MachineAir;
+use crate::air::LOOKUP_CHIPS;
 
 use super::columns::Blake3CompressInnerCols;
 use super::{
@@ -22,6 +23,10 @@ impl<F: PrimeField> MachineAir<F> for Blake3CompressInnerChip {
         "Blake3CompressInner".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
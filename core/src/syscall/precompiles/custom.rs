@@ -0,0 +1,488 @@
+//! Precompiles defined outside of this crate.
+//!
+//! A downstream crate registers a precompile with `Precompiles::register`, giving the syscall number
+//! the guest invokes, the `Syscall` executed by the runtime and the chip proving its events. The
+//! syscall records its events with `ExecutionRecord::add_custom_event` and the chip reads them back
+//! with `ExecutionRecord::custom_events`, using the syscall number as the key.
+//!
+//! The precompiles are passed to the runtime through `ProverOptions::with_precompiles` and to the
+//! machine through `RiscvStark::with_precompiles`, so both the prover and the verifier must use the
+//! same precompiles.
+
+use std::any::{type_name, Any};
+use std::collections::HashMap;
+use std::fmt::{Debug, Display, Formatter};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use p3_air::{Air, AirBuilder, BaseAir, PairBuilder};
+use p3_field::{Field, PrimeField32};
+use p3_matrix::dense::RowMajorMatrix;
+use p3_matrix::MatrixRowSlices;
+use p3_uni_stark::SymbolicExpression;
+
+use crate::air::{AirInteraction, MachineAir, PublicValuesAirBuilder, SP1AirBuilder};
+use crate::lookup::{Interaction, InteractionBuilder};
+use crate::runtime::{ExecutionRecord, Program, Syscall, SyscallCode};
+
+/// The chip of a precompile defined outside of this crate.
+///
+/// The constraints and interactions of the chip are recorded once with an `InteractionBuilder`, so
+/// the chip must not depend on the public values. It is automatically implemented for any chip
+/// which can be evaluated by an `InteractionBuilder`.
+///
+/// A chip which emits events to other chips in `generate_dependencies`, such as ALU operations or
+/// byte lookups, must name them in `MachineAir::emits_events_to` (e.g. with `ALU_CHIPS` and
+/// `LOOKUP_CHIPS`), so that their dependencies are generated after its own.
+pub trait CustomAir<F: Field>: MachineAir<F> + Air<InteractionBuilder<F>> + Send + Sync {}
+
+impl<F: Field, T> CustomAir<F> for T where
+    T: MachineAir<F> + Air<InteractionBuilder<F>> + Send + Sync
+{
+}
+
+/// A precompile registered with `Precompiles::register`.
+struct RegisteredPrecompile {
+    /// The syscall number invoking the precompile.
+    syscall_id: u32,
+    /// Creates the syscall for a new runtime.
+    syscall: Box<dyn Fn() -> Rc<dyn Syscall> + Send + Sync>,
+    /// An `Arc<dyn CustomAir<F>>` for the field the chip was registered with.
+    chip: Box<dyn Any + Send + Sync>,
+}
+
+/// An error that occurred while registering a precompile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PrecompileError {
+    /// The syscall number is used by a syscall of the runtime.
+    Reserved { syscall_id: u32 },
+
+    /// A precompile has already been registered with the syscall number.
+    AlreadyRegistered { syscall_id: u32 },
+}
+
+impl Display for PrecompileError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrecompileError::Reserved { syscall_id } => {
+                write!(f, "syscall {} is reserved by the runtime", syscall_id)
+            }
+            PrecompileError::AlreadyRegistered { syscall_id } => {
+                write!(f, "syscall {} is already registered", syscall_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PrecompileError {}
+
+/// The precompiles defined outside of this crate which a runtime executes and a machine proves.
+#[derive(Clone, Default)]
+pub struct Precompiles {
+    precompiles: Vec<Arc<RegisteredPrecompile>>,
+}
+
+impl Precompiles {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a precompile invoked by the syscall number `syscall_id`.
+    ///
+    /// The runtime executes `syscall` whenever the guest invokes `syscall_id`, and `chip` proves the
+    /// events the syscall records under `syscall_id` when the machine is defined over the field `F`.
+    pub fn register<F, S, A>(
+        &mut self,
+        syscall_id: u32,
+        syscall: S,
+        chip: A,
+    ) -> Result<(), PrecompileError>
+    where
+        F: PrimeField32,
+        S: Syscall + Clone + Send + Sync + 'static,
+        A: CustomAir<F> + 'static,
+    {
+        if SyscallCode::from_u32(syscall_id).is_some() {
+            return Err(PrecompileError::Reserved { syscall_id });
+        }
+        if self.syscall_ids().any(|id| id == syscall_id) {
+            return Err(PrecompileError::AlreadyRegistered { syscall_id });
+        }
+        let chip: Arc<dyn CustomAir<F>> = Arc::new(chip);
+        self.precompiles.push(Arc::new(RegisteredPrecompile {
+            syscall_id,
+            syscall: Box::new(move || Rc::new(syscall.clone())),
+            chip: Box::new(chip),
+        }));
+        Ok(())
+    }
+
+    /// The syscall numbers of the registered precompiles, in registration order.
+    pub fn syscall_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.precompiles
+            .iter()
+            .map(|precompile| precompile.syscall_id)
+    }
+
+    /// The syscalls of the registered precompiles, keyed by their syscall number.
+    pub(crate) fn syscall_map(&self) -> HashMap<u32, Rc<dyn Syscall>> {
+        self.precompiles
+            .iter()
+            .map(|precompile| (precompile.syscall_id, (precompile.syscall)()))
+            .collect()
+    }
+
+    /// The chips of the registered precompiles over the field `F`.
+    ///
+    /// Panics if a precompile was registered with a chip over another field, since the machine
+    /// could not prove its events.
+    pub(crate) fn chips<F: PrimeField32>(&self) -> Vec<CustomChip<F>> {
+        self.precompiles
+            .iter()
+            .map(|precompile| {
+                let chip = precompile
+                    .chip
+                    .downcast_ref::<Arc<dyn CustomAir<F>>>()
+                    .unwrap_or_else(|| {
+                        panic!(
+                            "the chip of precompile {} is not defined over {}",
+                            precompile.syscall_id,
+                            type_name::<F>()
+                        )
+                    });
+                CustomChip::new(precompile.syscall_id, chip.clone())
+            })
+            .collect()
+    }
+}
+
+impl Debug for Precompiles {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.syscall_ids()).finish()
+    }
+}
+
+/// A column of a constraint, in the current or the next row.
+#[derive(Debug, Clone, Copy)]
+enum Column {
+    Main { is_next: bool, index: usize },
+    Preprocessed { is_next: bool, index: usize },
+}
+
+/// A constraint recorded from a `CustomAir`.
+///
+/// This mirrors `SymbolicExpression`, which cannot be shared between the threads of the prover.
+#[derive(Debug, Clone)]
+enum Constraint<F> {
+    Column(Column),
+    Constant(F),
+    IsFirstRow,
+    IsLastRow,
+    IsTransition,
+    Add(Box<Constraint<F>>, Box<Constraint<F>>),
+    Sub(Box<Constraint<F>>, Box<Constraint<F>>),
+    Neg(Box<Constraint<F>>),
+    Mul(Box<Constraint<F>>, Box<Constraint<F>>),
+}
+
+impl<F: Field> Constraint<F> {
+    fn from_symbolic(expression: &SymbolicExpression<F>, main_width: usize) -> Self {
+        let from = |expression: &SymbolicExpression<F>| {
+            Box::new(Self::from_symbolic(expression, main_width))
+        };
+        match expression {
+            SymbolicExpression::Variable(v) if v.column < main_width => {
+                Constraint::Column(Column::Main {
+                    is_next: v.is_next,
+                    index: v.column,
+                })
+            }
+            SymbolicExpression::Variable(v) => Constraint::Column(Column::Preprocessed {
+                is_next: v.is_next,
+                index: v.column - main_width,
+            }),
+            SymbolicExpression::Constant(c) => Constraint::Constant(*c),
+            SymbolicExpression::IsFirstRow => Constraint::IsFirstRow,
+            SymbolicExpression::IsLastRow => Constraint::IsLastRow,
+            SymbolicExpression::IsTransition => Constraint::IsTransition,
+            SymbolicExpression::Add { x, y, .. } => Constraint::Add(from(x), from(y)),
+            SymbolicExpression::Sub { x, y, .. } => Constraint::Sub(from(x), from(y)),
+            SymbolicExpression::Neg { x, .. } => Constraint::Neg(from(x)),
+            SymbolicExpression::Mul { x, y, .. } => Constraint::Mul(from(x), from(y)),
+        }
+    }
+
+    /// Evaluates the constraint on the local and next rows of the main and preprocessed traces.
+    fn eval<AB: AirBuilder<F = F>>(
+        &self,
+        builder: &AB,
+        main: [&[AB::Var]; 2],
+        preprocessed: [&[AB::Var]; 2],
+    ) -> AB::Expr {
+        let eval = |x: &Constraint<F>| x.eval(builder, main, preprocessed);
+        match self {
+            Constraint::Column(Column::Main { is_next, index }) => {
+                main[*is_next as usize][*index].into()
+            }
+            Constraint::Column(Column::Preprocessed { is_next, index }) => {
+                preprocessed[*is_next as usize][*index].into()
+            }
+            Constraint::Constant(c) => (*c).into(),
+            Constraint::IsFirstRow => builder.is_first_row(),
+            Constraint::IsLastRow => builder.is_last_row(),
+            Constraint::IsTransition => builder.is_transition(),
+            Constraint::Add(x, y) => eval(x) + eval(y),
+            Constraint::Sub(x, y) => eval(x) - eval(y),
+            Constraint::Neg(x) => -eval(x),
+            Constraint::Mul(x, y) => eval(x) * eval(y),
+        }
+    }
+}
+
+/// A chip of `RiscvAir` wrapping the chip of a registered precompile.
+///
+/// The constraints and interactions of the wrapped chip are recorded when the chip is created and
+/// replayed on every builder.
+pub struct CustomChip<F: Field> {
+    syscall_id: u32,
+    air: Arc<dyn CustomAir<F>>,
+    constraints: Vec<Constraint<F>>,
+    sends: Vec<Interaction<F>>,
+    receives: Vec<Interaction<F>>,
+}
+
+impl<F: Field> CustomChip<F> {
+    /// Records the constraints and interactions of `air`, which proves the events of `syscall_id`.
+    pub fn new(syscall_id: u32, air: Arc<dyn CustomAir<F>>) -> Self {
+        let main_width = air.width();
+        let mut builder = InteractionBuilder::new(air.preprocessed_width(), main_width);
+        air.eval(&mut builder);
+        let constraints = builder
+            .constraints()
+            .iter()
+            .map(|constraint| Constraint::from_symbolic(constraint, main_width))
+            .collect();
+        let (sends, receives) = builder.interactions();
+        Self {
+            syscall_id,
+            air,
+            constraints,
+            sends,
+            receives,
+        }
+    }
+
+    /// The syscall number of the precompile.
+    pub fn syscall_id(&self) -> u32 {
+        self.syscall_id
+    }
+
+    /// Returns `true` if the given `shard` includes events for this chip.
+    pub fn included(&self, shard: &ExecutionRecord) -> bool {
+        shard
+            .custom_events
+            .get(&self.syscall_id)
+            .is_some_and(|events| !events.is_empty())
+    }
+}
+
+impl<F: Field> BaseAir<F> for CustomChip<F> {
+    fn width(&self) -> usize {
+        self.air.width()
+    }
+}
+
+impl<F: Field> MachineAir<F> for CustomChip<F> {
+    fn name(&self) -> String {
+        self.air.name()
+    }
+
+    fn generate_trace(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        self.air.generate_trace(input, output)
+    }
+
+    fn generate_dependencies(&self, input: &ExecutionRecord, output: &mut ExecutionRecord) {
+        self.air.generate_dependencies(input, output)
+    }
+
+    fn emits_events_to(&self) -> Vec<String> {
+        self.air.emits_events_to()
+    }
+
+    fn preprocessed_width(&self) -> usize {
+        self.air.preprocessed_width()
+    }
+
+    fn generate_preprocessed_trace(&self, program: &Program) -> Option<RowMajorMatrix<F>> {
+        self.air.generate_preprocessed_trace(program)
+    }
+}
+
+impl<F, AB> Air<AB> for CustomChip<F>
+where
+    F: Field,
+    AB: SP1AirBuilder<F = F> + PairBuilder + PublicValuesAirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let main_local: &[AB::Var] = main.row_slice(0);
+        let main_next: &[AB::Var] = main.row_slice(1);
+        let preprocessed = builder.preprocessed();
+        let preprocessed_local: &[AB::Var] = preprocessed.row_slice(0);
+        let preprocessed_next: &[AB::Var] = preprocessed.row_slice(1);
+
+        for constraint in self.constraints.iter() {
+            let constraint = constraint.eval(
+                builder,
+                [main_local, main_next],
+                [preprocessed_local, preprocessed_next],
+            );
+            builder.assert_zero(constraint);
+        }
+        let message = |interaction: &Interaction<F>| {
+            AirInteraction::new(
+                interaction
+                    .values
+                    .iter()
+                    .map(|value| value.apply::<AB::Expr, AB::Var>(preprocessed_local, main_local))
+                    .collect(),
+                interaction
+                    .multiplicity
+                    .apply::<AB::Expr, AB::Var>(preprocessed_local, main_local),
+                interaction.kind,
+            )
+        };
+        for interaction in self.sends.iter() {
+            builder.send(message(interaction));
+        }
+        for interaction in self.receives.iter() {
+            builder.receive(message(interaction));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use p3_air::{Air, BaseAir};
+    use p3_baby_bear::BabyBear;
+    use p3_field::{Field, PrimeField32, PrimeField64};
+    use p3_matrix::dense::RowMajorMatrix;
+    use p3_matrix::MatrixRowSlices;
+
+    use super::{PrecompileError, Precompiles};
+    use crate::air::{MachineAir, SP1AirBuilder};
+    use crate::runtime::{
+        ExecutionError, ExecutionRecord, Instruction, Opcode, Program, Register, Runtime, Syscall,
+        SyscallCode, SyscallContext,
+    };
+    use crate::utils::{pad_to_power_of_two, run_test_with_options, ProverOptions};
+
+    const SQUARE: u32 = 200;
+
+    /// A precompile which squares a0 in the field.
+    #[derive(Clone)]
+    struct SquareChip;
+
+    impl Syscall for SquareChip {
+        fn execute(&self, ctx: &mut SyscallContext) -> Result<u32, ExecutionError> {
+            let modulus = BabyBear::ORDER_U64;
+            let x = ctx.register_unsafe(Register::X10) as u64 % modulus;
+            let x_squared = (x * x % modulus) as u32;
+            let x = x as u32;
+            ctx.record_mut().add_custom_event(SQUARE, &[x, x_squared]);
+            Ok(x_squared)
+        }
+    }
+
+    impl<F: PrimeField32> MachineAir<F> for SquareChip {
+        fn name(&self) -> String {
+            "Square".to_string()
+        }
+
+        fn generate_trace(
+            &self,
+            input: &ExecutionRecord,
+            _output: &mut ExecutionRecord,
+        ) -> RowMajorMatrix<F> {
+            let mut values = input
+                .custom_events::<[u32; 2]>(SQUARE)
+                .into_iter()
+                .flat_map(|[x, x_squared]| [x, x_squared, 1].map(F::from_canonical_u32))
+                .collect::<Vec<_>>();
+            pad_to_power_of_two::<3, F>(&mut values);
+            RowMajorMatrix::new(values, 3)
+        }
+    }
+
+    impl<F: Field> BaseAir<F> for SquareChip {
+        fn width(&self) -> usize {
+            3
+        }
+    }
+
+    impl<AB: SP1AirBuilder> Air<AB> for SquareChip {
+        fn eval(&self, builder: &mut AB) {
+            let main = builder.main();
+            let local = main.row_slice(0);
+            let (x, x_squared, is_real) = (local[0], local[1], local[2]);
+            builder.assert_bool(is_real);
+            builder.when(is_real).assert_eq(x * x, x_squared);
+        }
+    }
+
+    #[test]
+    fn test_custom_precompile_prove() {
+        let mut precompiles = Precompiles::new();
+        precompiles
+            .register::<BabyBear, _, _>(SQUARE, SquareChip, SquareChip)
+            .unwrap();
+        let options = ProverOptions::default().with_precompiles(precompiles);
+
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, SQUARE, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 7, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let program = Program::new(instructions, 0, 0);
+        let mut runtime = Runtime::with_options(program.clone(), &options);
+        runtime.run().unwrap();
+        assert_eq!(runtime.register(Register::X10), 49);
+        assert_eq!(
+            runtime.record.custom_events::<[u32; 2]>(SQUARE),
+            vec![[7, 49]]
+        );
+        run_test_with_options(runtime, &options).unwrap();
+
+        // Runtimes created without the precompiles do not support the syscall.
+        let mut runtime = Runtime::new(program);
+        assert!(matches!(
+            runtime.run(),
+            Err(ExecutionError::UnsupportedSyscall {
+                syscall_id: SQUARE,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_register_precompile_errors() {
+        let mut precompiles = Precompiles::new();
+        let halt = SyscallCode::HALT as u32;
+        assert_eq!(
+            precompiles.register::<BabyBear, _, _>(halt, SquareChip, SquareChip),
+            Err(PrecompileError::Reserved { syscall_id: halt })
+        );
+        precompiles
+            .register::<BabyBear, _, _>(SQUARE, SquareChip, SquareChip)
+            .unwrap();
+        assert_eq!(
+            precompiles.register::<BabyBear, _, _>(SQUARE, SquareChip, SquareChip),
+            Err(PrecompileError::AlreadyRegistered { syscall_id: SQUARE })
+        );
+        assert_eq!(precompiles.syscall_ids().collect::<Vec<_>>(), vec![SQUARE]);
+    }
+}
@@ -1,5 +1,6 @@
 use crate::air::MachineAir;
 use crate::air::SP1AirBuilder;
+use crate::air::LOOKUP_CHIPS;
 use crate::field::event::FieldEvent;
 use crate::memory::MemoryCols;
 use crate::memory::MemoryReadCols;
@@ -10,6 +11,7 @@ use crate::operations::field::field_op::FieldOpCols;
 use crate::operations::field::field_op::FieldOperation;
 use crate::operations::field::params::Limbs;
 use crate::operations::field::params::NUM_LIMBS;
+use crate::runtime::ExecutionError;
 use crate::runtime::ExecutionRecord;
 use crate::runtime::Syscall;
 use crate::syscall::precompiles::create_ec_add_event;
@@ -114,10 +116,10 @@ impl<E: EllipticCurve + EdwardsParameters> Syscall for EdAddAssignChip<E> {
         8
     }
 
-    fn execute(&self, rt: &mut SyscallContext) -> u32 {
-        let event = create_ec_add_event::<E>(rt);
+    fn execute(&self, rt: &mut SyscallContext) -> Result<u32, ExecutionError> {
+        let event = create_ec_add_event::<E>(rt)?;
         rt.record_mut().ed_add_events.push(event.clone());
-        event.p_ptr + 1
+        Ok(event.p_ptr + 1)
     }
 }
 
@@ -126,6 +128,10 @@ impl<F: PrimeField32, E: EllipticCurve + EdwardsParameters> MachineAir<F> for Ed
         "EdAddAssign".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     #[instrument(name = "generate Ed Add trace", skip_all)]
     fn generate_trace(
         &self,
@@ -1,6 +1,7 @@
 use crate::air::BaseAirBuilder;
 use crate::air::MachineAir;
 use crate::air::SP1AirBuilder;
+use crate::air::LOOKUP_CHIPS;
 use crate::air::WORD_SIZE;
 use crate::cpu::MemoryReadRecord;
 use crate::cpu::MemoryWriteRecord;
@@ -9,6 +10,7 @@ use crate::memory::MemoryWriteCols;
 use crate::operations::field::field_op::FieldOpCols;
 use crate::operations::field::field_op::FieldOperation;
 use crate::operations::field::field_sqrt::FieldSqrtCols;
+use crate::runtime::ExecutionError;
 use crate::runtime::ExecutionRecord;
 use crate::runtime::Syscall;
 use crate::syscall::precompiles::SyscallContext;
@@ -198,7 +200,7 @@ pub struct EdDecompressChip<E> {
 }
 
 impl<E: EdwardsParameters> Syscall for EdDecompressChip<E> {
-    fn execute(&self, rt: &mut SyscallContext) -> u32 {
+    fn execute(&self, rt: &mut SyscallContext) -> Result<u32, ExecutionError> {
         let a0 = crate::runtime::Register::X10;
 
         let start_clk = rt.clk;
@@ -206,7 +208,9 @@ impl<E: EdwardsParameters> Syscall for EdDecompressChip<E> {
         // TODO: this will have to be be constrained, but can do it later.
         let slice_ptr = rt.register_unsafe(a0);
         if slice_ptr % 4 != 0 {
-            panic!();
+            return Err(
+                rt.invalid_argument(format!("slice_ptr 0x{:x} is not word aligned", slice_ptr))
+            );
         }
 
         let (y_memory_records_vec, y_vec) = rt.mr_slice(
@@ -257,7 +261,7 @@ impl<E: EdwardsParameters> Syscall for EdDecompressChip<E> {
 
         rt.clk += 4;
 
-        slice_ptr
+        Ok(slice_ptr)
     }
 
     fn num_extra_cycles(&self) -> u32 {
@@ -278,6 +282,10 @@ impl<F: PrimeField32, E: EdwardsParameters> MachineAir<F> for EdDecompressChip<E
         "EdDecompress".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
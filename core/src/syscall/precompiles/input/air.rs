@@ -0,0 +1,66 @@
+use p3_air::{Air, AirBuilder, BaseAir};
+
+use super::{ReadInputChip, ReadInputCols, NUM_READ_INPUT_COLS};
+use crate::air::{BaseAirBuilder, SP1AirBuilder};
+use core::borrow::Borrow;
+use p3_field::AbstractField;
+use p3_matrix::MatrixRowSlices;
+
+impl<F> BaseAir<F> for ReadInputChip {
+    fn width(&self) -> usize {
+        NUM_READ_INPUT_COLS
+    }
+}
+
+impl<AB> Air<AB> for ReadInputChip
+where
+    AB: SP1AirBuilder,
+{
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local: &ReadInputCols<AB::Var> = main.row_slice(0).borrow();
+        let next: &ReadInputCols<AB::Var> = main.row_slice(1).borrow();
+
+        builder.assert_bool(local.is_real);
+        builder.assert_bool(local.is_last);
+        builder.when(local.is_last).assert_one(local.is_real);
+
+        // Until the last word of the buffer, the next row writes the next word at the same clock
+        // cycle.
+        builder
+            .when_transition()
+            .when(local.is_real)
+            .when_not(local.is_last)
+            .assert_one(next.is_real);
+        builder
+            .when_transition()
+            .when(local.is_real)
+            .when_not(local.is_last)
+            .assert_eq(local.shard, next.shard);
+        builder
+            .when_transition()
+            .when(local.is_real)
+            .when_not(local.is_last)
+            .assert_eq(local.clk, next.clk);
+        builder
+            .when_transition()
+            .when(local.is_real)
+            .when_not(local.is_last)
+            .assert_eq(local.addr + AB::F::from_canonical_u32(4), next.addr);
+
+        // The buffer must end within the trace.
+        builder
+            .when_last_row()
+            .when(local.is_real)
+            .assert_one(local.is_last);
+
+        // Write the word.
+        builder.constraint_memory_access(
+            local.shard,
+            local.clk,
+            local.addr,
+            &local.write,
+            local.is_real,
+        );
+    }
+}
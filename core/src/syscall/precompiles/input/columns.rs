@@ -0,0 +1,26 @@
+use std::mem::size_of;
+
+use sp1_derive::AlignedBorrow;
+
+use crate::memory::MemoryWriteCols;
+
+pub const NUM_READ_INPUT_COLS: usize = size_of::<ReadInputCols<u8>>();
+
+#[derive(AlignedBorrow, Default, Debug, Clone, Copy)]
+#[repr(C)]
+pub struct ReadInputCols<T> {
+    pub shard: T,
+    pub clk: T,
+
+    /// The address of the word written in this row.
+    pub addr: T,
+
+    /// The write of the word.
+    pub write: MemoryWriteCols<T>,
+
+    /// Whether this row writes the last word of the buffer.
+    pub is_last: T,
+
+    /// Selector.
+    pub is_real: T,
+}
@@ -0,0 +1,47 @@
+use crate::{
+    runtime::{ExecutionError, Register, Syscall},
+    syscall::precompiles::{input::ReadInputEvent, SyscallContext},
+};
+
+use super::{ReadInputChip, READ_INPUT_EVENT_WORDS};
+
+impl Syscall for ReadInputChip {
+    fn execute(&self, rt: &mut SyscallContext) -> Result<u32, ExecutionError> {
+        // The file descriptor is passed in `a0` and the buffer to write to in `a1` and `a2`.
+        let fd = rt.register_unsafe(Register::X10);
+        let ptr = rt.register_unsafe(Register::X11);
+        let nbytes = rt.register_unsafe(Register::X12);
+        if ptr % 4 != 0 {
+            return Err(rt.invalid_argument(format!("ptr 0x{:x} is not word aligned", ptr)));
+        }
+        let bytes = rt.rt.read_input(fd, nbytes as usize)?;
+
+        let mut writes = Vec::new();
+        for (i, chunk) in bytes.chunks(4).enumerate() {
+            let addr = ptr
+                .checked_add(i as u32 * 4)
+                .ok_or_else(|| rt.invalid_argument("buffer overflows the address space"))?;
+            rt.rt.validate_memory_access(addr, 4)?;
+
+            // The bytes of a partially written last word keep their previous value.
+            let mut word = rt.word_unsafe(addr).to_le_bytes();
+            word[..chunk.len()].copy_from_slice(chunk);
+            writes.push(rt.mw(addr, u32::from_le_bytes(word)));
+        }
+
+        // A large buffer is recorded as several events, so that the rows of each event, which must
+        // be in the same shard, are bounded.
+        let shard = rt.current_shard();
+        let clk = rt.clk;
+        for (i, writes) in writes.chunks(READ_INPUT_EVENT_WORDS).enumerate() {
+            rt.record_mut().read_input_events.push(ReadInputEvent {
+                shard,
+                clk,
+                ptr: ptr + (i * READ_INPUT_EVENT_WORDS * 4) as u32,
+                writes: writes.to_vec(),
+            });
+        }
+
+        Ok(nbytes)
+    }
+}
@@ -0,0 +1,121 @@
+//! This module contains the implementation of the `read_input` precompile, which writes bytes of
+//! the input streams directly into memory.
+//!
+//! Each row of the trace writes one word of the buffer, and the rows of an event write consecutive
+//! words at the same clock cycle. The written values are provided by the prover, like
+//! the words loaded with `LWA`.
+mod air;
+mod columns;
+mod execute;
+mod trace;
+
+pub use columns::*;
+
+use crate::cpu::MemoryWriteRecord;
+use serde::{Deserialize, Serialize};
+
+/// The maximum number of words written by a `ReadInputEvent`.
+///
+/// The words of an event are written by consecutive rows of the same shard, so a syscall which
+/// writes more words is recorded as several events.
+pub const READ_INPUT_EVENT_WORDS: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReadInputEvent {
+    pub shard: u32,
+    pub clk: u32,
+    pub ptr: u32,
+    pub writes: Vec<MemoryWriteRecord>,
+}
+
+#[derive(Default)]
+pub struct ReadInputChip;
+
+impl ReadInputChip {
+    pub fn new() -> Self {
+        Self {}
+    }
+}
+
+#[cfg(test)]
+pub mod read_input_tests {
+    use super::READ_INPUT_EVENT_WORDS;
+    use crate::{
+        runtime::{Instruction, Opcode, Program, Register, Runtime, ShardingConfig},
+        utils::run_test_core,
+    };
+
+    pub fn read_input_program() -> Program {
+        let ptr = 0x1000;
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 115, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 0, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, ptr, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, 10, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+            Instruction::new(Opcode::LW, 29, 0, ptr + 8, false, true),
+        ];
+        Program::new(instructions, 0, 0)
+    }
+
+    #[test]
+    fn test_read_input_run() {
+        let mut runtime = Runtime::new(read_input_program());
+        runtime.write_stdin_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        runtime.run().unwrap();
+        assert_eq!(runtime.word(0x1000), u32::from_le_bytes([1, 2, 3, 4]));
+        assert_eq!(runtime.word(0x1004), u32::from_le_bytes([5, 6, 7, 8]));
+        assert_eq!(runtime.register(Register::X29), 0x0a09);
+        assert_eq!(runtime.record.read_input_events[0].writes.len(), 3);
+    }
+
+    #[test]
+    fn test_read_input_large() {
+        let ptr = 0x1000;
+        let nbytes = (READ_INPUT_EVENT_WORDS * 4 * 5 / 2) as u32;
+        let instructions = vec![
+            Instruction::new(Opcode::ADD, 5, 0, 115, false, true),
+            Instruction::new(Opcode::ADD, 10, 0, 0, false, true),
+            Instruction::new(Opcode::ADD, 11, 0, ptr, false, true),
+            Instruction::new(Opcode::ADD, 12, 0, nbytes, false, true),
+            Instruction::new(Opcode::ECALL, 10, 5, 0, false, true),
+        ];
+        let mut runtime = Runtime::new(Program::new(instructions, 0, 0));
+        runtime.write_stdin_slice(&(0..nbytes).map(|i| i as u8).collect::<Vec<_>>());
+        runtime.run().unwrap();
+
+        // The words are split into events of a bounded number of words.
+        let events = &runtime.record.read_input_events;
+        let lens = events.iter().map(|e| e.writes.len()).collect::<Vec<_>>();
+        assert_eq!(
+            lens,
+            vec![
+                READ_INPUT_EVENT_WORDS,
+                READ_INPUT_EVENT_WORDS,
+                READ_INPUT_EVENT_WORDS / 2
+            ]
+        );
+        assert_eq!(events[1].ptr, ptr + READ_INPUT_EVENT_WORDS as u32 * 4);
+        assert!(events.iter().all(|e| e.clk == events[0].clk));
+
+        // Each shard holds at most `read_input_len` events.
+        let config = ShardingConfig {
+            read_input_len: 1,
+            ..Default::default()
+        };
+        let shards = runtime.record.clone().shard(&config);
+        assert_eq!(shards.len(), 3);
+        assert!(shards
+            .iter()
+            .all(|shard| shard.read_input_events.len() == 1));
+        run_test_core(runtime).unwrap();
+    }
+
+    #[test]
+    fn test_read_input_prove() {
+        let mut runtime = Runtime::new(read_input_program());
+        runtime.write_stdin_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        runtime.run().unwrap();
+        run_test_core(runtime).unwrap();
+    }
+}
@@ -0,0 +1,57 @@
+use std::borrow::BorrowMut;
+
+use p3_field::PrimeField;
+use p3_matrix::dense::RowMajorMatrix;
+
+use crate::{
+    air::{MachineAir, LOOKUP_CHIPS},
+    runtime::ExecutionRecord,
+    utils::pad_rows,
+};
+
+use super::{ReadInputChip, ReadInputCols, NUM_READ_INPUT_COLS};
+
+impl<F: PrimeField> MachineAir<F> for ReadInputChip {
+    fn name(&self) -> String {
+        "ReadInput".to_string()
+    }
+
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
+    fn generate_trace(
+        &self,
+        input: &ExecutionRecord,
+        output: &mut ExecutionRecord,
+    ) -> RowMajorMatrix<F> {
+        let mut rows = Vec::new();
+
+        let mut new_field_events = Vec::new();
+        for event in input.read_input_events.iter() {
+            for (i, write) in event.writes.iter().enumerate() {
+                let mut row = [F::zero(); NUM_READ_INPUT_COLS];
+                let cols: &mut ReadInputCols<F> = row.as_mut_slice().borrow_mut();
+
+                cols.shard = F::from_canonical_u32(event.shard);
+                cols.clk = F::from_canonical_u32(event.clk);
+                cols.addr = F::from_canonical_u32(event.ptr + i as u32 * 4);
+                cols.write.populate(*write, &mut new_field_events);
+                cols.is_last = F::from_bool(i == event.writes.len() - 1);
+                cols.is_real = F::one();
+
+                rows.push(row);
+            }
+        }
+
+        output.add_field_events(&new_field_events);
+
+        pad_rows(&mut rows, || [F::zero(); NUM_READ_INPUT_COLS]);
+
+        // Convert the trace to a row major matrix.
+        RowMajorMatrix::new(
+            rows.into_iter().flatten().collect::<Vec<_>>(),
+            NUM_READ_INPUT_COLS,
+        )
+    }
+}
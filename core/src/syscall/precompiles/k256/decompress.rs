@@ -2,6 +2,7 @@ use crate::air::BaseAirBuilder;
 use crate::air::MachineAir;
 use crate::air::SP1AirBuilder;
 use crate::air::Word;
+use crate::air::LOOKUP_CHIPS;
 use crate::cpu::MemoryReadRecord;
 use crate::cpu::MemoryWriteRecord;
 use crate::memory::MemoryReadCols;
@@ -9,6 +10,7 @@ use crate::memory::MemoryReadWriteCols;
 use crate::operations::field::field_op::FieldOpCols;
 use crate::operations::field::field_op::FieldOperation;
 use crate::operations::field::field_sqrt::FieldSqrtCols;
+use crate::runtime::ExecutionError;
 use crate::runtime::ExecutionRecord;
 use crate::runtime::Syscall;
 use crate::syscall::precompiles::SyscallContext;
@@ -76,7 +78,7 @@ impl Syscall for K256DecompressChip {
         4
     }
 
-    fn execute(&self, rt: &mut SyscallContext) -> u32 {
+    fn execute(&self, rt: &mut SyscallContext) -> Result<u32, ExecutionError> {
         let a0 = crate::runtime::Register::X10;
 
         let start_clk = rt.clk;
@@ -84,7 +86,9 @@ impl Syscall for K256DecompressChip {
         // TODO: this will have to be be constrained, but can do it later.
         let slice_ptr = rt.register_unsafe(a0);
         if slice_ptr % 4 != 0 {
-            panic!();
+            return Err(
+                rt.invalid_argument(format!("slice_ptr 0x{:x} is not word aligned", slice_ptr))
+            );
         }
 
         let (x_memory_records_vec, x_vec) = rt.mr_slice(
@@ -132,7 +136,7 @@ impl Syscall for K256DecompressChip {
 
         rt.clk += 4;
 
-        slice_ptr
+        Ok(slice_ptr)
     }
 }
 
@@ -291,6 +295,10 @@ impl<F: PrimeField32> MachineAir<F> for K256DecompressChip {
         "K256Decompress".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
@@ -1,5 +1,5 @@
 use crate::{
-    runtime::{Register, Syscall},
+    runtime::{ExecutionError, Register, Syscall},
     syscall::precompiles::{keccak256::KeccakPermuteEvent, SyscallContext},
 };
 
@@ -20,7 +20,7 @@ impl Syscall for KeccakPermuteChip {
         NUM_ROUNDS as u32 * 4
     }
 
-    fn execute(&self, rt: &mut SyscallContext) -> u32 {
+    fn execute(&self, rt: &mut SyscallContext) -> Result<u32, ExecutionError> {
         // Read `state_ptr` from register a0.
         let state_ptr = rt.register_unsafe(Register::X10);
 
@@ -110,6 +110,6 @@ impl Syscall for KeccakPermuteChip {
                 state_addr: state_ptr,
             });
 
-        state_ptr
+        Ok(state_ptr)
     }
 }
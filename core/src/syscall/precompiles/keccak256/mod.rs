@@ -66,7 +66,7 @@ pub mod permute_tests {
     pub fn test_keccak_permute_program_execute() {
         let program = keccak_permute_program();
         let mut runtime = Runtime::new(program);
-        runtime.run()
+        runtime.run().unwrap();
     }
 
     #[test]
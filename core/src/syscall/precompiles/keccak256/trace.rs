@@ -9,7 +9,9 @@ use p3_maybe_rayon::prelude::{ParallelIterator, ParallelSlice};
 use tracing::instrument;
 
 use crate::{
-    air::MachineAir, runtime::ExecutionRecord, syscall::precompiles::keccak256::STATE_SIZE,
+    air::{MachineAir, LOOKUP_CHIPS},
+    runtime::ExecutionRecord,
+    syscall::precompiles::keccak256::STATE_SIZE,
 };
 
 use super::{
@@ -22,6 +24,10 @@ impl<F: PrimeField32> MachineAir<F> for KeccakPermuteChip {
         "KeccakPermute".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     #[instrument(name = "generate KeccakPermute trace", skip_all)]
     fn generate_trace(
         &self,
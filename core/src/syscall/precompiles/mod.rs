@@ -1,5 +1,7 @@
 pub mod blake3;
+pub mod custom;
 pub mod edwards;
+pub mod input;
 pub mod k256;
 pub mod keccak256;
 pub mod sha256;
@@ -10,7 +12,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::air::SP1AirBuilder;
 use crate::operations::field::params::Limbs;
-use crate::runtime::SyscallContext;
+use crate::runtime::{ExecutionError, SyscallContext};
 use crate::utils::ec::field::FieldParameters;
 use crate::utils::ec::{AffinePoint, EllipticCurve};
 use crate::{cpu::MemoryReadRecord, cpu::MemoryWriteRecord};
@@ -29,7 +31,9 @@ pub struct ECAddEvent {
     pub q_memory_records: [MemoryReadRecord; 16],
 }
 
-pub fn create_ec_add_event<E: EllipticCurve>(rt: &mut SyscallContext) -> ECAddEvent {
+pub fn create_ec_add_event<E: EllipticCurve>(
+    rt: &mut SyscallContext,
+) -> Result<ECAddEvent, ExecutionError> {
     let a0 = crate::runtime::Register::X10;
     let a1 = crate::runtime::Register::X11;
 
@@ -38,12 +42,12 @@ pub fn create_ec_add_event<E: EllipticCurve>(rt: &mut SyscallContext) -> ECAddEv
     // TODO: these will have to be be constrained, but can do it later.
     let p_ptr = rt.register_unsafe(a0);
     if p_ptr % 4 != 0 {
-        panic!();
+        return Err(rt.invalid_argument(format!("p_ptr 0x{:x} is not word aligned", p_ptr)));
     }
 
     let (q_ptr_record, q_ptr) = rt.mr(a1 as u32);
     if q_ptr % 4 != 0 {
-        panic!();
+        return Err(rt.invalid_argument(format!("q_ptr 0x{:x} is not word aligned", q_ptr)));
     }
 
     let p: [u32; 16] = rt.slice_unsafe(p_ptr, 16).try_into().unwrap();
@@ -62,7 +66,7 @@ pub fn create_ec_add_event<E: EllipticCurve>(rt: &mut SyscallContext) -> ECAddEv
 
     rt.clk += 4;
 
-    ECAddEvent {
+    Ok(ECAddEvent {
         shard: rt.current_shard(),
         clk: start_clk,
         p_ptr,
@@ -72,7 +76,7 @@ pub fn create_ec_add_event<E: EllipticCurve>(rt: &mut SyscallContext) -> ECAddEv
         q_ptr_record,
         p_memory_records,
         q_memory_records,
-    }
+    })
 }
 
 /// Elliptic curve double event.
@@ -85,7 +89,9 @@ pub struct ECDoubleEvent {
     pub p_memory_records: [MemoryWriteRecord; 16],
 }
 
-pub fn create_ec_double_event<E: EllipticCurve>(rt: &mut SyscallContext) -> ECDoubleEvent {
+pub fn create_ec_double_event<E: EllipticCurve>(
+    rt: &mut SyscallContext,
+) -> Result<ECDoubleEvent, ExecutionError> {
     let a0 = crate::runtime::Register::X10;
 
     let start_clk = rt.clk;
@@ -93,7 +99,7 @@ pub fn create_ec_double_event<E: EllipticCurve>(rt: &mut SyscallContext) -> ECDo
     // TODO: these will have to be be constrained, but can do it later.
     let p_ptr = rt.register_unsafe(a0);
     if p_ptr % 4 != 0 {
-        panic!();
+        return Err(rt.invalid_argument(format!("p_ptr 0x{:x} is not word aligned", p_ptr)));
     }
 
     let p: [u32; 16] = rt.slice_unsafe(p_ptr, 16).try_into().unwrap();
@@ -109,13 +115,13 @@ pub fn create_ec_double_event<E: EllipticCurve>(rt: &mut SyscallContext) -> ECDo
 
     rt.clk += 4;
 
-    ECDoubleEvent {
+    Ok(ECDoubleEvent {
         shard: rt.current_shard(),
         clk: start_clk,
         p_ptr,
         p,
         p_memory_records,
-    }
+    })
 }
 
 pub fn limbs_from_biguint<AB, F: FieldParameters>(value: &BigUint) -> Limbs<AB::Expr>
@@ -1,5 +1,5 @@
 use crate::{
-    runtime::{Register, Syscall},
+    runtime::{ExecutionError, Register, Syscall},
     syscall::precompiles::{
         sha256::{ShaCompressEvent, SHA_COMPRESS_K},
         SyscallContext,
@@ -13,7 +13,7 @@ impl Syscall for ShaCompressChip {
         8 * 4 + 64 * 4 + 8 * 4
     }
 
-    fn execute(&self, rt: &mut SyscallContext) -> u32 {
+    fn execute(&self, rt: &mut SyscallContext) -> Result<u32, ExecutionError> {
         // Read `w_ptr` from register a0.
         let w_ptr = rt.register_unsafe(Register::X10);
 
@@ -96,6 +96,6 @@ impl Syscall for ShaCompressChip {
             h_write_records: h_write_records.try_into().unwrap(),
         });
 
-        w_ptr
+        Ok(w_ptr)
     }
 }
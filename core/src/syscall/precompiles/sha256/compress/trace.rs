@@ -4,7 +4,7 @@ use p3_field::PrimeField;
 use p3_matrix::dense::RowMajorMatrix;
 
 use crate::{
-    air::{MachineAir, Word},
+    air::{MachineAir, Word, LOOKUP_CHIPS},
     memory::MemoryCols,
     runtime::ExecutionRecord,
 };
@@ -19,6 +19,10 @@ impl<F: PrimeField> MachineAir<F> for ShaCompressChip {
         "ShaCompress".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
@@ -3,7 +3,10 @@ use std::borrow::BorrowMut;
 use p3_field::PrimeField;
 use p3_matrix::dense::RowMajorMatrix;
 
-use crate::{air::MachineAir, runtime::ExecutionRecord};
+use crate::{
+    air::{MachineAir, LOOKUP_CHIPS},
+    runtime::ExecutionRecord,
+};
 
 use super::{ShaExtendChip, ShaExtendCols, NUM_SHA_EXTEND_COLS};
 
@@ -12,6 +15,10 @@ impl<F: PrimeField> MachineAir<F> for ShaExtendChip {
         "ShaExtend".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
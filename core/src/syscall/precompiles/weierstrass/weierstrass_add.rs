@@ -1,11 +1,13 @@
 use crate::air::MachineAir;
 use crate::air::SP1AirBuilder;
+use crate::air::LOOKUP_CHIPS;
 use crate::memory::MemoryCols;
 use crate::memory::MemoryReadCols;
 use crate::memory::MemoryWriteCols;
 use crate::operations::field::field_op::FieldOpCols;
 use crate::operations::field::field_op::FieldOperation;
 use crate::operations::field::params::NUM_LIMBS;
+use crate::runtime::ExecutionError;
 use crate::runtime::ExecutionRecord;
 use crate::runtime::Register;
 use crate::runtime::Syscall;
@@ -66,10 +68,10 @@ pub struct WeierstrassAddAssignChip<E> {
 }
 
 impl<E: EllipticCurve> Syscall for WeierstrassAddAssignChip<E> {
-    fn execute(&self, rt: &mut SyscallContext) -> u32 {
-        let event = create_ec_add_event::<E>(rt);
+    fn execute(&self, rt: &mut SyscallContext) -> Result<u32, ExecutionError> {
+        let event = create_ec_add_event::<E>(rt)?;
         rt.record_mut().weierstrass_add_events.push(event.clone());
-        event.p_ptr + 1
+        Ok(event.p_ptr + 1)
     }
 
     fn num_extra_cycles(&self) -> u32 {
@@ -149,6 +151,10 @@ impl<F: PrimeField32, E: EllipticCurve + WeierstrassParameters> MachineAir<F>
         "WeierstrassAddAssign".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     fn generate_trace(
         &self,
         input: &ExecutionRecord,
@@ -1,10 +1,12 @@
 use crate::air::MachineAir;
 use crate::air::SP1AirBuilder;
+use crate::air::LOOKUP_CHIPS;
 use crate::memory::MemoryCols;
 use crate::memory::MemoryWriteCols;
 use crate::operations::field::field_op::FieldOpCols;
 use crate::operations::field::field_op::FieldOperation;
 use crate::operations::field::params::NUM_LIMBS;
+use crate::runtime::ExecutionError;
 use crate::runtime::ExecutionRecord;
 use crate::runtime::Syscall;
 use crate::syscall::precompiles::create_ec_double_event;
@@ -67,12 +69,12 @@ pub struct WeierstrassDoubleAssignChip<E> {
 }
 
 impl<E: EllipticCurve + WeierstrassParameters> Syscall for WeierstrassDoubleAssignChip<E> {
-    fn execute(&self, rt: &mut SyscallContext) -> u32 {
-        let event = create_ec_double_event::<E>(rt);
+    fn execute(&self, rt: &mut SyscallContext) -> Result<u32, ExecutionError> {
+        let event = create_ec_double_event::<E>(rt)?;
         rt.record_mut()
             .weierstrass_double_events
             .push(event.clone());
-        event.p_ptr + 1
+        Ok(event.p_ptr + 1)
     }
 
     fn num_extra_cycles(&self) -> u32 {
@@ -167,6 +169,10 @@ impl<F: PrimeField32, E: EllipticCurve + WeierstrassParameters> MachineAir<F>
         "WeierstrassDoubleAssign".to_string()
     }
 
+    fn emits_events_to(&self) -> Vec<String> {
+        LOOKUP_CHIPS.map(String::from).to_vec()
+    }
+
     #[instrument(name = "generate WeierstrassDoubleAssign trace", skip_all)]
     fn generate_trace(
         &self,
@@ -0,0 +1,26 @@
+use crate::runtime::{ExecutionError, Register, Syscall, SyscallContext};
+
+/// Returns a word of the seed of the program's random number generator, set on the host with
+/// `Runtime::set_rng_seed`.
+///
+/// The low word of the seed is returned for `a0 = 0` and the high word for `a0 = 1`. The seed is
+/// not constrained, so the program must not rely on it being random.
+pub struct SyscallRngSeed;
+
+impl SyscallRngSeed {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Syscall for SyscallRngSeed {
+    fn execute(&self, ctx: &mut SyscallContext) -> Result<u32, ExecutionError> {
+        let word_idx = ctx.register_unsafe(Register::X10);
+        let seed = ctx.rt.state.rng_seed;
+        match word_idx {
+            0 => Ok(seed as u32),
+            1 => Ok((seed >> 32) as u32),
+            _ => Err(ctx.invalid_argument(format!("invalid seed word index: {}", word_idx))),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use crate::runtime::{ForkState, Syscall, SyscallContext};
+use crate::runtime::{ExecutionError, ForkState, Syscall, SyscallContext};
 use hashbrown::HashMap;
 
 pub struct SyscallEnterUnconstrained;
@@ -10,20 +10,21 @@ impl SyscallEnterUnconstrained {
 }
 
 impl Syscall for SyscallEnterUnconstrained {
-    fn execute(&self, ctx: &mut SyscallContext) -> u32 {
+    fn execute(&self, ctx: &mut SyscallContext) -> Result<u32, ExecutionError> {
         if ctx.rt.unconstrained {
-            panic!("Unconstrained block is already active.");
+            return Err(ctx.invalid_argument("unconstrained block is already active"));
         }
         ctx.rt.unconstrained = true;
         ctx.rt.unconstrained_state = ForkState {
             global_clk: ctx.rt.state.global_clk,
             clk: ctx.rt.state.clk,
             pc: ctx.rt.state.pc,
+            touched_memory_words: ctx.rt.state.touched_memory_words,
             memory_diff: HashMap::default(),
             record: std::mem::take(&mut ctx.rt.record),
             op_record: std::mem::take(&mut ctx.rt.cpu_record),
         };
-        1
+        Ok(1)
     }
 }
 
@@ -36,12 +37,13 @@ impl SyscallExitUnconstrained {
 }
 
 impl Syscall for SyscallExitUnconstrained {
-    fn execute(&self, ctx: &mut SyscallContext) -> u32 {
+    fn execute(&self, ctx: &mut SyscallContext) -> Result<u32, ExecutionError> {
         // Reset the state of the runtime.
         if ctx.rt.unconstrained {
             ctx.rt.state.global_clk = ctx.rt.unconstrained_state.global_clk;
             ctx.rt.state.clk = ctx.rt.unconstrained_state.clk;
             ctx.rt.state.pc = ctx.rt.unconstrained_state.pc;
+            ctx.rt.state.touched_memory_words = ctx.rt.unconstrained_state.touched_memory_words;
             ctx.next_pc = ctx.rt.state.pc.wrapping_add(4);
             for (addr, value) in ctx.rt.unconstrained_state.memory_diff.drain() {
                 match value {
@@ -58,6 +60,6 @@ impl Syscall for SyscallExitUnconstrained {
             ctx.rt.unconstrained = false;
         }
         ctx.rt.unconstrained_state = ForkState::default();
-        0
+        Ok(0)
     }
 }
@@ -1,5 +1,7 @@
+use std::io::Write;
+
 use crate::{
-    runtime::{Register, Syscall, SyscallContext},
+    runtime::{ExecutionError, Register, Syscall, SyscallContext},
     utils::u32_to_comma_separated,
 };
 
@@ -12,22 +14,19 @@ impl SyscallWrite {
 }
 
 impl Syscall for SyscallWrite {
-    fn execute(&self, ctx: &mut SyscallContext) -> u32 {
-        let a0 = Register::X10;
-        let a1 = Register::X11;
-        let a2 = Register::X12;
-        let rt = &mut ctx.rt;
-        let fd = rt.register(a0);
+    fn execute(&self, ctx: &mut SyscallContext) -> Result<u32, ExecutionError> {
+        let fd = ctx.register_unsafe(Register::X10);
         if fd == 1 || fd == 2 || fd == 3 || fd == 4 {
-            let write_buf = rt.register(a1);
-            let nbytes = rt.register(a2);
+            let write_buf = ctx.register_unsafe(Register::X11);
+            let nbytes = ctx.register_unsafe(Register::X12);
             // Read nbytes from memory starting at write_buf.
-            let bytes = (0..nbytes)
-                .map(|i| rt.byte(write_buf + i))
-                .collect::<Vec<u8>>();
+            let bytes = ctx.bytes_unsafe(write_buf, nbytes)?;
             let slice = bytes.as_slice();
+            let rt = &mut ctx.rt;
             if fd == 1 {
-                let s = core::str::from_utf8(slice).unwrap();
+                // Text which is not valid UTF-8 is shown with replacement characters.
+                let s = String::from_utf8_lossy(slice);
+                let s = s.as_ref();
                 if s.contains("cycle-tracker-start:") {
                     let fn_name = s
                         .split("cycle-tracker-start:")
@@ -56,30 +55,49 @@ impl Syscall for SyscallWrite {
                         u32_to_comma_separated(rt.state.global_clk - start)
                     );
                 } else {
-                    let flush_s = update_io_buf(ctx, fd, s);
-                    if !flush_s.is_empty() {
-                        flush_s
-                            .into_iter()
-                            .for_each(|line| println!("[stdout] {}", line));
-                    }
+                    write_output(ctx, fd, s);
                 }
             } else if fd == 2 {
-                let s = core::str::from_utf8(slice).unwrap();
-                let flush_s = update_io_buf(ctx, fd, s);
-                if !flush_s.is_empty() {
-                    flush_s
-                        .into_iter()
-                        .for_each(|line| println!("[stderr] {}", line));
-                }
+                let s = String::from_utf8_lossy(slice);
+                write_output(ctx, fd, &s);
             } else if fd == 3 {
                 rt.state.output_stream.extend_from_slice(slice);
             } else if fd == 4 {
-                rt.state.input_stream.extend_from_slice(slice);
+                rt.state.hint_stream.extend_from_slice(slice);
             } else {
                 unreachable!()
             }
         }
-        0
+        Ok(0)
+    }
+}
+
+/// Records the text the program wrote to stdout or stderr and sends it to the writer set with
+/// `Runtime::redirect_stdout` or `Runtime::redirect_stderr`. Without a writer, the text is printed
+/// line by line.
+///
+/// If the writer fails, the error is logged and the rest of the output is only recorded, since the
+/// writer belongs to the host and does not affect the execution.
+fn write_output(ctx: &mut SyscallContext, fd: u32, s: &str) {
+    let rt = &mut ctx.rt;
+    let (name, output, sink) = if fd == 1 {
+        ("stdout", &mut rt.program_stdout, &mut rt.stdout_sink)
+    } else {
+        ("stderr", &mut rt.program_stderr, &mut rt.stderr_sink)
+    };
+    output.push_str(s);
+    if fd == 2 {
+        rt.last_stderr = Some(s.to_string());
+    }
+    if let Some(writer) = sink {
+        if let Err(err) = writer.write_all(s.as_bytes()) {
+            tracing::error!("failed to write the program's {}: {}", name, err);
+            *sink = Some(Box::new(std::io::sink()));
+        }
+        return;
+    }
+    for line in update_io_buf(ctx, fd, s) {
+        println!("[{}] {}", name, line);
     }
 }
 
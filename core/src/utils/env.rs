@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 /// Gets the number of rows which by default should be used for each chip to maximize padding.
 ///
 /// Some chips, such as FieldLTU, may use a constant multiple of this value to optimize performance.
@@ -25,3 +27,8 @@ pub fn reconstruct_commitments() -> bool {
         Err(_) => true,
     }
 }
+
+/// Gets the file to which the cycles of each function stack are written, if any.
+pub fn profile_file() -> Option<PathBuf> {
+    std::env::var_os("PROFILE_FILE").map(PathBuf::from)
+}
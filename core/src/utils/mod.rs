@@ -2,13 +2,17 @@ mod buffer;
 pub mod ec;
 pub mod env;
 mod logger;
+mod options;
 mod poseidon2_instance;
 mod programs;
+mod progress;
 mod prove;
 mod tracer;
 
 pub use buffer::*;
 pub use logger::*;
+pub use options::*;
+pub use progress::*;
 pub use prove::*;
 pub use tracer::*;
 
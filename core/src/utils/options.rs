@@ -0,0 +1,213 @@
+use std::collections::HashMap;
+use std::fmt::{Debug, Formatter};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::runtime::ExecutionLimits;
+use crate::stark::ProverError;
+use crate::syscall::precompiles::custom::Precompiles;
+use crate::utils::{env, CancellationToken, ProverEvent, ProverObserver};
+
+/// Options for executing and proving a program.
+///
+/// The defaults are read from the `SHARD_SIZE`, `SAVE_DISK_THRESHOLD`, `RECONSTRUCT_COMMITMENTS`
+/// and `PROFILE_FILE` environment variables, so that proofs with different options can run
+/// in the same process by passing them explicitly.
+#[derive(Clone)]
+pub struct ProverOptions {
+    /// The number of cycles in each shard, which must be a power of two.
+    pub shard_size: usize,
+
+    /// The number of shards above which the shard traces are saved to disk while proving.
+    pub save_disk_threshold: usize,
+
+    /// Whether to recompute the shard traces and commitments when proving each shard instead of
+    /// keeping them in memory or on disk.
+    pub reconstruct_commitments: bool,
+
+    /// The observer which receives the progress of the execution and the proof, if any.
+    pub observer: Option<Arc<dyn ProverObserver>>,
+
+    /// The token which aborts the execution and the proof with `ProverError::Cancelled` once
+    /// cancelled.
+    pub cancellation_token: CancellationToken,
+
+    /// The directory through which `DistributedProver` exchanges work units and shard proofs with
+    /// its workers.
+    pub work_dir: Option<PathBuf>,
+
+    /// How long a worker of `DistributedProver` may go without renewing its claim on a work unit
+    /// before the unit is given to another worker.
+    pub work_lease: Duration,
+
+    /// How long `DistributedProver` waits for its workers to prove all the shards, if bounded.
+    pub work_deadline: Option<Duration>,
+
+    /// The file to which a profile of the cycles of each function stack is written once the
+    /// program has finished, if any. See `ProfileFormat::from_path` for the format.
+    pub profile_file: Option<PathBuf>,
+
+    /// The precompiles defined outside of this crate which the program may invoke.
+    pub precompiles: Precompiles,
+
+    /// The limits on the cycles and memory the program may use, which are unbounded by default.
+    pub limits: ExecutionLimits,
+
+    /// Whether a program which halts with a non-zero exit code, e.g. because it panicked, can be
+    /// proven instead of failing with `ExecutionError::NonZeroExitCode`.
+    pub allow_nonzero_exit: bool,
+
+    /// The callbacks which the program can invoke with the `HOST_CALL` syscall, keyed by their tag.
+    pub host_callbacks: HashMap<u32, HostCallback>,
+}
+
+/// A callback which the program can invoke with the `HOST_CALL` syscall.
+pub type HostCallback = Arc<Mutex<dyn FnMut(&[u8]) -> Vec<u8> + Send>>;
+
+impl Default for ProverOptions {
+    fn default() -> Self {
+        Self {
+            shard_size: env::shard_size(),
+            save_disk_threshold: env::save_disk_threshold(),
+            reconstruct_commitments: env::reconstruct_commitments(),
+            observer: None,
+            cancellation_token: CancellationToken::new(),
+            work_dir: None,
+            work_lease: Duration::from_secs(60),
+            work_deadline: None,
+            profile_file: env::profile_file(),
+            precompiles: Precompiles::new(),
+            limits: ExecutionLimits::unbounded(),
+            allow_nonzero_exit: false,
+            host_callbacks: HashMap::new(),
+        }
+    }
+}
+
+impl Debug for ProverOptions {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ProverOptions")
+            .field("shard_size", &self.shard_size)
+            .field("save_disk_threshold", &self.save_disk_threshold)
+            .field("reconstruct_commitments", &self.reconstruct_commitments)
+            .field("observer", &self.observer.is_some())
+            .field("cancellation_token", &self.cancellation_token)
+            .field("work_dir", &self.work_dir)
+            .field("work_lease", &self.work_lease)
+            .field("work_deadline", &self.work_deadline)
+            .field("profile_file", &self.profile_file)
+            .field("precompiles", &self.precompiles)
+            .field("limits", &self.limits)
+            .field("allow_nonzero_exit", &self.allow_nonzero_exit)
+            .field("host_callbacks", &self.host_callbacks.keys())
+            .finish()
+    }
+}
+
+impl ProverOptions {
+    /// Sets the number of cycles in each shard, which must be a power of two.
+    pub fn with_shard_size(mut self, shard_size: usize) -> Self {
+        assert!(
+            shard_size.is_power_of_two(),
+            "shard size must be a power of two"
+        );
+        self.shard_size = shard_size;
+        self
+    }
+
+    /// Sets the number of shards above which the shard traces are saved to disk while proving.
+    pub fn with_save_disk_threshold(mut self, save_disk_threshold: usize) -> Self {
+        self.save_disk_threshold = save_disk_threshold;
+        self
+    }
+
+    /// Sets whether to recompute the shard traces and commitments when proving each shard.
+    pub fn with_reconstruct_commitments(mut self, reconstruct_commitments: bool) -> Self {
+        self.reconstruct_commitments = reconstruct_commitments;
+        self
+    }
+
+    /// Sets the observer which receives the progress of the proof.
+    pub fn with_observer(mut self, observer: impl ProverObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
+    }
+
+    /// Sets the token which aborts the proof once cancelled.
+    pub fn with_cancellation_token(mut self, cancellation_token: CancellationToken) -> Self {
+        self.cancellation_token = cancellation_token;
+        self
+    }
+
+    /// Sets the directory through which `DistributedProver` exchanges work with its workers.
+    pub fn with_work_dir(mut self, work_dir: impl Into<PathBuf>) -> Self {
+        self.work_dir = Some(work_dir.into());
+        self
+    }
+
+    /// Sets how long a worker may go without renewing its claim on a work unit before the unit is
+    /// given to another worker. Workers renew their claims four times per lease.
+    pub fn with_work_lease(mut self, work_lease: Duration) -> Self {
+        self.work_lease = work_lease;
+        self
+    }
+
+    /// Sets how long `DistributedProver` waits for its workers before failing.
+    pub fn with_work_deadline(mut self, work_deadline: Duration) -> Self {
+        self.work_deadline = Some(work_deadline);
+        self
+    }
+
+    /// Sets the file to which a profile of the program's cycles is written.
+    pub fn with_profile_file(mut self, profile_file: impl Into<PathBuf>) -> Self {
+        self.profile_file = Some(profile_file.into());
+        self
+    }
+
+    /// Sets the precompiles defined outside of this crate which the program may invoke. The proof
+    /// must be verified by a machine created with the same precompiles.
+    pub fn with_precompiles(mut self, precompiles: Precompiles) -> Self {
+        self.precompiles = precompiles;
+        self
+    }
+
+    /// Sets the limits on the cycles and memory the program may use.
+    pub fn with_limits(mut self, limits: ExecutionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Sets whether a program which halts with a non-zero exit code can be proven. The exit code is
+    /// committed in the proof, and the error describing the halt is returned in `SP1Stdout::panic`.
+    pub fn with_allow_nonzero_exit(mut self, allow_nonzero_exit: bool) -> Self {
+        self.allow_nonzero_exit = allow_nonzero_exit;
+        self
+    }
+
+    /// Registers a callback which the program can invoke with the `HOST_CALL` syscall and `tag` on
+    /// every runtime created with these options. See `Runtime::register_host_callback`.
+    pub fn with_host_callback(
+        mut self,
+        tag: u32,
+        callback: impl FnMut(&[u8]) -> Vec<u8> + Send + 'static,
+    ) -> Self {
+        self.host_callbacks
+            .insert(tag, Arc::new(Mutex::new(callback)));
+        self
+    }
+
+    pub(crate) fn observe(&self, event: ProverEvent) {
+        if let Some(observer) = &self.observer {
+            observer.on_event(&event);
+        }
+    }
+
+    pub(crate) fn check_cancelled(&self) -> Result<(), ProverError> {
+        if self.cancellation_token.is_cancelled() {
+            Err(ProverError::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// An event emitted while proving, describing its progress.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProverEvent {
+    /// The program has been executed for `cycles` cycles so far.
+    ///
+    /// The runtime emits it after each shard it executes and once the program has finished.
+    Executed { cycles: u64 },
+
+    /// The execution was split into `num_shards` shards.
+    Sharded { num_shards: usize },
+
+    /// The traces of the shard with index `shard` were generated and committed.
+    ShardCommitted { shard: usize },
+
+    /// The shard with index `shard` was proven, as the `proven`-th of `num_shards` shards.
+    ///
+    /// `elapsed` is the time since the first shard started being proven, and `remaining` is an
+    /// estimate of the time until all shards are proven, assuming the others take as long on
+    /// average.
+    ShardProven {
+        shard: usize,
+        proven: usize,
+        num_shards: usize,
+        elapsed: Duration,
+        remaining: Duration,
+    },
+}
+
+impl ProverEvent {
+    pub(crate) fn shard_proven(
+        shard: usize,
+        proven: usize,
+        num_shards: usize,
+        start: Instant,
+    ) -> Self {
+        let elapsed = start.elapsed();
+        let remaining = elapsed.mul_f64((num_shards - proven) as f64 / proven as f64);
+        ProverEvent::ShardProven {
+            shard,
+            proven,
+            num_shards,
+            elapsed,
+            remaining,
+        }
+    }
+}
+
+/// Receives the progress of a proof, e.g. to show a progress bar.
+///
+/// The shards are committed and proven in parallel, so the events can be received from several
+/// threads and the shards complete out of order.
+pub trait ProverObserver: Send + Sync {
+    fn on_event(&self, event: &ProverEvent);
+}
+
+/// Sends the events on a channel. They are dropped if the receiver has hung up.
+impl ProverObserver for Sender<ProverEvent> {
+    fn on_event(&self, event: &ProverEvent) {
+        let _ = self.send(event.clone());
+    }
+}
+
+/// A token to abort a proof from another thread.
+///
+/// The runtime checks the token after executing each shard, and the prover before committing and
+/// proving each shard, so a shard which is already being proven is finished first.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels the proofs using this token, or any of its clones.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
@@ -1,9 +1,10 @@
 use std::time::Instant;
 
 use crate::utils::poseidon2_instance::RC_16_30;
+use crate::utils::ProverOptions;
 use crate::{
     runtime::{Program, Runtime},
-    stark::{LocalProver, OpeningProof, ShardMainData},
+    stark::{LocalProver, OpeningProof, Prover, ProverError, ShardMainData},
     stark::{RiscvStark, StarkGenericConfig},
 };
 pub use baby_bear_blake3::BabyBearBlake3;
@@ -20,6 +21,12 @@ pub trait StarkUtils: StarkGenericConfig {
         Pcs = Self::Pcs,
         Challenger = Self::Challenger,
     >;
+
+    /// A name identifying the config, which must stay the same across builds and platforms, e.g.
+    /// for the workers of `DistributedProver` to check that they prove with the coordinator's
+    /// config.
+    const NAME: &'static str;
+
     fn challenger(&self) -> Self::Challenger;
 
     fn uni_stark_config(&self) -> &Self::UniConfig;
@@ -27,40 +34,58 @@ pub trait StarkUtils: StarkGenericConfig {
 
 pub fn get_cycles(program: Program) -> u64 {
     let mut runtime = Runtime::new(program);
-    runtime.run();
+    runtime.run().unwrap();
     runtime.state.global_clk as u64
 }
 
 pub fn prove(program: Program) -> crate::stark::Proof<BabyBearBlake3> {
     let runtime = tracing::info_span!("runtime.run(...)").in_scope(|| {
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         runtime
     });
     let config = BabyBearBlake3::new();
-    prove_core(config, runtime)
+    prove_core(config, runtime, &ProverOptions::default()).unwrap()
 }
 
 #[cfg(test)]
 pub fn run_test(program: Program) -> Result<(), crate::stark::ProgramVerificationError> {
-    #[cfg(not(feature = "perf"))]
-    use crate::lookup::{debug_interactions_with_all_chips, InteractionKind};
-
     let runtime = tracing::info_span!("runtime.run(...)").in_scope(|| {
         let mut runtime = Runtime::new(program);
-        runtime.run();
+        runtime.run().unwrap();
         runtime
     });
+    run_test_core(runtime)
+}
+
+/// Prove and verify the execution of a runtime which has already been run.
+#[cfg(test)]
+pub fn run_test_core(runtime: Runtime) -> Result<(), crate::stark::ProgramVerificationError> {
+    run_test_with_options(runtime, &ProverOptions::default())
+}
+
+/// Prove and verify the execution of a runtime which has already been run, with the given options.
+#[cfg(test)]
+pub fn run_test_with_options(
+    runtime: Runtime,
+    options: &ProverOptions,
+) -> Result<(), crate::stark::ProgramVerificationError> {
+    #[cfg(not(feature = "perf"))]
+    use crate::lookup::{debug_interactions_with_all_chips, InteractionKind};
+
     let config = BabyBearBlake3::new();
 
-    let machine = RiscvStark::new(config);
+    let machine = RiscvStark::with_precompiles(config, &options.precompiles);
     let (pk, vk) = machine.setup(runtime.program.as_ref());
     let mut challenger = machine.config().challenger();
 
     let start = Instant::now();
     let record_clone = runtime.record.clone();
-    let proof = tracing::info_span!("runtime.prove(...)")
-        .in_scope(|| machine.prove::<LocalProver<_>>(&pk, record_clone, &mut challenger));
+    let proof = tracing::info_span!("runtime.prove(...)").in_scope(|| {
+        machine
+            .prove::<LocalProver<_>>(&pk, record_clone, &mut challenger, options)
+            .unwrap()
+    });
 
     #[cfg(not(feature = "perf"))]
     assert!(debug_interactions_with_all_chips::<BabyBearBlake3>(
@@ -92,8 +117,29 @@ pub fn prove_elf(elf: &[u8]) -> crate::stark::Proof<BabyBearBlake3> {
 pub fn prove_core<SC: StarkGenericConfig + StarkUtils + Send + Sync + Serialize>(
     config: SC,
     runtime: Runtime,
-) -> crate::stark::Proof<SC>
+    options: &ProverOptions,
+) -> Result<crate::stark::Proof<SC>, ProverError>
+where
+    SC::Challenger: Clone,
+    OpeningProof<SC>: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::ProverData: Send + Sync,
+    ShardMainData<SC>: Serialize + DeserializeOwned,
+    <SC as StarkGenericConfig>::Val: PrimeField32,
+{
+    prove_core_with_prover::<SC, LocalProver<SC>>(config, runtime, options)
+}
+
+/// Proves the execution of a runtime which has already been run with the prover `P`, e.g.
+/// `DistributedProver` to have workers prove the shards.
+pub fn prove_core_with_prover<SC, P>(
+    config: SC,
+    runtime: Runtime,
+    options: &ProverOptions,
+) -> Result<crate::stark::Proof<SC>, ProverError>
 where
+    SC: StarkGenericConfig + StarkUtils + Send + Sync + Serialize,
+    P: Prover<SC>,
     SC::Challenger: Clone,
     OpeningProof<SC>: Send + Sync,
     <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment: Send + Sync,
@@ -105,13 +151,13 @@ where
 
     let start = Instant::now();
 
-    let machine = RiscvStark::new(config);
+    let machine = RiscvStark::with_precompiles(config, &options.precompiles);
     let (pk, _) = machine.setup(runtime.program.as_ref());
 
     // Prove the program.
     let cycles = runtime.state.global_clk;
     let proof = tracing::info_span!("runtime.prove(...)")
-        .in_scope(|| machine.prove::<LocalProver<_>>(&pk, runtime.record, &mut challenger));
+        .in_scope(|| machine.prove::<P>(&pk, runtime.record, &mut challenger, options))?;
     let time = start.elapsed().as_millis();
     let nb_bytes = bincode::serialize(&proof).unwrap().len();
 
@@ -123,7 +169,47 @@ where
         Size::from_bytes(nb_bytes),
     );
 
-    proof
+    Ok(proof)
+}
+
+/// Executes the program of `runtime` and proves it one shard at a time, so that the peak memory
+/// does not grow with the number of cycles.
+pub fn prove_core_streaming<SC: StarkGenericConfig + StarkUtils + Send + Sync + Serialize>(
+    config: SC,
+    runtime: &mut Runtime,
+    options: &ProverOptions,
+) -> Result<crate::stark::Proof<SC>, ProverError>
+where
+    SC::Challenger: Clone,
+    OpeningProof<SC>: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::Commitment: Send + Sync,
+    <SC::Pcs as Pcs<SC::Val, RowMajorMatrix<SC::Val>>>::ProverData: Send + Sync,
+    ShardMainData<SC>: Serialize + DeserializeOwned,
+    <SC as StarkGenericConfig>::Val: PrimeField32,
+{
+    let mut challenger = config.challenger();
+
+    let start = Instant::now();
+
+    let machine = RiscvStark::with_precompiles(config, &options.precompiles);
+    let (pk, _) = machine.setup(runtime.program.as_ref());
+
+    // Execute and prove the program shard by shard.
+    let records = std::iter::from_fn(|| runtime.execute_shard().transpose());
+    let proof = tracing::info_span!("runtime.prove_streaming(...)").in_scope(|| {
+        machine.prove_streaming::<LocalProver<_>, _, _>(&pk, records, &mut challenger, options)
+    })?;
+    let cycles = runtime.state.global_clk;
+    let time = start.elapsed().as_millis();
+
+    tracing::info!(
+        "cycles={}, e2e={}, khz={:.2}",
+        cycles,
+        time,
+        (cycles as f64 / time as f64),
+    );
+
+    Ok(proof)
 }
 
 pub fn uni_stark_prove<SC, A>(
@@ -263,6 +349,8 @@ pub mod baby_bear_poseidon2 {
     impl StarkUtils for BabyBearPoseidon2 {
         type UniConfig = Self;
 
+        const NAME: &'static str = "baby-bear-poseidon2";
+
         fn challenger(&self) -> Self::Challenger {
             Challenger::new(self.perm.clone())
         }
@@ -387,6 +475,8 @@ pub(super) mod baby_bear_keccak {
     impl StarkUtils for BabyBearKeccak {
         type UniConfig = Self;
 
+        const NAME: &'static str = "baby-bear-keccak";
+
         fn challenger(&self) -> Self::Challenger {
             Challenger::from_hasher(vec![], ByteHash {})
         }
@@ -571,6 +661,8 @@ pub(super) mod baby_bear_blake3 {
     impl StarkUtils for BabyBearBlake3 {
         type UniConfig = Self;
 
+        const NAME: &'static str = "baby-bear-blake3";
+
         fn challenger(&self) -> Self::Challenger {
             cfg_if::cfg_if! {
                 if #[cfg(all(target_os = "zkvm", target_arch = "riscv32"))] {
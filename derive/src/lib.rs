@@ -141,6 +141,13 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
                 }
             });
 
+            let emits_events_to_arms = variants.iter().map(|(variant_name, field)| {
+                let field_ty = &field.ty;
+                quote! {
+                    #name::#variant_name(x) => <#field_ty as crate::air::MachineAir<F>>::emits_events_to(x)
+                }
+            });
+
             let machine_air = quote! {
                 impl #impl_generics crate::air::MachineAir<F> for #name #ty_generics #where_clause {
                     fn name(&self) -> String {
@@ -183,6 +190,12 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
                             #(#generate_dependencies_arms,)*
                         }
                     }
+
+                    fn emits_events_to(&self) -> Vec<String> {
+                        match self {
+                            #(#emits_events_to_arms,)*
+                        }
+                    }
                 }
             };
 
@@ -193,12 +206,16 @@ pub fn machine_air_derive(input: TokenStream) -> TokenStream {
                 }
             });
 
-            // Attach an extra generic AB : crate::air::SP1AirBuilder to the generics of the enum
+            // Attach an extra generic AB : crate::air::SP1AirBuilder + p3_air::PairBuilder +
+            // crate::air::PublicValuesAirBuilder to the generics of the enum, so that variants can
+            // access their preprocessed columns and the public values.
             let generics = &ast.generics;
             let mut new_generics = generics.clone();
-            new_generics
-                .params
-                .push(syn::parse_quote! { AB: crate::air::SP1AirBuilder<F = F> });
+            new_generics.params.push(syn::parse_quote! {
+                AB: crate::air::SP1AirBuilder<F = F>
+                    + p3_air::PairBuilder
+                    + crate::air::PublicValuesAirBuilder
+            });
 
             let (air_impl_generics, _, _) = new_generics.split_for_impl();
 
@@ -2,7 +2,9 @@ use clap::{command, Parser};
 use csv::WriterBuilder;
 use serde::Serialize;
 use sp1_core::runtime::{Program, Runtime};
-use sp1_core::utils::{get_cycles, prove_core, BabyBearBlake3, BabyBearKeccak, BabyBearPoseidon2};
+use sp1_core::utils::{
+    get_cycles, prove_core, BabyBearBlake3, BabyBearKeccak, BabyBearPoseidon2, ProverOptions,
+};
 use sp1_core::{SP1ProofWithIO, SP1Stdin, SP1Stdout, SP1Verifier};
 use std::fmt;
 use std::fs::OpenOptions;
@@ -132,12 +134,12 @@ fn run_evaluation(hashfn: &HashFnId, program: &Program, elf: &[u8]) -> (f64, f64
         HashFnId::Blake3 => {
             let mut runtime = Runtime::new(program.clone());
             let execution_start = Instant::now();
-            runtime.run();
+            runtime.run().unwrap();
             let execution_duration = execution_start.elapsed().as_secs_f64();
 
             let config = BabyBearBlake3::new();
             let prove_start = Instant::now();
-            let proof = prove_core(config.clone(), runtime);
+            let proof = prove_core(config.clone(), runtime, &ProverOptions::default()).unwrap();
             let prove_duration = prove_start.elapsed().as_secs_f64();
             let proof = SP1ProofWithIO {
                 stdin: SP1Stdin::new(),
@@ -154,12 +156,12 @@ fn run_evaluation(hashfn: &HashFnId, program: &Program, elf: &[u8]) -> (f64, f64
         HashFnId::Poseidon => {
             let mut runtime = Runtime::new(program.clone());
             let execution_start = Instant::now();
-            runtime.run();
+            runtime.run().unwrap();
             let execution_duration = execution_start.elapsed().as_secs_f64();
 
             let config = BabyBearPoseidon2::new();
             let prove_start = Instant::now();
-            let proof = prove_core(config.clone(), runtime);
+            let proof = prove_core(config.clone(), runtime, &ProverOptions::default()).unwrap();
             let prove_duration = prove_start.elapsed().as_secs_f64();
             let proof = SP1ProofWithIO {
                 stdin: SP1Stdin::new(),
@@ -176,12 +178,12 @@ fn run_evaluation(hashfn: &HashFnId, program: &Program, elf: &[u8]) -> (f64, f64
         HashFnId::Keccak256 => {
             let mut runtime = Runtime::new(program.clone());
             let execution_start = Instant::now();
-            runtime.run();
+            runtime.run().unwrap();
             let execution_duration = execution_start.elapsed().as_secs_f64();
 
             let config = BabyBearKeccak::new();
             let prove_start = Instant::now();
-            let proof = prove_core(config.clone(), runtime);
+            let proof = prove_core(config.clone(), runtime, &ProverOptions::default()).unwrap();
             let prove_duration = prove_start.elapsed().as_secs_f64();
             let proof = SP1ProofWithIO {
                 stdin: SP1Stdin::new(),
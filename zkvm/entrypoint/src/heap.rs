@@ -1,12 +1,30 @@
 use core::alloc::{GlobalAlloc, Layout};
+use core::ptr;
 
 use crate::syscalls::sys_alloc_aligned;
 
+/// The allocator installed by `entrypoint!`, which is `FreeListAlloc` with the
+/// `free-list-allocator` feature and `SimpleAlloc` otherwise.
+pub type HeapAlloc = SelectedAlloc;
+
+#[cfg(not(feature = "free-list-allocator"))]
+use SimpleAlloc as SelectedAlloc;
+
+#[cfg(feature = "free-list-allocator")]
+use FreeListAlloc as SelectedAlloc;
+
 /// A simple heap allocator.
 ///
 /// Allocates memory from left to right, without any deallocation.
+#[derive(Default)]
 pub struct SimpleAlloc;
 
+impl SimpleAlloc {
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
 unsafe impl GlobalAlloc for SimpleAlloc {
     unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
         sys_alloc_aligned(layout.size(), layout.align())
@@ -14,3 +32,245 @@ unsafe impl GlobalAlloc for SimpleAlloc {
 
     unsafe fn dealloc(&self, _: *mut u8, _: Layout) {}
 }
+
+/// A heap allocator which reuses freed memory.
+///
+/// Freed blocks are kept in a list sorted by address, in which adjacent blocks are merged.
+/// Allocations take the first free block that fits and only grow the heap with
+/// `sys_alloc_aligned` if there is none, so programs whose live set is small do not run out of
+/// memory however much they allocate in total. This costs more cycles per allocation than
+/// `SimpleAlloc`.
+#[derive(Default)]
+pub struct FreeListAlloc;
+
+impl FreeListAlloc {
+    pub const fn new() -> Self {
+        Self
+    }
+}
+
+/// A free block, stored at the start of the block itself.
+struct FreeBlock {
+    size: usize,
+    next: *mut FreeBlock,
+}
+
+/// The granularity of the blocks, which must fit a `FreeBlock` once freed.
+const BLOCK_ALIGN: usize = core::mem::size_of::<FreeBlock>();
+
+/// The free blocks of `FreeListAlloc`.
+static mut FREE_LIST: FreeList = FreeList::new();
+
+fn align_up(value: usize, align: usize) -> usize {
+    (value + align - 1) & !(align - 1)
+}
+
+/// The size and alignment of the block used for an allocation of `layout`.
+fn block_layout(layout: Layout) -> (usize, usize) {
+    (
+        align_up(layout.size().max(1), BLOCK_ALIGN),
+        layout.align().max(BLOCK_ALIGN),
+    )
+}
+
+/// A list of free blocks sorted by address.
+struct FreeList {
+    /// The free block with the lowest address.
+    head: *mut FreeBlock,
+}
+
+impl FreeList {
+    const fn new() -> Self {
+        Self {
+            head: ptr::null_mut(),
+        }
+    }
+
+    /// Adds the block of `size` bytes at `start` to the list, merging it with its neighbours.
+    ///
+    /// SAFETY: `start` and `size` must be multiples of `BLOCK_ALIGN`, and the block must be
+    /// writable and not overlap any block in the list.
+    unsafe fn free(&mut self, start: usize, size: usize) {
+        let mut prev: *mut FreeBlock = ptr::null_mut();
+        let mut next = self.head;
+        while !next.is_null() && (next as usize) < start {
+            prev = next;
+            next = (*next).next;
+        }
+
+        let block = start as *mut FreeBlock;
+        block.write(FreeBlock { size, next });
+        if !next.is_null() && start + size == next as usize {
+            (*block).size += (*next).size;
+            (*block).next = (*next).next;
+        }
+
+        if prev.is_null() {
+            self.head = block;
+        } else if prev as usize + (*prev).size == start {
+            (*prev).size += (*block).size;
+            (*prev).next = (*block).next;
+        } else {
+            (*prev).next = block;
+        }
+    }
+
+    /// Removes `size` bytes aligned to `align` from the first free block that fits, or returns
+    /// `None` if there is none.
+    ///
+    /// SAFETY: `size` and `align` must be multiples of `BLOCK_ALIGN`.
+    unsafe fn take(&mut self, size: usize, align: usize) -> Option<usize> {
+        let mut prev: *mut FreeBlock = ptr::null_mut();
+        let mut block = self.head;
+        while !block.is_null() {
+            let start = block as usize;
+            let end = start + (*block).size;
+            let aligned = align_up(start, align);
+            if aligned + size <= end {
+                let next = (*block).next;
+                if prev.is_null() {
+                    self.head = next;
+                } else {
+                    (*prev).next = next;
+                }
+                // Return the parts of the block before and after the allocation to the list.
+                if aligned > start {
+                    self.free(start, aligned - start);
+                }
+                if aligned + size < end {
+                    self.free(aligned + size, end - aligned - size);
+                }
+                return Some(aligned);
+            }
+            prev = block;
+            block = (*block).next;
+        }
+        None
+    }
+}
+
+unsafe impl GlobalAlloc for FreeListAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let (size, align) = block_layout(layout);
+        // SAFETY: Single threaded, so nothing else can touch the list while we're working.
+        match (*ptr::addr_of_mut!(FREE_LIST)).take(size, align) {
+            Some(ptr) => ptr as *mut u8,
+            None => sys_alloc_aligned(size, align),
+        }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let (size, _) = block_layout(layout);
+        (*ptr::addr_of_mut!(FREE_LIST)).free(ptr as usize, size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fake heap region to back the free list in host tests.
+    #[repr(align(64))]
+    struct Region([u8; 1024]);
+
+    /// Returns a free list containing the whole of `region`.
+    fn free_list(region: &mut Region) -> (FreeList, usize) {
+        let mut list = FreeList::new();
+        let start = region.0.as_mut_ptr() as usize;
+        unsafe { list.free(start, region.0.len()) };
+        (list, start)
+    }
+
+    fn free_blocks(list: &FreeList) -> Vec<(usize, usize)> {
+        let mut blocks = Vec::new();
+        let mut block = list.head;
+        while !block.is_null() {
+            unsafe {
+                blocks.push((block as usize, (*block).size));
+                block = (*block).next;
+            }
+        }
+        blocks
+    }
+
+    #[test]
+    fn test_take_and_free() {
+        let mut region = Box::new(Region([0; 1024]));
+        let (mut list, start) = free_list(&mut region);
+
+        unsafe {
+            let a = list.take(64, BLOCK_ALIGN).unwrap();
+            let b = list.take(128, BLOCK_ALIGN).unwrap();
+            let c = list.take(64, BLOCK_ALIGN).unwrap();
+            assert_eq!((a, b, c), (start, start + 64, start + 192));
+            assert_eq!(free_blocks(&list), vec![(start + 256, 768)]);
+
+            // Freed blocks are reused before the rest of the region.
+            list.free(b, 128);
+            assert_eq!(list.take(128, BLOCK_ALIGN), Some(b));
+
+            // Adjacent blocks are merged in whichever order they are freed.
+            list.free(b, 128);
+            list.free(a, 64);
+            assert_eq!(free_blocks(&list), vec![(start, 192), (start + 256, 768)]);
+            list.free(c, 64);
+            assert_eq!(free_blocks(&list), vec![(start, 1024)]);
+        }
+    }
+
+    #[test]
+    fn test_take_aligned() {
+        let mut region = Box::new(Region([0; 1024]));
+        let (mut list, start) = free_list(&mut region);
+
+        unsafe {
+            let a = list.take(BLOCK_ALIGN, BLOCK_ALIGN).unwrap();
+            let b = list.take(64, 64).unwrap();
+            assert_eq!(a, start);
+            assert_eq!(b, start + 64);
+
+            // The padding before the aligned block is kept in the list.
+            assert_eq!(
+                free_blocks(&list),
+                vec![(start + BLOCK_ALIGN, 64 - BLOCK_ALIGN), (start + 128, 896)]
+            );
+
+            list.free(a, BLOCK_ALIGN);
+            list.free(b, 64);
+            assert_eq!(free_blocks(&list), vec![(start, 1024)]);
+        }
+    }
+
+    #[test]
+    fn test_take_too_large() {
+        let mut region = Box::new(Region([0; 1024]));
+        let (mut list, start) = free_list(&mut region);
+
+        unsafe {
+            assert_eq!(list.take(2048, BLOCK_ALIGN), None);
+            let a = list.take(512, BLOCK_ALIGN).unwrap();
+            let _b = list.take(256, BLOCK_ALIGN).unwrap();
+            list.free(a, 512);
+
+            // The free space is split around the live block, so neither part fits.
+            assert_eq!(list.take(768, BLOCK_ALIGN), None);
+            assert_eq!(list.take(512, BLOCK_ALIGN), Some(start));
+        }
+    }
+
+    #[test]
+    fn test_block_layout() {
+        assert_eq!(
+            block_layout(Layout::from_size_align(0, 1).unwrap()),
+            (BLOCK_ALIGN, BLOCK_ALIGN)
+        );
+        assert_eq!(
+            block_layout(Layout::from_size_align(BLOCK_ALIGN + 1, 4).unwrap()),
+            (2 * BLOCK_ALIGN, BLOCK_ALIGN)
+        );
+        assert_eq!(
+            block_layout(Layout::from_size_align(8, 128).unwrap()),
+            (BLOCK_ALIGN, 128)
+        );
+    }
+}
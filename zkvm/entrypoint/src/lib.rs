@@ -14,10 +14,10 @@ macro_rules! entrypoint {
     ($path:path) => {
         const ZKVM_ENTRY: fn() = $path;
 
-        use $crate::heap::SimpleAlloc;
+        use $crate::heap::HeapAlloc;
 
         #[global_allocator]
-        static HEAP: SimpleAlloc = SimpleAlloc;
+        static HEAP: HeapAlloc = HeapAlloc::new();
 
         mod zkvm_generated_main {
 
@@ -34,7 +34,7 @@ mod libm;
 
 #[cfg(target_os = "zkvm")]
 mod zkvm {
-    use crate::syscalls::syscall_halt;
+    use crate::syscalls::{syscall_halt, syscall_rng_seed};
     use getrandom::{register_custom_getrandom, Error};
 
     #[cfg(not(feature = "interface"))]
@@ -71,19 +71,21 @@ mod zkvm {
         sym STACK_TOP
     );
 
-    static GETRANDOM_WARNING_ONCE: std::sync::Once = std::sync::Once::new();
+    /// The random number generator behind `getrandom`, seeded by the host on first use.
+    static mut RNG: Option<rand::rngs::StdRng> = None;
 
     fn zkvm_getrandom(s: &mut [u8]) -> Result<(), Error> {
-        use rand::Rng;
+        use rand::RngCore;
         use rand::SeedableRng;
 
-        GETRANDOM_WARNING_ONCE.call_once(|| {
-            println!("WARNING: Using insecure random number generator");
-        });
-        let mut rng = rand::rngs::StdRng::seed_from_u64(123);
-        for i in 0..s.len() {
-            s[i] = rng.gen();
-        }
+        // SAFETY: Single threaded, so nothing else can touch this while we're working.
+        let rng = unsafe {
+            RNG.get_or_insert_with(|| {
+                println!("WARNING: Using random number generator seeded by the prover");
+                rand::rngs::StdRng::seed_from_u64(syscall_rng_seed())
+            })
+        };
+        rng.fill_bytes(s);
         Ok(())
     }
 
@@ -1,14 +1,35 @@
 #[cfg(target_os = "zkvm")]
 use core::arch::asm;
 
+#[cfg(target_os = "zkvm")]
+use sha2::Digest;
+
 /// Halts the program.
 #[allow(unused_variables)]
 pub extern "C" fn syscall_halt(exit_code: u8) -> ! {
     #[cfg(target_os = "zkvm")]
     unsafe {
+        // Commit the digests of the public values and public inputs before halting.
+        let value_digest = crate::syscalls::PUBLIC_VALUES_HASHER
+            .take()
+            .unwrap_or_default()
+            .finalize();
+        let input_digest = crate::syscalls::PUBLIC_INPUT_HASHER
+            .take()
+            .unwrap_or_default()
+            .finalize();
+        for (i, word) in value_digest
+            .chunks_exact(4)
+            .chain(input_digest.chunks_exact(4))
+            .enumerate()
+        {
+            crate::syscalls::syscall_commit(i, u32::from_le_bytes(word.try_into().unwrap()));
+        }
+
+        // The exit code is committed as a public value, so the upper bits of `a0` must be cleared.
         asm!(
             "ecall",
-            in("a0") exit_code,
+            in("a0") exit_code as u32,
             in("t0") crate::syscalls::HALT
         );
         unreachable!()
@@ -1,15 +1,40 @@
 #[cfg(target_os = "zkvm")]
 use core::arch::asm;
 
+#[cfg(target_os = "zkvm")]
+use sha2::{Digest, Sha256};
+
+/// The file descriptor for the public values of the program.
+pub const FD_PUBLIC_VALUES: u32 = 3;
+
+/// The file descriptor for the hints of the program.
+pub const FD_HINT: u32 = 4;
+
+/// The file descriptor for the public inputs of the program.
+pub const FD_PUBLIC_INPUT: u32 = 5;
+
+/// The file descriptor for the response of the last host call or environment variable lookup.
+pub const FD_HOST_RESPONSE: u32 = 6;
+
+/// The running hash of the bytes written to `FD_PUBLIC_VALUES`, committed when the program halts.
+#[cfg(target_os = "zkvm")]
+pub(crate) static mut PUBLIC_VALUES_HASHER: Option<Sha256> = None;
+
+/// The running hash of the bytes read from `FD_PUBLIC_INPUT`, committed when the program halts.
+#[cfg(target_os = "zkvm")]
+pub(crate) static mut PUBLIC_INPUT_HASHER: Option<Sha256> = None;
+
 /// Reads data from the prover.
+///
+/// The bytes up to the first word-aligned address of the buffer are loaded with `LWA`, and the
+/// rest is written to memory by the host with a single `READ_INPUT` syscall.
 #[allow(unused_variables)]
 #[no_mangle]
 pub extern "C" fn syscall_read(fd: u32, read_buf: *mut u8, nbytes: usize) {
-    let whole_words: usize = nbytes / 4;
-    let remaining_bytes = nbytes % 4;
+    let head = ((read_buf as usize).wrapping_neg() % 4).min(nbytes);
 
-    for i in 0..whole_words {
-        let offset = i * 4;
+    // Handle the bytes before the first aligned word of the buffer.
+    if head > 0 {
         #[cfg(target_os = "zkvm")]
         unsafe {
             let mut word;
@@ -17,37 +42,41 @@ pub extern "C" fn syscall_read(fd: u32, read_buf: *mut u8, nbytes: usize) {
                 "ecall",
                 in("t0") crate::syscalls::LWA,
                 in("a0") fd,
-                in("a1") 4, // The number of bytes we're requesting
+                in("a1") head,
                 lateout("a0") word,
             );
 
-            // Copy the word into the read buffer
+            // Copy the necessary bytes of the word into the read buffer
             let word_ptr = &mut word as *mut u32 as *mut u8;
-            for j in 0..4 {
-                *read_buf.add(offset + j) = *word_ptr.add(j);
+            for j in 0..head {
+                *read_buf.add(j) = *word_ptr.add(j);
             }
         }
     }
 
-    // Handle the remaining bytes for the last partial word
-    if remaining_bytes > 0 {
-        let offset = whole_words * 4;
+    // Write the rest of the buffer directly from the input stream.
+    if nbytes > head {
         #[cfg(target_os = "zkvm")]
         unsafe {
-            let mut word;
             asm!(
                 "ecall",
-                in("t0") crate::syscalls::LWA,
+                in("t0") crate::syscalls::READ_INPUT,
                 in("a0") fd,
-                in("a1") remaining_bytes, // Request the remaining bytes
-                lateout("a0") word,
+                in("a1") read_buf.add(head),
+                in("a2") nbytes - head,
+                lateout("a0") _,
             );
+        }
+    }
 
-            // Copy the necessary bytes of the word into the read buffer
-            let word_ptr = &mut word as *mut u32 as *mut u8;
-            for j in 0..remaining_bytes {
-                *read_buf.add(offset + j) = *word_ptr.add(j);
-            }
+    // Public inputs are hashed so that their digest can be committed when the program halts.
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        if fd == FD_PUBLIC_INPUT {
+            let bytes = core::slice::from_raw_parts(read_buf, nbytes);
+            PUBLIC_INPUT_HASHER
+                .get_or_insert_with(Sha256::new)
+                .update(bytes);
         }
     }
 
@@ -61,6 +90,12 @@ pub extern "C" fn syscall_read(fd: u32, read_buf: *mut u8, nbytes: usize) {
 pub extern "C" fn syscall_write(fd: u32, write_buf: *const u8, nbytes: usize) {
     #[cfg(target_os = "zkvm")]
     unsafe {
+        if fd == FD_PUBLIC_VALUES {
+            let bytes = core::slice::from_raw_parts(write_buf, nbytes);
+            PUBLIC_VALUES_HASHER
+                .get_or_insert_with(Sha256::new)
+                .update(bytes);
+        }
         asm!(
             "ecall",
             in("t0") crate::syscalls::WRITE,
@@ -73,3 +108,95 @@ pub extern "C" fn syscall_write(fd: u32, write_buf: *const u8, nbytes: usize) {
     #[cfg(not(target_os = "zkvm"))]
     unreachable!()
 }
+
+/// Commits a word of the public values digests.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_commit(word_idx: usize, word: u32) {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        asm!(
+            "ecall",
+            in("t0") crate::syscalls::COMMIT,
+            in("a0") word_idx,
+            in("a1") word,
+        );
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
+/// Looks up the environment variable with the given name and returns the length of its value, or
+/// `usize::MAX` if it is not set. At most `nbytes` bytes of the value are read from
+/// `FD_HOST_RESPONSE` until the next host call.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_getenv(varname: *const u8, varname_len: usize, nbytes: usize) -> usize {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        let len;
+        asm!(
+            "ecall",
+            in("t0") crate::syscalls::GETENV,
+            in("a0") varname,
+            in("a1") varname_len,
+            in("a2") nbytes,
+            lateout("a0") len,
+        );
+        len
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
+/// Returns the seed of the random number generator, which is chosen by the host.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_rng_seed() -> u64 {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        let lo: u32;
+        let hi: u32;
+        asm!(
+            "ecall",
+            in("t0") crate::syscalls::RNG_SEED,
+            in("a0") 0,
+            lateout("a0") lo,
+        );
+        asm!(
+            "ecall",
+            in("t0") crate::syscalls::RNG_SEED,
+            in("a0") 1,
+            lateout("a0") hi,
+        );
+        (hi as u64) << 32 | lo as u64
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
+
+/// Sends a request to the host callback registered with `tag` and returns the length of its
+/// response, which is read from `FD_HOST_RESPONSE` until the next host call.
+#[allow(unused_variables)]
+#[no_mangle]
+pub extern "C" fn syscall_host_call(tag: u32, req_buf: *const u8, nbytes: usize) -> usize {
+    #[cfg(target_os = "zkvm")]
+    unsafe {
+        let len;
+        asm!(
+            "ecall",
+            in("t0") crate::syscalls::HOST_CALL,
+            in("a0") tag,
+            in("a1") req_buf,
+            in("a2") nbytes,
+            lateout("a0") len,
+        );
+        len
+    }
+
+    #[cfg(not(target_os = "zkvm"))]
+    unreachable!()
+}
@@ -12,6 +12,10 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use core::fmt::Write;
+
+use crate::syscalls::sys_panic;
+
 const SYSTEM_START: usize = 0x0C00_0000;
 
 #[allow(clippy::missing_safety_doc)]
@@ -29,8 +33,9 @@ pub unsafe extern "C" fn sys_alloc_aligned(bytes: usize, align: usize) -> *mut u
     // SAFETY: Single threaded, so nothing else can touch this while we're working.
     let mut heap_pos = unsafe { HEAP_POS };
 
+    let heap_start = unsafe { (&_end) as *const u8 as usize };
     if heap_pos == 0 {
-        heap_pos = unsafe { (&_end) as *const u8 as usize };
+        heap_pos = heap_start;
     }
 
     let offset = heap_pos & (align - 1);
@@ -39,13 +44,45 @@ pub unsafe extern "C" fn sys_alloc_aligned(bytes: usize, align: usize) -> *mut u
     }
 
     let ptr = heap_pos as *mut u8;
-    heap_pos += bytes;
 
     // Check to make sure heap doesn't collide with SYSTEM memory.
-    if SYSTEM_START < heap_pos {
-        panic!();
+    match heap_pos.checked_add(bytes) {
+        Some(end) if end <= SYSTEM_START => heap_pos = end,
+        _ => out_of_memory(bytes, heap_pos - heap_start, SYSTEM_START - heap_start),
     }
 
     unsafe { HEAP_POS = heap_pos };
     ptr
 }
+
+/// Halts the program with a message reporting the size of the heap.
+///
+/// `heap_size` is how far the heap has grown, which includes memory the allocator has freed but
+/// not returned, since memory is never released back to `sys_alloc_aligned`. The message is
+/// formatted on the stack, since the heap is exhausted.
+fn out_of_memory(bytes: usize, heap_size: usize, capacity: usize) -> ! {
+    struct StackBuf {
+        data: [u8; 160],
+        len: usize,
+    }
+
+    impl Write for StackBuf {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            let n = s.len().min(self.data.len() - self.len);
+            self.data[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+            self.len += n;
+            Ok(())
+        }
+    }
+
+    let mut buf = StackBuf {
+        data: [0; 160],
+        len: 0,
+    };
+    let _ = write!(
+        buf,
+        "out of memory: cannot allocate {} bytes, the heap has grown to {} of {} bytes",
+        bytes, heap_size, capacity
+    );
+    unsafe { sys_panic(buf.data.as_ptr(), buf.len) }
+}
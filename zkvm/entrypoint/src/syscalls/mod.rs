@@ -60,5 +60,20 @@ pub const EXIT_UNCONSTRAINED: u32 = 111;
 /// Executes `BLAKE3_COMPRESS_INNER`.
 pub const BLAKE3_COMPRESS_INNER: u32 = 112;
 
+/// Commits a word of the public values digests.
+pub const COMMIT: u32 = 113;
+
+/// Sends a request to a callback registered on the host.
+pub const HOST_CALL: u32 = 114;
+
+/// Writes bytes of an input stream to memory.
+pub const READ_INPUT: u32 = 115;
+
+/// Looks up an environment variable set on the host.
+pub const GETENV: u32 = 116;
+
+/// Returns a word of the seed of the random number generator.
+pub const RNG_SEED: u32 = 117;
+
 /// Writes to a file descriptor. Currently only used for `STDOUT/STDERR`.
 pub const WRITE: u32 = 999;
@@ -1,4 +1,9 @@
-use crate::syscalls::{syscall_halt, syscall_write};
+use crate::syscalls::{
+    sys_alloc_aligned, syscall_getenv, syscall_halt, syscall_read, syscall_write, FD_HOST_RESPONSE,
+};
+
+/// The number of bytes in a word.
+const WORD_SIZE: usize = 4;
 
 #[allow(clippy::missing_safety_doc)]
 #[no_mangle]
@@ -7,7 +12,8 @@ pub unsafe extern "C" fn sys_panic(msg_ptr: *const u8, len: usize) -> ! {
     syscall_halt(1);
 }
 
-#[allow(unused_variables)]
+/// Reads the value of an environment variable set on the host into `recv_buf`, which holds
+/// `words` words, and returns the length of the value, or `usize::MAX` if it is not set.
 #[no_mangle]
 pub fn sys_getenv(
     recv_buf: *mut u32,
@@ -15,13 +21,19 @@ pub fn sys_getenv(
     varname: *const u8,
     varname_len: usize,
 ) -> usize {
-    0
+    let len = syscall_getenv(varname, varname_len, words * WORD_SIZE);
+    if len != usize::MAX {
+        let nbytes = len.min(words * WORD_SIZE);
+        if nbytes > 0 {
+            syscall_read(FD_HOST_RESPONSE, recv_buf as *mut u8, nbytes);
+        }
+    }
+    len
 }
 
-#[allow(unused_variables)]
 #[no_mangle]
 pub fn sys_alloc_words(nwords: usize) -> *mut u32 {
-    core::ptr::null_mut()
+    unsafe { sys_alloc_aligned(nwords * WORD_SIZE, WORD_SIZE) as *mut u32 }
 }
 
 #[allow(unused_unsafe)]
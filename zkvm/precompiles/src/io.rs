@@ -1,5 +1,5 @@
 #![allow(unused_unsafe)]
-use crate::{syscall_read, syscall_write};
+use crate::{syscall_host_call, syscall_read, syscall_rng_seed, syscall_write};
 use bincode;
 use serde::de::DeserializeOwned;
 use serde::Serialize;
@@ -8,6 +8,8 @@ use std::io::Write;
 
 const FD_IO: u32 = 3;
 const FD_HINT: u32 = 4;
+const FD_PUBLIC_INPUT: u32 = 5;
+const FD_HOST_RESPONSE: u32 = 6;
 pub struct SyscallReader {
     fd: u32,
 }
@@ -52,6 +54,23 @@ pub fn read_slice(buf: &mut [u8]) {
     my_reader.read_exact(buf).unwrap();
 }
 
+/// Reads a value from the public inputs, whose digest is committed in the proof.
+pub fn read_public<T: DeserializeOwned>() -> T {
+    let my_reader = SyscallReader {
+        fd: FD_PUBLIC_INPUT,
+    };
+    let result = bincode::deserialize_from::<_, T>(my_reader);
+    result.unwrap()
+}
+
+/// Reads a slice of bytes from the public inputs, whose digest is committed in the proof.
+pub fn read_public_slice(buf: &mut [u8]) {
+    let mut my_reader = SyscallReader {
+        fd: FD_PUBLIC_INPUT,
+    };
+    my_reader.read_exact(buf).unwrap();
+}
+
 pub fn write<T: Serialize>(value: &T) {
     let writer = SyscallWriter { fd: FD_IO };
     bincode::serialize_into(writer, value).expect("serialization failed");
@@ -62,6 +81,19 @@ pub fn write_slice(buf: &[u8]) {
     my_reader.write_all(buf).unwrap();
 }
 
+/// Reads a value written with `hint`, typically inside an `unconstrained!` block.
+pub fn read_hint<T: DeserializeOwned>() -> T {
+    let my_reader = SyscallReader { fd: FD_HINT };
+    let result = bincode::deserialize_from::<_, T>(my_reader);
+    result.unwrap()
+}
+
+/// Reads a slice of bytes written with `hint_slice`, typically inside an `unconstrained!` block.
+pub fn read_hint_slice(buf: &mut [u8]) {
+    let mut my_reader = SyscallReader { fd: FD_HINT };
+    my_reader.read_exact(buf).unwrap();
+}
+
 pub fn hint<T: Serialize>(value: &T) {
     let writer = SyscallWriter { fd: FD_HINT };
     bincode::serialize_into(writer, value).expect("serialization failed");
@@ -71,3 +103,21 @@ pub fn hint_slice(buf: &[u8]) {
     let mut my_reader = SyscallWriter { fd: FD_HINT };
     my_reader.write_all(buf).unwrap();
 }
+
+/// Returns the seed chosen by the host for the random number generator behind `getrandom`.
+pub fn rng_seed() -> u64 {
+    unsafe { syscall_rng_seed() }
+}
+
+/// Sends `request` to the host callback registered with `tag` and returns its response.
+///
+/// The response comes from the host and is not constrained, so it must be checked like a hint.
+pub fn host_call(tag: u32, request: &[u8]) -> Vec<u8> {
+    let len = unsafe { syscall_host_call(tag, request.as_ptr(), request.len()) };
+    let mut response = vec![0u8; len];
+    let mut my_reader = SyscallReader {
+        fd: FD_HOST_RESPONSE,
+    };
+    my_reader.read_exact(&mut response).unwrap();
+    response
+}
@@ -5,7 +5,10 @@ pub mod unconstrained;
 extern "C" {
     pub fn syscall_halt(exit_code: u8) -> !;
     pub fn syscall_write(fd: u32, write_buf: *const u8, nbytes: usize);
+    pub fn syscall_commit(word_idx: usize, word: u32);
     pub fn syscall_read(fd: u32, read_buf: *mut u8, nbytes: usize);
+    pub fn syscall_host_call(tag: u32, req_buf: *const u8, nbytes: usize) -> usize;
+    pub fn syscall_rng_seed() -> u64;
     pub fn syscall_sha256_extend(w: *mut u32);
     pub fn syscall_sha256_compress(w: *mut u32, state: *mut u32);
     pub fn syscall_ed_add(p: *mut u32, q: *mut u32);